@@ -0,0 +1,296 @@
+use chrono::NaiveDate;
+
+use crate::types::{InvoiceStatus, ParsedInvoice};
+
+#[cfg(feature = "from_file")]
+use crate::reconcile::Reconciliation;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// How far past its due date an invoice is.
+pub enum AgingBucket {
+    NotDue,
+    Days0To30,
+    Days31To60,
+    Days61To90,
+    Over90,
+}
+
+impl AgingBucket {
+    pub const ALL: [AgingBucket; 5] = [
+        AgingBucket::NotDue, AgingBucket::Days0To30, AgingBucket::Days31To60, AgingBucket::Days61To90, AgingBucket::Over90,
+    ];
+
+    /// Bucket for an invoice that's `days` past due, negative when it isn't due yet
+    pub fn from_days(days: i64) -> AgingBucket {
+        match days {
+            ..0 => AgingBucket::NotDue,
+            0..=30 => AgingBucket::Days0To30,
+            31..=60 => AgingBucket::Days31To60,
+            61..=90 => AgingBucket::Days61To90,
+            _ => AgingBucket::Over90,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AgingBucket::NotDue => "Not due",
+            AgingBucket::Days0To30 => "0-30",
+            AgingBucket::Days31To60 => "31-60",
+            AgingBucket::Days61To90 => "61-90",
+            AgingBucket::Over90 => "90+",
+        }
+    }
+
+    fn index(&self) -> usize {
+        AgingBucket::ALL.iter().position(|b| b == self).unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Open invoice, with what's left to pay on it.
+pub struct AgedInvoice {
+    pub invoice_number: u16,
+    pub customer: String,
+    pub currency: String,
+    pub due_date: NaiveDate,
+    /// Days since the due date, negative when it's still ahead
+    pub days_overdue: i64,
+    pub bucket: AgingBucket,
+    pub outstanding: f64,
+}
+
+#[derive(Clone, Debug)]
+/// Outstanding amounts of one customer in one currency, per bucket.
+pub struct CustomerAging {
+    pub customer: String,
+    pub currency: String,
+    /// Amounts in the order of `AgingBucket::ALL`
+    pub buckets: [f64; 5],
+}
+
+impl CustomerAging {
+    pub fn total(&self) -> f64 {
+        self.buckets.iter().sum()
+    }
+
+    pub fn amount(&self, bucket: AgingBucket) -> f64 {
+        self.buckets[bucket.index()]
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Receivables by how long they're overdue, per customer.
+pub struct AgingReport {
+    pub as_of: NaiveDate,
+    pub invoices: Vec<AgedInvoice>,
+    /// One line per customer and currency, sorted by customer
+    pub customers: Vec<CustomerAging>,
+    /// Totals per currency, amounts can't be added up across currencies
+    pub totals: Vec<CustomerAging>,
+    /// Open invoices whose due date can't be parsed, left out of the buckets
+    pub undated: Vec<u16>,
+}
+
+impl AgingReport {
+    /// Ages the invoices still waiting for payment: issued or overdue, and without a recorded payment
+    pub fn new(invoices: &[ParsedInvoice], as_of: NaiveDate) -> AgingReport {
+        let open = invoices.iter()
+            .filter(|i| is_open(i))
+            .map(|i| (i, i.total.amount_due as f64))
+            .collect();
+        Self::from_outstanding(open, as_of)
+    }
+
+    /// Ages the invoices with what's left after the reconciled transfers, partial payments included
+    #[cfg(feature = "from_file")]
+    pub fn from_reconciliation(invoices: &[ParsedInvoice], reconciliation: &Reconciliation, as_of: NaiveDate) -> AgingReport {
+        let open = invoices.iter()
+            .filter(|i| is_open(i))
+            .map(|i| {
+                let outstanding = reconciliation.invoices.iter()
+                    .find(|r| r.invoice_number == i.data.invoice_number)
+                    .map_or(i.total.amount_due as f64, |r| r.outstanding());
+                (i, outstanding)
+            })
+            .filter(|(_, outstanding)| *outstanding > 0.0)
+            .collect();
+        Self::from_outstanding(open, as_of)
+    }
+
+    fn from_outstanding(open: Vec<(&ParsedInvoice, f64)>, as_of: NaiveDate) -> AgingReport {
+        let mut report = AgingReport { as_of, invoices: vec![], customers: vec![], totals: vec![], undated: vec![] };
+
+        for (invoice, outstanding) in open {
+            let Some(due_date) = invoice.due_date() else {
+                report.undated.push(invoice.data.invoice_number);
+                continue;
+            };
+            let days_overdue = (as_of - due_date).num_days();
+            report.invoices.push(AgedInvoice {
+                invoice_number: invoice.data.invoice_number,
+                customer: invoice.to.name.clone(),
+                currency: invoice.payment.currency.clone(),
+                due_date,
+                days_overdue,
+                bucket: AgingBucket::from_days(days_overdue),
+                outstanding,
+            });
+        }
+        report.invoices.sort_by(|a, b| (&a.customer, b.days_overdue).cmp(&(&b.customer, a.days_overdue)));
+
+        for aged in report.invoices.iter() {
+            add_to(&mut report.customers, &aged.customer, aged);
+            add_to(&mut report.totals, "Total", aged);
+        }
+        report
+    }
+
+    /// Per customer summary as csv, with one total line per currency at the end
+    #[cfg(feature = "from_file")]
+    pub fn to_csv(&self, file_name: String) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = csv::Writer::from_path(format!("{}.csv", file_name))?;
+        let mut header = vec!["customer", "currency"];
+        header.extend(AgingBucket::ALL.iter().map(|b| b.label()));
+        header.push("total");
+        writer.write_record(&header)?;
+
+        for line in self.customers.iter().chain(self.totals.iter()) {
+            let mut record = vec![line.customer.clone(), line.currency.clone()];
+            record.extend(line.buckets.iter().map(|amount| format!("{:.2}", amount)));
+            record.push(format!("{:.2}", line.total()));
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn is_open(invoice: &ParsedInvoice) -> bool {
+    matches!(invoice.status(), InvoiceStatus::Issued | InvoiceStatus::Overdue) && !invoice.payment.is_paid()
+}
+
+fn add_to(lines: &mut Vec<CustomerAging>, customer: &str, aged: &AgedInvoice) {
+    let position = lines.iter().position(|l| l.customer == customer && l.currency == aged.currency);
+    let line = match position {
+        Some(i) => &mut lines[i],
+        None => {
+            lines.push(CustomerAging {
+                customer: customer.to_string(),
+                currency: aged.currency.clone(),
+                buckets: [0.0; 5],
+            });
+            lines.last_mut().unwrap()
+        },
+    };
+    line.buckets[aged.bucket.index()] += aged.outstanding;
+}
+
+#[cfg(feature = "pdf")]
+impl crate::invoice::ExportsPDF for AgingReport {
+    fn to_pdf(self, file_name: String) -> Result<(), Box<dyn std::error::Error>> {
+        use genpdf::{
+            elements::{Break, FrameCellDecorator, Paragraph, TableLayout},
+            style::{Style, StyledString},
+            Alignment, Document, Element, Margins, Mm, SimplePageDecorator,
+        };
+        use crate::simple_invoice::genpdf_error_convert;
+
+        let pad_box = Margins::from(Mm::from(1));
+        let bold = |s: &str| StyledString::new(s.to_string(), Style::new().bold());
+
+        let mut doc = Document::new(Self::set_pdf_fonts()?);
+        doc.set_title(format!("Aging report {}", self.as_of));
+        doc.set_font_size(9);
+        let mut decorator = SimplePageDecorator::new();
+        decorator.set_margins(10);
+        doc.set_page_decorator(decorator);
+
+        doc.push(Paragraph::new(StyledString::new(
+            format!("Aging report as of {}", self.as_of), Style::new().bold().with_font_size(14),
+        )));
+        doc.push(Break::new(1.5));
+
+        // SUMMARY, customer and currency followed by the buckets and the total
+        let mut table = TableLayout::new(vec![4, 2, 2, 2, 2, 2, 2, 2]);
+        table.set_cell_decorator(FrameCellDecorator::new(true, true, true));
+        let mut row = table.row();
+        row.push_element(Paragraph::new(bold("Customer")).padded(pad_box));
+        row.push_element(Paragraph::new(bold("Currency")).padded(pad_box));
+        for bucket in AgingBucket::ALL.iter() {
+            row.push_element(Paragraph::new(bold(bucket.label())).aligned(Alignment::Right).padded(pad_box));
+        }
+        row.push_element(Paragraph::new(bold("Total")).aligned(Alignment::Right).padded(pad_box));
+        row.push().map_err(genpdf_error_convert)?;
+
+        for (line, is_total) in self.customers.iter().map(|l| (l, false)).chain(self.totals.iter().map(|l| (l, true))) {
+            let style = if is_total { Style::new().bold() } else { Style::new() };
+            let cell = |text: String| Paragraph::new(StyledString::new(text, style));
+            let mut row = table.row();
+            row.push_element(cell(line.customer.clone()).padded(pad_box));
+            row.push_element(cell(line.currency.clone()).padded(pad_box));
+            for amount in line.buckets.iter().chain(std::iter::once(&line.total())) {
+                row.push_element(cell(format!("{:.2}", amount)).aligned(Alignment::Right).padded(pad_box));
+            }
+            row.push().map_err(genpdf_error_convert)?;
+        }
+        doc.push(table);
+        doc.push(Break::new(2));
+
+        // DETAILS, every open invoice
+        doc.push(Paragraph::new(bold("Open invoices")));
+        doc.push(Break::new(0.5));
+        let mut table = TableLayout::new(vec![2, 4, 3, 2, 2, 3]);
+        table.set_cell_decorator(FrameCellDecorator::new(true, true, true));
+        let mut row = table.row();
+        for (title, align) in [
+            ("Invoice", Alignment::Left), ("Customer", Alignment::Left), ("Due date", Alignment::Left),
+            ("Days overdue", Alignment::Right), ("Bucket", Alignment::Right), ("Outstanding", Alignment::Right),
+        ] {
+            row.push_element(Paragraph::new(bold(title)).aligned(align).padded(pad_box));
+        }
+        row.push().map_err(genpdf_error_convert)?;
+
+        for aged in self.invoices.iter() {
+            let mut row = table.row();
+            row.push_element(Paragraph::new(format!("#{}", aged.invoice_number)).padded(pad_box));
+            row.push_element(Paragraph::new(aged.customer.clone()).padded(pad_box));
+            row.push_element(Paragraph::new(aged.due_date.to_string()).padded(pad_box));
+            row.push_element(Paragraph::new(aged.days_overdue.max(0).to_string()).aligned(Alignment::Right).padded(pad_box));
+            row.push_element(Paragraph::new(aged.bucket.label()).aligned(Alignment::Right).padded(pad_box));
+            row.push_element(
+                Paragraph::new(format!("{:.2} {}", aged.outstanding, aged.currency)).aligned(Alignment::Right).padded(pad_box)
+            );
+            row.push().map_err(genpdf_error_convert)?;
+        }
+        doc.push(table);
+
+        if !self.undated.is_empty() {
+            doc.push(Break::new(1));
+            let numbers: Vec<String> = self.undated.iter().map(|n| format!("#{}", n)).collect();
+            doc.push(Paragraph::new(format!("Left out, due date can't be read: {}", numbers.join(", "))));
+        }
+
+        doc.render_to_file(format!("{}.pdf", file_name)).map_err(genpdf_error_convert)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "html")]
+#[derive(askama::Template)]
+#[template(path = "aging_report.html")]
+/// Askama Template type for the aging report
+struct TemplateableAgingReport {
+    pub report: AgingReport,
+    pub buckets: [AgingBucket; 5],
+}
+
+#[cfg(feature = "html")]
+impl crate::invoice::ExportsHTML for AgingReport {
+    fn to_html(self, file_name: String) -> Result<(), Box<dyn std::error::Error>> {
+        use askama::Template;
+
+        let rendered = TemplateableAgingReport { report: self, buckets: AgingBucket::ALL }.render()?;
+        std::fs::write(format!("{}.html", file_name), rendered)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::reader::reader::FileInvoice;
+use crate::types::{Catalog, InvoiceStatus, ParsedInvoice, RawInvoice};
+
+/// Hash the first entry is chained to
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Problem found in the audit log, or with an invoice that's about to be added to it.
+#[derive(Debug)]
+pub enum AuditError {
+    /// Drafts can still change, only issued invoices are recorded
+    NotIssued(u16),
+    /// Line that isn't an audit entry at all
+    Malformed { line: usize },
+    /// Entry missing before this one, it was deleted or the log was reordered
+    MissingEntry { sequence: u64 },
+    /// Entry whose content doesn't match its hash anymore
+    Tampered { sequence: u64 },
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditError::NotIssued(number) => write!(f, "invoice {} is a draft, only issued invoices are logged", number),
+            AuditError::Malformed { line } => write!(f, "audit log line {} isn't an audit entry", line),
+            AuditError::MissingEntry { sequence } => write!(f, "audit log entries before entry {} are missing", sequence),
+            AuditError::Tampered { sequence } => write!(f, "audit log entry {} was altered", sequence),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+/// One recorded invoice, hashed together with the hash of the entry before it.
+pub struct AuditEntry {
+    pub sequence: u64,
+    pub invoice_number: u16,
+    /// RFC 3339 timestamp of when the entry was appended
+    pub recorded_at: String,
+    /// Canonical serialization of the invoice, the InvoiceReader json format on a single line
+    pub invoice: String,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// sha256 over every other field of the entry, hex encoded
+    fn compute_hash(&self) -> String {
+        let content = format!(
+            "{}\n{}\n{}\n{}\n{}", self.sequence, self.invoice_number, self.recorded_at, self.previous_hash, self.invoice
+        );
+        Sha256::digest(content.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// The invoice as it was recorded
+    pub fn raw_invoice(&self) -> Result<RawInvoice, Box<dyn std::error::Error>> {
+        // recorded invoices are complete, they never refer to a catalog
+        Ok(serde_json::from_str::<FileInvoice>(&self.invoice)?.resolve(&Catalog::default())?)
+    }
+}
+
+/// Append-only log of issued invoices, kept as a hash chain in a JSON-lines file.
+///
+/// Every entry includes the hash of the previous one, so editing, removing or reordering entries
+/// breaks the chain from that point on. Removing entries from the end can't be noticed from
+/// the log alone, keep the `head` hash somewhere else (a printed report, a timestamping service)
+/// to catch that too.
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    /// Opens the log at `path`, creating an empty one if the file doesn't exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.into();
+        OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(AuditLog { path })
+    }
+
+    /// Records the current state of an issued invoice, after checking the existing chain
+    pub fn append(&self, invoice: &ParsedInvoice) -> Result<AuditEntry, Box<dyn std::error::Error>> {
+        if invoice.status() == InvoiceStatus::Draft {
+            return Err(Box::new(AuditError::NotIssued(invoice.data.invoice_number)));
+        }
+
+        let entries = self.verify()?;
+        let mut entry = AuditEntry {
+            sequence: entries.len() as u64,
+            invoice_number: invoice.data.invoice_number,
+            recorded_at: Utc::now().to_rfc3339(),
+            invoice: serde_json::to_string(&FileInvoice::from(invoice.clone()))?,
+            previous_hash: entries.last().map_or(String::from(GENESIS), |e| e.hash.clone()),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(entry)
+    }
+
+    /// Walks the whole chain, returning its entries if none were altered or removed
+    pub fn verify(&self) -> Result<Vec<AuditEntry>, Box<dyn std::error::Error>> {
+        let mut entries: Vec<AuditEntry> = vec![];
+        for (i, line) in fs::read_to_string(&self.path)?.lines().enumerate() {
+            let entry: AuditEntry = serde_json::from_str(line).map_err(|_| AuditError::Malformed { line: i + 1 })?;
+            let previous = entries.last().map_or(GENESIS, |e| e.hash.as_str());
+
+            if entry.sequence != entries.len() as u64 {
+                return Err(Box::new(AuditError::MissingEntry { sequence: entry.sequence }));
+            }
+            if entry.compute_hash() != entry.hash {
+                return Err(Box::new(AuditError::Tampered { sequence: entry.sequence }));
+            }
+            // the entry before was rewritten, hash included
+            if entry.previous_hash != previous {
+                return Err(Box::new(AuditError::Tampered { sequence: entry.sequence.saturating_sub(1) }));
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Hash of the latest entry, which vouches for the whole log
+    pub fn head(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Ok(self.verify()?.pop().map(|e| e.hash))
+    }
+
+    /// Whether the invoice is exactly as it was last recorded in the log
+    pub fn matches_log(&self, invoice: &ParsedInvoice) -> Result<bool, Box<dyn std::error::Error>> {
+        let canonical = serde_json::to_string(&FileInvoice::from(invoice.clone()))?;
+        let latest = self.verify()?.into_iter().rev().find(|e| e.invoice_number == invoice.data.invoice_number);
+        Ok(latest.is_some_and(|e| e.invoice == canonical))
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+
+use crate::types::{Catalog, PersonalInfo, Product};
+
+/// Catalog reference that can't be resolved.
+#[derive(Debug)]
+pub enum CatalogError {
+    UnknownCustomer(String),
+    UnknownProduct(String),
+    /// Field neither the invoice nor the catalog provide
+    MissingField(&'static str),
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::UnknownCustomer(key) => write!(f, "unknown customer: {}", key),
+            CatalogError::UnknownProduct(sku) => write!(f, "unknown product: {}", sku),
+            CatalogError::MissingField(field) => write!(f, "missing {}, and no catalog entry to take it from", field),
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+impl Catalog {
+    pub fn customer(&self, key: &str) -> Result<&PersonalInfo, CatalogError> {
+        self.customers.get(key).ok_or_else(|| CatalogError::UnknownCustomer(key.to_string()))
+    }
+
+    pub fn product(&self, sku: &str) -> Result<&Product, CatalogError> {
+        self.products.get(sku).ok_or_else(|| CatalogError::UnknownProduct(sku.to_string()))
+    }
+
+    /// Our company profile, the sender of invoices that don't set one
+    pub fn company(&self) -> Result<&PersonalInfo, CatalogError> {
+        self.company.as_ref().ok_or(CatalogError::MissingField("from"))
+    }
+}
@@ -0,0 +1,450 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+use roxmltree::{Document, Node};
+
+use crate::en16931::{parse_item, ElectronicAddress, Party, PartyContact, PaymentMeans, PostalAddress, SemanticInvoice, SPECIFICATION};
+use crate::types::{Contact, InvoiceData, Payment, PersonalInfo, RawInvoice};
+use crate::xml::{amount, XmlReader, XmlWriter};
+
+pub const RSM_NAMESPACE: &str = "urn:un:unece:uncefact:data:standard:CrossIndustryInvoice:100";
+pub const RAM_NAMESPACE: &str = "urn:un:unece:uncefact:data:standard:ReusableAggregateBusinessInformationEntity:100";
+pub const UDT_NAMESPACE: &str = "urn:un:unece:uncefact:data:standard:UnqualifiedDataType:100";
+pub const QDT_NAMESPACE: &str = "urn:un:unece:uncefact:data:standard:QualifiedDataType:100";
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+/// Factur-X / ZUGFeRD profile, each one carries everything the ones before it do.
+pub enum CiiProfile {
+    /// Totals, parties and the document header only
+    Minimum,
+    /// Minimum plus addresses, payment means and the VAT breakdown, still without lines
+    BasicWL,
+    /// Basic WL plus the invoice lines
+    Basic,
+    /// Everything EN 16931 defines
+    EN16931,
+    Extended,
+}
+
+impl CiiProfile {
+    pub const ALL: [CiiProfile; 5] = [
+        CiiProfile::Minimum, CiiProfile::BasicWL, CiiProfile::Basic, CiiProfile::EN16931, CiiProfile::Extended,
+    ];
+
+    /// Guideline identifier (BT-24) the profile writes into the document
+    pub fn guideline(&self) -> &'static str {
+        match self {
+            CiiProfile::Minimum => "urn:factur-x.eu:1p0:minimum",
+            CiiProfile::BasicWL => "urn:factur-x.eu:1p0:basicwl",
+            CiiProfile::Basic => "urn:cen.eu:en16931:2017#compliant#urn:factur-x.eu:1p0:basic",
+            CiiProfile::EN16931 => SPECIFICATION,
+            CiiProfile::Extended => "urn:cen.eu:en16931:2017#conformant#urn:factur-x.eu:1p0:extended",
+        }
+    }
+
+    /// Profile of a guideline identifier, identifiers of a CIUS on top of EN 16931 count as EN 16931
+    pub fn from_guideline(id: &str) -> Option<CiiProfile> {
+        let id = id.trim();
+        CiiProfile::ALL.into_iter()
+            .find(|p| p.guideline() == id)
+            .or_else(|| id.starts_with(SPECIFICATION).then_some(CiiProfile::EN16931))
+    }
+
+    /// Name of the profile as Factur-X metadata spells it
+    pub fn name(&self) -> &'static str {
+        match self {
+            CiiProfile::Minimum => "MINIMUM",
+            CiiProfile::BasicWL => "BASIC WL",
+            CiiProfile::Basic => "BASIC",
+            CiiProfile::EN16931 => "EN 16931",
+            CiiProfile::Extended => "EXTENDED",
+        }
+    }
+}
+
+/// CII document that can't be read back into an invoice.
+#[derive(Debug)]
+pub enum CiiError {
+    Xml(roxmltree::Error),
+    /// Root element isn't a cross industry invoice
+    NotAnInvoice,
+    /// Element the invoice can't do without, by its path
+    MissingElement(&'static str),
+    InvalidValue { element: &'static str, value: String },
+    /// Profile that leaves the lines out, so there are no items to read
+    NoLines(CiiProfile),
+}
+
+impl fmt::Display for CiiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CiiError::Xml(e) => write!(f, "invalid xml: {}", e),
+            CiiError::NotAnInvoice => write!(f, "not a cross industry invoice"),
+            CiiError::MissingElement(path) => write!(f, "missing CII element {}", path),
+            CiiError::InvalidValue { element, value } => write!(f, "invalid value for {}: {}", element, value),
+            CiiError::NoLines(profile) => write!(f, "the {} profile has no invoice lines", profile.name()),
+        }
+    }
+}
+
+impl std::error::Error for CiiError {}
+
+/// CII D16B document of the invoice, with what the profile asks for.
+///
+/// The EN 16931 profile keeps the specification identifier of the invoice, so a CIUS like
+/// XRechnung carries its own.
+pub fn invoice_xml(invoice: &SemanticInvoice, profile: CiiProfile) -> String {
+    let currency = [("currencyID", invoice.currency.as_str())];
+    let details = profile >= CiiProfile::BasicWL;
+    let guideline = if profile == CiiProfile::EN16931 { invoice.specification.as_str() } else { profile.guideline() };
+
+    let mut xml = XmlWriter::new();
+    xml.open("rsm:CrossIndustryInvoice", &[
+        ("xmlns:rsm", RSM_NAMESPACE), ("xmlns:ram", RAM_NAMESPACE),
+        ("xmlns:udt", UDT_NAMESPACE), ("xmlns:qdt", QDT_NAMESPACE),
+    ]);
+    xml.open("rsm:ExchangedDocumentContext", &[]);
+    if let Some(process) = &invoice.business_process {
+        xml.open("ram:BusinessProcessSpecifiedDocumentContextParameter", &[]);
+        xml.leaf("ram:ID", &[], process);
+        xml.close("ram:BusinessProcessSpecifiedDocumentContextParameter");
+    }
+    xml.open("ram:GuidelineSpecifiedDocumentContextParameter", &[]);
+    xml.leaf("ram:ID", &[], guideline);
+    xml.close("ram:GuidelineSpecifiedDocumentContextParameter");
+    xml.close("rsm:ExchangedDocumentContext");
+
+    xml.open("rsm:ExchangedDocument", &[]);
+    xml.leaf("ram:ID", &[], &invoice.number);
+    xml.leaf("ram:TypeCode", &[], &invoice.type_code.to_string());
+    if let Some(date) = invoice.issue_date {
+        write_date(&mut xml, "ram:IssueDateTime", date);
+    }
+    xml.close("rsm:ExchangedDocument");
+
+    xml.open("rsm:SupplyChainTradeTransaction", &[]);
+    if profile >= CiiProfile::Basic {
+        for line in invoice.lines.iter() {
+            xml.open("ram:IncludedSupplyChainTradeLineItem", &[]);
+            xml.open("ram:AssociatedDocumentLineDocument", &[]);
+            xml.leaf("ram:LineID", &[], &line.id);
+            xml.close("ram:AssociatedDocumentLineDocument");
+            xml.open("ram:SpecifiedTradeProduct", &[]);
+            xml.leaf("ram:Name", &[], &line.name);
+            xml.close("ram:SpecifiedTradeProduct");
+            xml.open("ram:SpecifiedLineTradeAgreement", &[]);
+            xml.open("ram:NetPriceProductTradePrice", &[]);
+            xml.leaf("ram:ChargeAmount", &[], &amount(line.price));
+            xml.close("ram:NetPriceProductTradePrice");
+            xml.close("ram:SpecifiedLineTradeAgreement");
+            xml.open("ram:SpecifiedLineTradeDelivery", &[]);
+            xml.leaf("ram:BilledQuantity", &[("unitCode", line.unit_code)], &line.quantity.to_string());
+            xml.close("ram:SpecifiedLineTradeDelivery");
+            xml.open("ram:SpecifiedLineTradeSettlement", &[]);
+            xml.open("ram:ApplicableTradeTax", &[]);
+            xml.leaf("ram:TypeCode", &[], "VAT");
+            xml.leaf("ram:CategoryCode", &[], line.category.code());
+            xml.leaf("ram:RateApplicablePercent", &[], &line.rate.to_string());
+            xml.close("ram:ApplicableTradeTax");
+            xml.open("ram:SpecifiedTradeSettlementLineMonetarySummation", &[]);
+            xml.leaf("ram:LineTotalAmount", &[], &amount(line.net_amount));
+            xml.close("ram:SpecifiedTradeSettlementLineMonetarySummation");
+            xml.close("ram:SpecifiedLineTradeSettlement");
+            xml.close("ram:IncludedSupplyChainTradeLineItem");
+        }
+    }
+
+    xml.open("ram:ApplicableHeaderTradeAgreement", &[]);
+    xml.optional("ram:BuyerReference", invoice.buyer_reference.as_deref());
+    write_party(&mut xml, "ram:SellerTradeParty", &invoice.seller, profile);
+    write_party(&mut xml, "ram:BuyerTradeParty", &invoice.buyer, profile);
+    xml.close("ram:ApplicableHeaderTradeAgreement");
+
+    xml.open("ram:ApplicableHeaderTradeDelivery", &[]);
+    if details && let Some(delivery) = &invoice.delivery {
+        xml.open("ram:ShipToTradeParty", &[]);
+        xml.leaf("ram:Name", &[], &delivery.name);
+        write_address(&mut xml, &delivery.address);
+        xml.close("ram:ShipToTradeParty");
+    }
+    xml.close("ram:ApplicableHeaderTradeDelivery");
+
+    xml.open("ram:ApplicableHeaderTradeSettlement", &[]);
+    if details {
+        let reference = invoice.payment_means.iter().find_map(|m| m.remittance_information.as_deref());
+        xml.optional("ram:PaymentReference", reference);
+    }
+    xml.leaf("ram:InvoiceCurrencyCode", &[], &invoice.currency);
+    if details {
+        for means in invoice.payment_means.iter() {
+            xml.open("ram:SpecifiedTradeSettlementPaymentMeans", &[]);
+            xml.leaf("ram:TypeCode", &[], means.type_code);
+            if let Some(account) = &means.account {
+                let is_iban = matches!(means.type_code, "30" | "31" | "42" | "58");
+                xml.open("ram:PayeePartyCreditorFinancialAccount", &[]);
+                xml.leaf(if is_iban { "ram:IBANID" } else { "ram:ProprietaryID" }, &[], account);
+                xml.optional("ram:AccountName", means.account_name.as_deref());
+                xml.close("ram:PayeePartyCreditorFinancialAccount");
+            }
+            if let Some(bic) = &means.bic {
+                xml.open("ram:PayeeSpecifiedCreditorFinancialInstitution", &[]);
+                xml.leaf("ram:BICID", &[], bic);
+                xml.close("ram:PayeeSpecifiedCreditorFinancialInstitution");
+            }
+            xml.close("ram:SpecifiedTradeSettlementPaymentMeans");
+        }
+        for breakdown in invoice.vat_breakdown.iter() {
+            xml.open("ram:ApplicableTradeTax", &[]);
+            xml.leaf("ram:CalculatedAmount", &[], &amount(breakdown.tax_amount));
+            xml.leaf("ram:TypeCode", &[], "VAT");
+            xml.optional("ram:ExemptionReason", breakdown.exemption_reason.as_deref());
+            xml.leaf("ram:BasisAmount", &[], &amount(breakdown.taxable_amount));
+            xml.leaf("ram:CategoryCode", &[], breakdown.category.code());
+            xml.optional("ram:ExemptionReasonCode", breakdown.exemption_reason_code);
+            xml.leaf("ram:RateApplicablePercent", &[], &breakdown.rate.to_string());
+            xml.close("ram:ApplicableTradeTax");
+        }
+        if let Some(date) = invoice.due_date {
+            xml.open("ram:SpecifiedTradePaymentTerms", &[]);
+            write_date(&mut xml, "ram:DueDateDateTime", date);
+            xml.close("ram:SpecifiedTradePaymentTerms");
+        }
+    }
+
+    xml.open("ram:SpecifiedTradeSettlementHeaderMonetarySummation", &[]);
+    if details {
+        xml.leaf("ram:LineTotalAmount", &[], &amount(invoice.totals.line_net_total));
+    }
+    xml.leaf("ram:TaxBasisTotalAmount", &[], &amount(invoice.totals.tax_exclusive));
+    xml.leaf("ram:TaxTotalAmount", &currency, &amount(invoice.totals.tax_total));
+    xml.leaf("ram:GrandTotalAmount", &[], &amount(invoice.totals.tax_inclusive));
+    if details && invoice.totals.paid > 0 {
+        xml.leaf("ram:TotalPrepaidAmount", &[], &amount(invoice.totals.paid));
+    }
+    xml.leaf("ram:DuePayableAmount", &[], &amount(invoice.totals.amount_due));
+    xml.close("ram:SpecifiedTradeSettlementHeaderMonetarySummation");
+    xml.close("ram:ApplicableHeaderTradeSettlement");
+
+    xml.close("rsm:SupplyChainTradeTransaction");
+    xml.close("rsm:CrossIndustryInvoice");
+    xml.finish()
+}
+
+/// Minimum only names the parties, and of the buyer nothing else, the seller keeps its country
+/// and VAT ID. Contacts start at EN 16931.
+fn write_party(xml: &mut XmlWriter, tag: &str, party: &Party, profile: CiiProfile) {
+    let seller = tag == "ram:SellerTradeParty";
+    xml.open(tag, &[]);
+    xml.leaf("ram:Name", &[], &party.name);
+    if profile >= CiiProfile::EN16931 && let Some(contact) = &party.contact {
+        xml.open("ram:DefinedTradeContact", &[]);
+        xml.optional("ram:PersonName", contact.name.as_deref());
+        if let Some(phone) = &contact.phone {
+            xml.open("ram:TelephoneUniversalCommunication", &[]);
+            xml.leaf("ram:CompleteNumber", &[], phone);
+            xml.close("ram:TelephoneUniversalCommunication");
+        }
+        if let Some(email) = &contact.email {
+            xml.open("ram:EmailURIUniversalCommunication", &[]);
+            xml.leaf("ram:URIID", &[], email);
+            xml.close("ram:EmailURIUniversalCommunication");
+        }
+        xml.close("ram:DefinedTradeContact");
+    }
+    if profile >= CiiProfile::BasicWL {
+        write_address(xml, &party.address);
+    } else if seller {
+        // the only part of the address the MINIMUM schema has
+        xml.open("ram:PostalTradeAddress", &[]);
+        xml.optional("ram:CountryID", party.address.country_code.as_deref());
+        xml.close("ram:PostalTradeAddress");
+    }
+    if profile >= CiiProfile::BasicWL && let Some(address) = &party.electronic_address {
+        xml.open("ram:URIUniversalCommunication", &[]);
+        xml.leaf("ram:URIID", &[("schemeID", &address.scheme)], &address.id);
+        xml.close("ram:URIUniversalCommunication");
+    }
+    if (profile >= CiiProfile::BasicWL || seller) && let Some(vat_id) = &party.vat_id {
+        xml.open("ram:SpecifiedTaxRegistration", &[]);
+        xml.leaf("ram:ID", &[("schemeID", "VA")], vat_id);
+        xml.close("ram:SpecifiedTaxRegistration");
+    }
+    xml.close(tag);
+}
+
+fn write_address(xml: &mut XmlWriter, address: &PostalAddress) {
+    xml.open("ram:PostalTradeAddress", &[]);
+    xml.optional("ram:PostcodeCode", address.post_code.as_deref());
+    xml.optional("ram:LineOne", address.line_one.as_deref());
+    xml.optional("ram:CityName", address.city.as_deref());
+    xml.optional("ram:CountryID", address.country_code.as_deref());
+    xml.close("ram:PostalTradeAddress");
+}
+
+/// Date in the 102 format, YYYYMMDD
+fn write_date(xml: &mut XmlWriter, tag: &str, date: NaiveDate) {
+    xml.open(tag, &[]);
+    xml.leaf("udt:DateTimeString", &[("format", "102")], &date.format("%Y%m%d").to_string());
+    xml.close(tag);
+}
+
+const CII: XmlReader = XmlReader {
+    namespaces: &[("rsm", RSM_NAMESPACE), ("ram", RAM_NAMESPACE), ("udt", UDT_NAMESPACE), ("qdt", QDT_NAMESPACE)],
+};
+
+/// Profile the document was written with, from its guideline identifier
+pub fn document_profile(xml: &str) -> Result<CiiProfile, CiiError> {
+    let document = Document::parse(xml).map_err(CiiError::Xml)?;
+    profile_of(document.root_element())
+}
+
+fn profile_of(root: Node) -> Result<CiiProfile, CiiError> {
+    if !CII.is(&root, "rsm:CrossIndustryInvoice") {
+        return Err(CiiError::NotAnInvoice);
+    }
+    let path = ["rsm:ExchangedDocumentContext", "ram:GuidelineSpecifiedDocumentContextParameter", "ram:ID"];
+    let guideline = CII.text(root, &path)
+        .ok_or(CiiError::MissingElement("rsm:ExchangedDocumentContext/ram:GuidelineSpecifiedDocumentContextParameter/ram:ID"))?;
+    CiiProfile::from_guideline(&guideline).ok_or(CiiError::InvalidValue { element: "guideline", value: guideline })
+}
+
+/// Reads a CII invoice back into a raw invoice.
+///
+/// Only the Basic profile and up have lines to read items from, countries come back as their ISO
+/// code, and payment means other than bank transfers, card links and PayPal are left out.
+pub fn parse_invoice(xml: &str) -> Result<RawInvoice, CiiError> {
+    let document = Document::parse(xml).map_err(CiiError::Xml)?;
+    let root = document.root_element();
+    let profile = profile_of(root)?;
+    if profile < CiiProfile::Basic {
+        return Err(CiiError::NoLines(profile));
+    }
+
+    let header = CII.find(root, &["rsm:ExchangedDocument"]).ok_or(CiiError::MissingElement("rsm:ExchangedDocument"))?;
+    let transaction = CII.find(root, &["rsm:SupplyChainTradeTransaction"])
+        .ok_or(CiiError::MissingElement("rsm:SupplyChainTradeTransaction"))?;
+    let agreement = ["ram:ApplicableHeaderTradeAgreement"];
+    let seller = CII.find(transaction, &[agreement[0], "ram:SellerTradeParty"])
+        .ok_or(CiiError::MissingElement("ram:SellerTradeParty"))?;
+    let buyer = CII.find(transaction, &[agreement[0], "ram:BuyerTradeParty"])
+        .ok_or(CiiError::MissingElement("ram:BuyerTradeParty"))?;
+    let settlement = CII.find(transaction, &["ram:ApplicableHeaderTradeSettlement"])
+        .ok_or(CiiError::MissingElement("ram:ApplicableHeaderTradeSettlement"))?;
+    let from = PersonalInfo::from(parse_party(seller));
+
+    let contact = CII.find(buyer, &["ram:DefinedTradeContact"])
+        .and_then(|c| CII.text(c, &["ram:PersonName"]).map(|name| (c, name)))
+        .map(|(c, name)| Contact {
+            name,
+            department: None,
+            email: CII.text(c, &["ram:EmailURIUniversalCommunication", "ram:URIID"]),
+            phone: CII.text(c, &["ram:TelephoneUniversalCommunication", "ram:CompleteNumber"]),
+        });
+
+    let ship_to = CII.find(transaction, &["ram:ApplicableHeaderTradeDelivery", "ram:ShipToTradeParty"])
+        .map(|party| PersonalInfo::from(parse_party(party)));
+
+    let mut items = vec![];
+    for line in CII.children(transaction, "ram:IncludedSupplyChainTradeLineItem") {
+        let tax = ["ram:SpecifiedLineTradeSettlement", "ram:ApplicableTradeTax"];
+        let item = parse_item(
+            CII.text(line, &["ram:SpecifiedTradeProduct", "ram:Name"]).unwrap_or_default(),
+            &CII.text(line, &["ram:SpecifiedLineTradeDelivery", "ram:BilledQuantity"])
+                .ok_or(CiiError::MissingElement("ram:SpecifiedLineTradeDelivery/ram:BilledQuantity"))?,
+            &CII.text(line, &["ram:SpecifiedLineTradeAgreement", "ram:NetPriceProductTradePrice", "ram:ChargeAmount"])
+                .ok_or(CiiError::MissingElement("ram:NetPriceProductTradePrice/ram:ChargeAmount"))?,
+            CII.text(line, &[tax[0], tax[1], "ram:RateApplicablePercent"]).as_deref(),
+            CII.text(line, &[tax[0], tax[1], "ram:CategoryCode"]).as_deref(),
+        );
+        items.push(item.map_err(|(element, value)| CiiError::InvalidValue { element, value })?);
+    }
+
+    let reference = CII.text(settlement, &["ram:PaymentReference"]);
+    let methods = CII.children(settlement, "ram:SpecifiedTradeSettlementPaymentMeans")
+        .filter_map(|means| {
+            let account = ["ram:PayeePartyCreditorFinancialAccount"];
+            let type_code = PaymentMeans::type_code_of(&CII.text(means, &["ram:TypeCode"]).unwrap_or_default());
+            PaymentMeans {
+                type_code,
+                // the reference is given once for all means, only transfers quote it
+                remittance_information: reference.clone().filter(|_| matches!(type_code, "30" | "31" | "42" | "58")),
+                account: CII.text(means, &[account[0], "ram:IBANID"])
+                    .or_else(|| CII.text(means, &[account[0], "ram:ProprietaryID"])),
+                account_name: CII.text(means, &[account[0], "ram:AccountName"]),
+                bic: CII.text(means, &["ram:PayeeSpecifiedCreditorFinancialInstitution", "ram:BICID"]),
+            }.to_method(&from.name)
+        })
+        .collect();
+
+    let number = CII.text(header, &["ram:ID"]).ok_or(CiiError::MissingElement("rsm:ExchangedDocument/ram:ID"))?;
+    let issue_date = CII.text(header, &["ram:IssueDateTime", "udt:DateTimeString"])
+        .ok_or(CiiError::MissingElement("ram:IssueDateTime"))?;
+    let due_date = CII.text(settlement, &["ram:SpecifiedTradePaymentTerms", "ram:DueDateDateTime", "udt:DateTimeString"]);
+    Ok(RawInvoice {
+        from,
+        to: PersonalInfo {
+            buyer_reference: CII.text(transaction, &[agreement[0], "ram:BuyerReference"]),
+            ..PersonalInfo::from(parse_party(buyer))
+        },
+        ship_to,
+        contact,
+        items,
+        payment: Payment {
+            currency: CII.text(settlement, &["ram:InvoiceCurrencyCode"])
+                .ok_or(CiiError::MissingElement("ram:InvoiceCurrencyCode"))?,
+            methods,
+            tx: None,
+            settled_on: None,
+        },
+        data: InvoiceData {
+            invoice_number: number.parse().map_err(|_| CiiError::InvalidValue { element: "ram:ID", value: number.clone() })?,
+            due_date: due_date.map(|d| parse_date_102(&d)).transpose()?.unwrap_or_default(),
+            issue_date: parse_date_102(&issue_date)?,
+            history: vec![],
+        },
+    })
+}
+
+fn parse_party(party: Node) -> Party {
+    let address = CII.find(party, &["ram:PostalTradeAddress"]);
+    Party {
+        name: CII.text(party, &["ram:Name"]).unwrap_or_default(),
+        vat_id: CII.children(party, "ram:SpecifiedTaxRegistration")
+            .filter_map(|r| CII.find(r, &["ram:ID"]))
+            .find(|id| id.attribute("schemeID") == Some("VA"))
+            .and_then(|id| id.text())
+            .map(|id| id.trim().to_string()),
+        electronic_address: CII.find(party, &["ram:URIUniversalCommunication", "ram:URIID"]).and_then(|uri| Some(ElectronicAddress {
+            scheme: uri.attribute("schemeID")?.to_string(),
+            id: uri.text()?.trim().to_string(),
+        })),
+        address: PostalAddress {
+            line_one: address.and_then(|a| CII.text(a, &["ram:LineOne"])),
+            city: address.and_then(|a| CII.text(a, &["ram:CityName"])),
+            post_code: address.and_then(|a| CII.text(a, &["ram:PostcodeCode"])),
+            country_code: address.and_then(|a| CII.text(a, &["ram:CountryID"])),
+        },
+        contact: CII.find(party, &["ram:DefinedTradeContact"]).map(|contact| PartyContact {
+            name: None,
+            phone: CII.text(contact, &["ram:TelephoneUniversalCommunication", "ram:CompleteNumber"]),
+            email: CII.text(contact, &["ram:EmailURIUniversalCommunication", "ram:URIID"]),
+        }),
+    }
+}
+
+/// 102 formatted date as the YYYY-MM-DD the invoice files use
+fn parse_date_102(date: &str) -> Result<String, CiiError> {
+    NaiveDate::parse_from_str(date, "%Y%m%d")
+        .map(|d| d.to_string())
+        .map_err(|_| CiiError::InvalidValue { element: "udt:DateTimeString", value: date.to_string() })
+}
+
+#[cfg(feature = "from_file")]
+impl crate::InvoiceReader {
+    /// Read a CII invoice, of the Basic profile or up, and convert it to raw type
+    pub fn from_cii(file: String) -> Result<Vec<RawInvoice>, Box<dyn std::error::Error>> {
+        let raw = parse_invoice(&std::fs::read_to_string(file)?)?;
+        raw.payment.validate()?;
+        Ok(vec![raw])
+    }
+}
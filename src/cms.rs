@@ -0,0 +1,128 @@
+//! Detached CMS signatures (RFC 5652) as PAdES baseline B-B asks for them.
+//!
+//! OpenSSL's `CMS_sign` always adds a signing-time attribute, which PAdES doesn't allow, the
+//! claimed signing time being the `/M` entry of the signature dictionary. So the SignedData is
+//! put together here, and OpenSSL only computes the hashes and the signature value.
+
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkey::{Id, PKeyRef, Private};
+use openssl::x509::X509;
+
+use crate::signature::SignError;
+
+const ID_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x01];
+const ID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+const ID_CONTENT_TYPE: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x03];
+const ID_MESSAGE_DIGEST: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04];
+const ID_SIGNING_CERTIFICATE_V2: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x02, 0x2f];
+const ID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+const RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const NULL: &[u8] = &[0x05, 0x00];
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const OCTET_STRING: u8 = 0x04;
+const CONTEXT_0: u8 = 0xa0;
+const DIRECTORY_NAME: u8 = 0xa4;
+
+/// Signature algorithm identifier of the key, only RSA and EC keys can sign
+pub(crate) fn signature_algorithm(key: &PKeyRef<Private>) -> Result<Vec<u8>, SignError> {
+    match key.id() {
+        Id::RSA => Ok(sequence(&[&oid(RSA_ENCRYPTION), NULL])),
+        Id::EC => Ok(sequence(&[&oid(ECDSA_WITH_SHA256)])),
+        _ => Err(SignError::UnsupportedKey),
+    }
+}
+
+/// DER ContentInfo of a SignedData over `data`, which isn't included.
+///
+/// The signed attributes are the content type, the message digest and the ESS
+/// signing-certificate-v2 referencing the certificate, all that PAdES-B-B asks for.
+pub(crate) fn sign_detached(data: &[u8], certificate: &X509, chain: &[X509], key: &PKeyRef<Private>) -> Result<Vec<u8>, SignError> {
+    let sha256 = sequence(&[&oid(ID_SHA256)]);
+    let certificate_der = certificate.to_der()?;
+    let issuer = certificate.issuer_name().to_der()?;
+    let serial = integer(&certificate.serial_number().to_bn()?.to_vec());
+
+    // the default hash algorithm of ESSCertIDv2 is sha256, so it's left out
+    let cert_id = sequence(&[
+        &der(OCTET_STRING, &hash(MessageDigest::sha256(), &certificate_der)?),
+        &sequence(&[&sequence(&[&der(DIRECTORY_NAME, &issuer)]), &serial]),
+    ]);
+    let attributes = set_of(SET, vec![
+        attribute(ID_CONTENT_TYPE, &oid(ID_DATA)),
+        attribute(ID_MESSAGE_DIGEST, &der(OCTET_STRING, &hash(MessageDigest::sha256(), data)?)),
+        attribute(ID_SIGNING_CERTIFICATE_V2, &sequence(&[&sequence(&[&cert_id])])),
+    ]);
+
+    // the attributes are signed as a SET, and written as [0] IMPLICIT into the SignerInfo
+    let mut signer = openssl::sign::Signer::new(MessageDigest::sha256(), key)?;
+    signer.update(&attributes)?;
+    let signature = signer.sign_to_vec()?;
+    let mut signed_attributes = attributes;
+    signed_attributes[0] = CONTEXT_0;
+
+    let signer_info = sequence(&[
+        &integer(&[1]),
+        &sequence(&[&issuer, &serial]),
+        &sha256,
+        &signed_attributes,
+        &signature_algorithm(key)?,
+        &der(OCTET_STRING, &signature),
+    ]);
+    let mut certificates = vec![certificate_der];
+    for certificate in chain {
+        certificates.push(certificate.to_der()?);
+    }
+    let signed_data = sequence(&[
+        &integer(&[1]),
+        &der(SET, &sha256),
+        &sequence(&[&oid(ID_DATA)]),
+        &set_of(CONTEXT_0, certificates),
+        &der(SET, &signer_info),
+    ]);
+    Ok(sequence(&[&oid(ID_SIGNED_DATA), &der(CONTEXT_0, &signed_data)]))
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    if content.len() < 0x80 {
+        out.push(content.len() as u8);
+    } else {
+        let length: Vec<u8> = content.len().to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+        out.push(0x80 | length.len() as u8);
+        out.extend(length);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn sequence(parts: &[&[u8]]) -> Vec<u8> {
+    der(SEQUENCE, &parts.concat())
+}
+
+/// DER sorts the elements of a SET OF by their encoding
+fn set_of(tag: u8, mut elements: Vec<Vec<u8>>) -> Vec<u8> {
+    elements.sort();
+    der(tag, &elements.concat())
+}
+
+fn oid(encoded: &[u8]) -> Vec<u8> {
+    der(0x06, encoded)
+}
+
+/// INTEGER of an unsigned big endian number
+fn integer(bytes: &[u8]) -> Vec<u8> {
+    let bytes = match bytes.iter().position(|b| *b != 0) {
+        Some(first) => &bytes[first..],
+        None => &[0][..],
+    };
+    // a leading zero keeps it from reading as negative
+    let padding: &[u8] = if bytes[0] & 0x80 != 0 { &[0] } else { &[] };
+    der(0x02, &[padding, bytes].concat())
+}
+
+fn attribute(kind: &[u8], value: &[u8]) -> Vec<u8> {
+    sequence(&[&oid(kind), &der(SET, value)])
+}
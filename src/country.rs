@@ -0,0 +1,26 @@
+/// Country names as found in invoice files, mapped to their ISO 3166-1 alpha-2 code
+const COUNTRY_CODES: [(&str, &str); 44] = [
+    ("andorra", "AD"), ("austria", "AT"), ("österreich", "AT"), ("belgium", "BE"),
+    ("bulgaria", "BG"), ("switzerland", "CH"), ("schweiz", "CH"), ("suisse", "CH"),
+    ("svizzera", "CH"), ("cyprus", "CY"), ("czechia", "CZ"), ("czech republic", "CZ"),
+    ("germany", "DE"), ("deutschland", "DE"), ("denmark", "DK"), ("estonia", "EE"),
+    ("spain", "ES"), ("españa", "ES"), ("finland", "FI"), ("france", "FR"),
+    ("united kingdom", "GB"), ("greece", "GR"), ("croatia", "HR"), ("hungary", "HU"),
+    ("ireland", "IE"), ("iceland", "IS"), ("italy", "IT"), ("italia", "IT"),
+    ("liechtenstein", "LI"), ("lithuania", "LT"), ("luxembourg", "LU"), ("latvia", "LV"),
+    ("monaco", "MC"), ("malta", "MT"), ("netherlands", "NL"), ("norway", "NO"),
+    ("poland", "PL"), ("portugal", "PT"), ("romania", "RO"), ("sweden", "SE"),
+    ("slovenia", "SI"), ("slovakia", "SK"), ("united states", "US"), ("usa", "US"),
+];
+
+/// ISO 3166-1 alpha-2 code of a country, given either its english (or native) name or the code itself
+pub(crate) fn iso_code(country: &str) -> Option<String> {
+    let country = country.trim();
+    if country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Some(country.to_ascii_uppercase());
+    }
+    let name = country.to_lowercase();
+    COUNTRY_CODES.iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, code)| code.to_string())
+}
@@ -0,0 +1,292 @@
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+use crate::payment::PaymentError;
+use crate::types::{Chain, CryptoWallet, Payment, PaymentMethod};
+
+impl Chain {
+    pub const ETHEREUM: Chain = Chain::Evm(1);
+
+    /// Human readable network name, what the payer has to pick in their wallet
+    pub fn name(&self) -> String {
+        match self {
+            Chain::Bitcoin => String::from("Bitcoin"),
+            Chain::Solana => String::from("Solana"),
+            Chain::Evm(chain_id) => match chain_id {
+                1 => String::from("Ethereum"),
+                10 => String::from("OP Mainnet"),
+                56 => String::from("BNB Smart Chain"),
+                137 => String::from("Polygon"),
+                8453 => String::from("Base"),
+                42161 => String::from("Arbitrum One"),
+                43114 => String::from("Avalanche C-Chain"),
+                11155111 => String::from("Sepolia"),
+                id => format!("EVM chain {}", id),
+            },
+        }
+    }
+
+    /// Network name, along with the chain id for EVM networks
+    pub fn description(&self) -> String {
+        match self {
+            Chain::Evm(chain_id) => format!("{} (chain ID {})", self.name(), chain_id),
+            _ => self.name(),
+        }
+    }
+
+    /// Symbol of the coin the chain pays its fees in, for the networks we know
+    pub fn native_currency(&self) -> Option<&'static str> {
+        match self {
+            Chain::Bitcoin => Some("BTC"),
+            Chain::Solana => Some("SOL"),
+            Chain::Evm(chain_id) => match chain_id {
+                1 | 10 | 8453 | 42161 | 11155111 => Some("ETH"),
+                56 => Some("BNB"),
+                137 => Some("POL"),
+                43114 => Some("AVAX"),
+                _ => None,
+            },
+        }
+    }
+
+    /// Block explorer showing transactions of the chain, for the networks we know one of
+    fn explorer(&self) -> Option<&'static str> {
+        match self {
+            Chain::Bitcoin => Some("https://mempool.space"),
+            Chain::Solana => Some("https://solscan.io"),
+            Chain::Evm(chain_id) => match chain_id {
+                1 => Some("https://etherscan.io"),
+                10 => Some("https://optimistic.etherscan.io"),
+                56 => Some("https://bscscan.com"),
+                137 => Some("https://polygonscan.com"),
+                8453 => Some("https://basescan.org"),
+                42161 => Some("https://arbiscan.io"),
+                43114 => Some("https://snowtrace.io"),
+                11155111 => Some("https://sepolia.etherscan.io"),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl Payment {
+    /// Chain the transaction was made on, the first accepted crypto network whose hash format it matches
+    pub fn tx_chain(&self) -> Option<Chain> {
+        let tx = self.tx.as_ref()?;
+        self.methods.iter().find_map(|method| match method {
+            PaymentMethod::Crypto(wallet) if validate_tx_hash(wallet.chain, tx).is_ok() => Some(wallet.chain),
+            _ => None,
+        })
+    }
+
+    /// Link to the transaction on a block explorer
+    pub fn tx_url(&self) -> Option<String> {
+        explorer_url(self.tx_chain()?, self.tx.as_ref()?)
+    }
+}
+
+impl CryptoWallet {
+    /// Checks the wallet and token addresses against the address format of the chain
+    pub fn validate(&self) -> Result<(), PaymentError> {
+        validate_address(self.chain, &self.address)?;
+        if let Some(token) = &self.token {
+            validate_address(self.chain, token)?;
+        }
+        Ok(())
+    }
+
+    /// Decimals of the currency, the configured ones or those of the well known currencies
+    pub fn decimals(&self) -> u8 {
+        self.decimals.unwrap_or(match self.currency.to_ascii_uppercase().as_str() {
+            "USDC" | "EURC" | "USDT" => 6,
+            "BTC" | "WBTC" => 8,
+            "SOL" => 9,
+            _ => 18,
+        })
+    }
+}
+
+/// Payment URI for a wallet, to be opened by a wallet app or encoded in a QR code.
+///
+/// Follows BIP-21 for bitcoin, EIP-681 for EVM networks and Solana Pay for solana.
+/// The amount is only included when the wallet's currency is the one the invoice is priced in.
+/// On EVM networks a wallet without a token contract can only ask for the native coin, so the
+/// amount is left out there unless the currency is that coin.
+pub fn payment_uri(wallet: &CryptoWallet, currency: &str, amount_due: u32, invoice_number: u16) -> String {
+    let amount = wallet.currency.eq_ignore_ascii_case(currency).then_some(amount_due);
+    let message = encode(&format!("Invoice {}", invoice_number));
+
+    match wallet.chain {
+        Chain::Bitcoin => {
+            let mut uri = format!("bitcoin:{}?message={}", wallet.address, message);
+            if let Some(amount) = amount {
+                uri.push_str(&format!("&amount={}", amount));
+            }
+            uri
+        },
+        Chain::Evm(chain_id) => {
+            let exponent = wallet.decimals();
+            match (&wallet.token, amount) {
+                (Some(token), Some(amount)) => format!(
+                    "ethereum:{}@{}/transfer?address={}&uint256={}e{}", token, chain_id, wallet.address, amount, exponent
+                ),
+                (Some(token), None) => format!("ethereum:{}@{}/transfer?address={}", token, chain_id, wallet.address),
+                (None, Some(amount)) if wallet.chain.native_currency()
+                    .is_some_and(|native| native.eq_ignore_ascii_case(&wallet.currency)) =>
+                    format!("ethereum:{}@{}?value={}e{}", wallet.address, chain_id, amount, exponent),
+                (None, _) => format!("ethereum:{}@{}", wallet.address, chain_id),
+            }
+        },
+        Chain::Solana => {
+            let mut params = vec![];
+            if let Some(amount) = amount {
+                params.push(format!("amount={}", amount));
+            }
+            if let Some(token) = &wallet.token {
+                params.push(format!("spl-token={}", token));
+            }
+            params.push(format!("message={}", message));
+            format!("solana:{}?{}", wallet.address, params.join("&"))
+        },
+    }
+}
+
+/// Validates an address in the format used by the chain:
+/// EIP-55 checksummed hex on EVM networks, a base58 public key on solana,
+/// and bech32/bech32m or base58check on bitcoin.
+pub fn validate_address(chain: Chain, address: &str) -> Result<(), PaymentError> {
+    let valid = match chain {
+        Chain::Evm(_) => is_evm_address(address),
+        Chain::Solana => base58_decode(address).is_some_and(|bytes| bytes.len() == 32),
+        Chain::Bitcoin => is_segwit_address(address) || is_base58check_address(address),
+    };
+
+    if valid { Ok(()) } else { Err(PaymentError::InvalidAddress(address.to_string())) }
+}
+
+/// Validates a transaction hash in the format used by the chain:
+/// 0x prefixed 32 byte hex on EVM networks, 32 byte hex on bitcoin and a base58 signature on solana.
+pub fn validate_tx_hash(chain: Chain, hash: &str) -> Result<(), PaymentError> {
+    let is_hex = |hex: &str| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit());
+    let valid = match chain {
+        Chain::Evm(_) => hash.strip_prefix("0x").is_some_and(is_hex),
+        Chain::Bitcoin => is_hex(hash),
+        Chain::Solana => base58_decode(hash).is_some_and(|bytes| bytes.len() == 64),
+    };
+
+    if valid { Ok(()) } else { Err(PaymentError::InvalidTransaction(hash.to_string())) }
+}
+
+/// Block explorer page of a transaction, None for chains without a known explorer
+pub fn explorer_url(chain: Chain, hash: &str) -> Option<String> {
+    chain.explorer().map(|explorer| format!("{}/tx/{}", explorer, hash))
+}
+
+/// 20 byte hex address, when it mixes upper and lower case it has to match its EIP-55 checksum
+fn is_evm_address(address: &str) -> bool {
+    let Some(hex) = address.strip_prefix("0x") else { return false };
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return false;
+    }
+    let lower = hex.to_ascii_lowercase();
+    if hex == lower || hex == hex.to_ascii_uppercase() {
+        return true;
+    }
+
+    // a letter is uppercase when the matching nibble of keccak256(lowercase address) is 8 or more
+    let hash = Keccak256::digest(lower.as_bytes());
+    hex.chars().enumerate().all(|(i, c)| {
+        let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+        !c.is_ascii_alphabetic() || c.is_ascii_uppercase() == (nibble >= 8)
+    })
+}
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn base58_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = vec![];
+    for c in text.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|a| *a == c)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, (carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+    // every leading '1' stands for a zero byte
+    let zeros = text.bytes().take_while(|c| *c == b'1').count();
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes);
+    if text.is_empty() { None } else { Some(decoded) }
+}
+
+/// Legacy P2PKH ("1...") and P2SH ("3...") addresses, with a double sha256 checksum
+fn is_base58check_address(address: &str) -> bool {
+    let Some(bytes) = base58_decode(address) else { return false };
+    if bytes.len() != 25 || (bytes[0] != 0x00 && bytes[0] != 0x05) {
+        return false;
+    }
+    let checksum = Sha256::digest(Sha256::digest(&bytes[..21]));
+    checksum[..4] == bytes[21..]
+}
+
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Native segwit addresses, bech32 for witness version 0 and bech32m for later versions (BIP-173/350)
+fn is_segwit_address(address: &str) -> bool {
+    if address.len() > 90 || (address != address.to_lowercase() && address != address.to_uppercase()) {
+        return false;
+    }
+    let address = address.to_lowercase();
+    let Some((hrp, data)) = address.rsplit_once('1') else { return false };
+    if hrp != "bc" || data.len() < 7 {
+        return false;
+    }
+    let Some(values) = data.bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|a| *a == c).map(|v| v as u8))
+        .collect::<Option<Vec<u8>>>() else { return false };
+
+    let mut checked: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    checked.push(0);
+    checked.extend(hrp.bytes().map(|c| c & 31));
+    checked.extend(values.iter());
+
+    let witness_version = values[0];
+    let expected = if witness_version == 0 { 1 } else { 0x2bc830a3 };
+    if witness_version > 16 || bech32_polymod(&checked) != expected {
+        return false;
+    }
+
+    // the program is what's left between the version and the checksum, in 5 bit groups
+    let program_bits = (values.len() - 7) * 5;
+    let program_len = program_bits / 8;
+    (2..=40).contains(&program_len)
+        && (witness_version != 0 || program_len == 20 || program_len == 32)
+        && program_bits % 8 < 5
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    values.iter().fold(1u32, |checksum, value| {
+        let top = checksum >> 25;
+        let mut checksum = ((checksum & 0x1ffffff) << 5) ^ *value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+        checksum
+    })
+}
+
+/// Percent encoding of URI query values, everything but unreserved characters is escaped
+fn encode(value: &str) -> String {
+    value.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
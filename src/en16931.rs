@@ -0,0 +1,619 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use crate::country::iso_code;
+use crate::payment::normalize;
+use crate::status::parse_date;
+use crate::types::{BankTransfer, ParsedInvoice, PaymentMethod, PersonalInfo, TaxCategory};
+
+/// Specification identifier (BT-24) of the core EN 16931 model, without any CIUS or extension
+pub const SPECIFICATION: &str = "urn:cen.eu:en16931:2017";
+/// Invoice type code (BT-3) of a commercial invoice, UNTDID 1001
+pub const COMMERCIAL_INVOICE: u16 = 380;
+/// Unit code (BT-130) for items counted in pieces, UN/ECE Recommendation 20
+pub const UNIT_PIECE: &str = "C62";
+/// Electronic address scheme (BT-34-1 / BT-49-1) of email addresses, from the EAS code list
+pub const EMAIL_SCHEME: &str = "EM";
+
+/// Invoice in the terms of the EN 16931 semantic model, with the business term each field maps to.
+///
+/// Amounts stay whole currency units, like in `ParsedInvoice`.
+#[derive(Clone, Debug)]
+pub struct SemanticInvoice {
+    /// BT-24
+    pub specification: String,
+    /// BT-1
+    pub number: String,
+    /// BT-2
+    pub issue_date: Option<NaiveDate>,
+    /// BT-3
+    pub type_code: u16,
+    /// BT-5
+    pub currency: String,
+    /// BT-9
+    pub due_date: Option<NaiveDate>,
+    /// BT-10, the Leitweg-ID for German public authorities
+    pub buyer_reference: Option<String>,
+    /// BT-23, business process the invoice is part of, like a Peppol profile
+    pub business_process: Option<String>,
+    /// BG-4
+    pub seller: Party,
+    /// BG-7
+    pub buyer: Party,
+    /// BG-13, name (BT-70) and address (BG-15) of where the goods are delivered
+    pub delivery: Option<Party>,
+    /// BG-16
+    pub payment_means: Vec<PaymentMeans>,
+    /// BG-22
+    pub totals: DocumentTotals,
+    /// BG-23
+    pub vat_breakdown: Vec<VatBreakdown>,
+    /// BG-25
+    pub lines: Vec<InvoiceLine>,
+}
+
+#[derive(Clone, Debug)]
+/// Seller (BG-4) or buyer (BG-7).
+pub struct Party {
+    /// BT-27 / BT-44
+    pub name: String,
+    /// BT-31 / BT-48
+    pub vat_id: Option<String>,
+    /// BT-34 / BT-49
+    pub electronic_address: Option<ElectronicAddress>,
+    /// BG-5 / BG-8
+    pub address: PostalAddress,
+    /// BG-6 / BG-9
+    pub contact: Option<PartyContact>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Address a party receives electronic invoices at (BT-34, BT-49).
+pub struct ElectronicAddress {
+    /// BT-34-1 / BT-49-1, EAS code like `EM` for email or `0088` for a GLN
+    pub scheme: String,
+    pub id: String,
+}
+
+impl ElectronicAddress {
+    /// Address written as `scheme:id`, none without a scheme or identifier
+    pub fn parse(address: &str) -> Option<ElectronicAddress> {
+        let (scheme, id) = address.trim().split_once(':')?;
+        (!scheme.is_empty() && !id.is_empty()).then(|| ElectronicAddress { scheme: scheme.to_string(), id: id.to_string() })
+    }
+}
+
+impl fmt::Display for ElectronicAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.scheme, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// Postal address of a party (BG-5, BG-8, BG-15).
+pub struct PostalAddress {
+    /// BT-35 / BT-50, street and house number
+    pub line_one: Option<String>,
+    /// BT-37 / BT-52
+    pub city: Option<String>,
+    /// BT-38 / BT-53
+    pub post_code: Option<String>,
+    /// BT-40 / BT-55, ISO 3166-1 alpha-2
+    pub country_code: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+/// Contact point of a party (BG-6, BG-9).
+pub struct PartyContact {
+    /// BT-41 / BT-56
+    pub name: Option<String>,
+    /// BT-42 / BT-57
+    pub phone: Option<String>,
+    /// BT-43 / BT-58
+    pub email: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+/// Way of paying (BG-16).
+pub struct PaymentMeans {
+    /// BT-81, UNTDID 4461
+    pub type_code: &'static str,
+    /// BT-83, reference the payer should quote
+    pub remittance_information: Option<String>,
+    /// BT-84, IBAN, PayPal account or wallet address
+    pub account: Option<String>,
+    /// BT-85
+    pub account_name: Option<String>,
+    /// BT-86
+    pub bic: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+/// Document level totals (BG-22).
+pub struct DocumentTotals {
+    /// BT-106
+    pub line_net_total: u64,
+    /// BT-109
+    pub tax_exclusive: u64,
+    /// BT-110
+    pub tax_total: u64,
+    /// BT-112
+    pub tax_inclusive: u64,
+    /// BT-113
+    pub paid: u64,
+    /// BT-115
+    pub amount_due: u64,
+}
+
+#[derive(Clone, Debug)]
+/// Taxable amount and tax of one category and rate (BG-23).
+pub struct VatBreakdown {
+    /// BT-118
+    pub category: TaxCategory,
+    /// BT-119
+    pub rate: u8,
+    /// BT-116
+    pub taxable_amount: u64,
+    /// BT-117
+    pub tax_amount: u64,
+    /// BT-120
+    pub exemption_reason: Option<String>,
+    /// BT-121, VATEX code list
+    pub exemption_reason_code: Option<&'static str>,
+}
+
+#[derive(Clone, Debug)]
+/// Invoice line (BG-25).
+pub struct InvoiceLine {
+    /// BT-126
+    pub id: String,
+    /// BT-129
+    pub quantity: u32,
+    /// BT-130
+    pub unit_code: &'static str,
+    /// BT-131, quantity times price, without tax
+    pub net_amount: u64,
+    /// BT-146, net price of one unit
+    pub price: u32,
+    /// BT-151
+    pub category: TaxCategory,
+    /// BT-152
+    pub rate: u8,
+    /// BT-153
+    pub name: String,
+    /// Tax of the line, not a business term but what the breakdown sums up
+    pub tax_amount: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Business rule of EN 16931 the invoice doesn't satisfy.
+pub struct RuleViolation {
+    /// Rule identifier, BR-xx, BR-CO-xx or BR-<category>-xx, or the business term for one that's
+    /// only mandatory in a CIUS
+    pub rule: String,
+    pub message: String,
+}
+
+impl fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.rule, self.message)
+    }
+}
+
+impl TaxCategory {
+    /// Exemption reason code (BT-121) and text (BT-120) that follow from the category alone.
+    /// Exempt supplies need the article they're exempt under, which can't be guessed.
+    pub fn exemption(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            TaxCategory::ReverseCharge => Some(("VATEX-EU-AE", "Reverse charge")),
+            TaxCategory::IntraCommunity => Some(("VATEX-EU-IC", "Intra-community supply")),
+            TaxCategory::Export => Some(("VATEX-EU-G", "Export outside the EU")),
+            TaxCategory::OutsideScope => Some(("VATEX-EU-O", "Not subject to VAT")),
+            TaxCategory::Standard | TaxCategory::ZeroRated | TaxCategory::Exempt => None,
+        }
+    }
+
+    /// Prefix the category's rules use, BR-<prefix>-xx
+    fn rule_prefix(&self) -> &'static str {
+        match self {
+            TaxCategory::Standard => "S",
+            TaxCategory::ZeroRated => "Z",
+            TaxCategory::Exempt => "E",
+            TaxCategory::ReverseCharge => "AE",
+            TaxCategory::IntraCommunity => "IC",
+            TaxCategory::Export => "G",
+            TaxCategory::OutsideScope => "O",
+        }
+    }
+}
+
+impl ParsedInvoice {
+    /// Maps the invoice onto the EN 16931 semantic model
+    pub fn to_en16931(&self) -> SemanticInvoice {
+        SemanticInvoice::from(self)
+    }
+}
+
+impl From<&ParsedInvoice> for SemanticInvoice {
+    fn from(invoice: &ParsedInvoice) -> Self {
+        let mut buyer = Party::from(&invoice.to);
+        if let Some(contact) = &invoice.contact {
+            buyer.contact = Some(PartyContact {
+                name: Some(contact.name.clone()),
+                phone: contact.phone.clone().or_else(|| invoice.to.phone.clone()),
+                email: contact.email.clone().or_else(|| non_empty(&invoice.to.email)),
+            });
+        }
+
+        let lines: Vec<InvoiceLine> = invoice.items.iter().enumerate().map(|(i, item)| {
+            let net_amount = item.unit_price as u64 * item.quantity as u64;
+            InvoiceLine {
+                id: (i + 1).to_string(),
+                quantity: item.quantity as u32,
+                unit_code: UNIT_PIECE,
+                net_amount,
+                price: item.unit_price,
+                category: item.tax_category,
+                rate: item.tax_percent,
+                name: item.description.clone(),
+                tax_amount: item.total as u64 - net_amount,
+            }
+        }).collect();
+
+        let mut vat_breakdown: Vec<VatBreakdown> = vec![];
+        for line in lines.iter() {
+            match vat_breakdown.iter_mut().find(|b| b.category == line.category && b.rate == line.rate) {
+                Some(breakdown) => {
+                    breakdown.taxable_amount += line.net_amount;
+                    breakdown.tax_amount += line.tax_amount;
+                },
+                None => vat_breakdown.push(VatBreakdown {
+                    category: line.category,
+                    rate: line.rate,
+                    taxable_amount: line.net_amount,
+                    tax_amount: line.tax_amount,
+                    exemption_reason: line.category.exemption().map(|(_, reason)| reason.to_string()),
+                    exemption_reason_code: line.category.exemption().map(|(code, _)| code),
+                }),
+            }
+        }
+
+        let amount_due = if invoice.payment.is_paid() { 0 } else { invoice.total.amount_due as u64 };
+        SemanticInvoice {
+            specification: SPECIFICATION.to_string(),
+            number: invoice.data.invoice_number.to_string(),
+            issue_date: parse_date(&invoice.data.issue_date),
+            type_code: COMMERCIAL_INVOICE,
+            currency: invoice.payment.currency.clone(),
+            due_date: invoice.due_date(),
+            buyer_reference: invoice.to.buyer_reference.as_deref().and_then(non_empty),
+            business_process: None,
+            seller: Party::from(&invoice.from),
+            buyer,
+            delivery: invoice.ship_to.as_ref().map(Party::from),
+            payment_means: invoice.payment.methods.iter().map(PaymentMeans::from).collect(),
+            totals: DocumentTotals {
+                line_net_total: lines.iter().map(|l| l.net_amount).sum(),
+                tax_exclusive: invoice.total.no_tax as u64,
+                tax_total: invoice.total.tax_amount as u64,
+                tax_inclusive: invoice.total.amount as u64,
+                paid: invoice.total.amount as u64 - amount_due,
+                amount_due,
+            },
+            vat_breakdown,
+            lines,
+        }
+    }
+}
+
+impl From<&PersonalInfo> for Party {
+    fn from(info: &PersonalInfo) -> Self {
+        Party {
+            name: info.name.clone(),
+            vat_id: info.vat_id.as_deref().and_then(non_empty),
+            electronic_address: info.endpoint_id.as_deref().and_then(ElectronicAddress::parse)
+                .or_else(|| non_empty(&info.email).map(|id| ElectronicAddress { scheme: EMAIL_SCHEME.to_string(), id })),
+            address: PostalAddress {
+                line_one: non_empty(&format!("{} {}", info.addr_one.trim(), info.addr_two.trim())),
+                // the state field holds the city in the invoice files
+                city: non_empty(&info.state),
+                post_code: non_empty(&info.postal),
+                country_code: iso_code(&info.country),
+            },
+            contact: match (non_empty(&info.email), info.phone.as_deref().and_then(non_empty)) {
+                (None, None) => None,
+                (email, phone) => Some(PartyContact { name: None, phone, email }),
+            },
+        }
+    }
+}
+
+impl From<Party> for PersonalInfo {
+    /// Reverse of the mapping onto a party, the house number is split off the street again when
+    /// the street ends with one. Countries stay ISO codes.
+    fn from(party: Party) -> Self {
+        let street = party.address.line_one.unwrap_or_default();
+        let (addr_one, addr_two) = match street.rsplit_once(' ') {
+            Some((street, number)) if number.starts_with(|c: char| c.is_ascii_digit()) => {
+                (street.to_string(), number.to_string())
+            },
+            _ => (street, String::new()),
+        };
+        let (email, endpoint_id) = match party.electronic_address {
+            Some(address) if address.scheme == EMAIL_SCHEME => (Some(address.id), None),
+            Some(address) => (None, Some(address.to_string())),
+            None => (None, None),
+        };
+        let (phone, email) = match party.contact {
+            Some(contact) => (contact.phone, contact.email.or(email)),
+            None => (None, email),
+        };
+        PersonalInfo {
+            email: email.unwrap_or_default(),
+            name: party.name,
+            addr_one,
+            addr_two,
+            postal: party.address.post_code.unwrap_or_default(),
+            state: party.address.city.unwrap_or_default(),
+            country: party.address.country_code.unwrap_or_default(),
+            vat_id: party.vat_id,
+            phone,
+            buyer_reference: None,
+            endpoint_id,
+        }
+    }
+}
+
+impl PaymentMeans {
+    /// Payment method the means stand for, none for means without an account or that have no
+    /// matching method. Transfers without an account name are payable to `payee`.
+    pub fn to_method(&self, payee: &str) -> Option<PaymentMethod> {
+        let account = self.account.clone()?;
+        match self.type_code {
+            "30" | "31" | "42" | "58" => Some(PaymentMethod::BankTransfer(BankTransfer {
+                iban: normalize(&account),
+                bic: self.bic.clone(),
+                account_holder: self.account_name.clone().unwrap_or(payee.to_string()),
+                bank_name: None,
+                reference: self.remittance_information.clone(),
+            })),
+            "48" | "54" | "55" => Some(PaymentMethod::CardLink { url: account }),
+            "68" => Some(PaymentMethod::PayPal { account }),
+            _ => None,
+        }
+    }
+
+    /// Type code as found in a document, matched against the codes the mapping uses
+    pub fn type_code_of(code: &str) -> &'static str {
+        TYPE_CODES.iter().find(|c| **c == code.trim()).copied().unwrap_or("ZZZ")
+    }
+}
+
+/// UNTDID 4461 payment means codes read back from documents
+const TYPE_CODES: [&str; 9] = ["30", "31", "42", "48", "54", "55", "58", "68", "ZZZ"];
+
+#[cfg(any(feature = "ubl", feature = "cii"))]
+use crate::types::ItemRaw;
+
+/// Line item read from the text of an xml invoice, `Err` names the field whose value is invalid
+#[cfg(any(feature = "ubl", feature = "cii"))]
+pub(crate) fn parse_item(
+    name: String,
+    quantity: &str,
+    price: &str,
+    percent: Option<&str>,
+    category: Option<&str>,
+) -> Result<ItemRaw, (&'static str, String)> {
+    let tax_percent = match percent {
+        Some(percent) => whole_number::<u8>(percent)
+            .filter(|p| *p <= 100)
+            .ok_or(("tax rate", percent.to_string()))?,
+        None => 0,
+    };
+    Ok(ItemRaw {
+        description: name,
+        quantity: whole_number(quantity).ok_or(("quantity", quantity.to_string()))?,
+        amount: price.trim().parse::<f64>().ok()
+            .filter(|a| (0.0..=u32::MAX as f64).contains(a))
+            .map(|a| a.round() as u32)
+            .ok_or(("price", price.to_string()))?,
+        tax_percent,
+        tax_category: match category {
+            Some(code) => TaxCategory::from_code(code).ok_or(("tax category", code.to_string()))?,
+            None => TaxCategory::for_rate(tax_percent),
+        },
+    })
+}
+
+/// Decimal of a document that the crate can only hold as an integer, like "2" or "19.00". Fractions
+/// and values out of the integer's range are `None`, rounding them would change the invoice.
+#[cfg(any(feature = "ubl", feature = "cii"))]
+fn whole_number<T: TryFrom<u64>>(text: &str) -> Option<T> {
+    let value = text.trim().parse::<f64>().ok()?;
+    if value < 0.0 || value.fract() != 0.0 || value > u64::MAX as f64 {
+        return None;
+    }
+    T::try_from(value as u64).ok()
+}
+
+impl From<&PaymentMethod> for PaymentMeans {
+    fn from(method: &PaymentMethod) -> Self {
+        let means = |type_code, account: &str| PaymentMeans {
+            type_code,
+            remittance_information: None,
+            account: non_empty(account),
+            account_name: None,
+            bic: None,
+        };
+        match method {
+            PaymentMethod::BankTransfer(bank) => PaymentMeans {
+                // SEPA credit transfer for IBANs of SEPA countries, plain credit transfer otherwise
+                type_code: if bank.is_sepa() { "58" } else { "30" },
+                remittance_information: bank.reference.clone(),
+                account: non_empty(&normalize(&bank.iban)),
+                account_name: non_empty(&bank.account_holder),
+                bic: bank.bic.clone(),
+            },
+            PaymentMethod::CardLink { url } => means("54", url),
+            PaymentMethod::PayPal { account } => means("68", account),
+            PaymentMethod::Crypto(wallet) => means("ZZZ", &wallet.address),
+        }
+    }
+}
+
+impl SemanticInvoice {
+    /// Mandatory business terms and business rules the invoice doesn't satisfy, empty when it's compliant.
+    ///
+    /// Only rules our invoices can break are checked, terms that are always filled in aren't reported.
+    pub fn validate(&self) -> Vec<RuleViolation> {
+        let mut violations = vec![];
+        let mut check = |ok: bool, rule: &str, message: &str| {
+            if !ok {
+                violations.push(RuleViolation { rule: rule.to_string(), message: message.to_string() });
+            }
+        };
+
+        check(!self.specification.is_empty(), "BR-01", "specification identifier (BT-24) missing");
+        check(!self.number.is_empty(), "BR-02", "invoice number (BT-1) missing");
+        check(self.issue_date.is_some(), "BR-03", "issue date (BT-2) missing or unreadable");
+        check(self.type_code != 0, "BR-04", "invoice type code (BT-3) missing");
+        check(!self.currency.is_empty(), "BR-05", "invoice currency code (BT-5) missing");
+        check(
+            self.currency.len() == 3 && self.currency.chars().all(|c| c.is_ascii_uppercase()),
+            "BR-CL-04", "invoice currency code (BT-5) isn't an ISO 4217 code",
+        );
+        check(!self.seller.name.trim().is_empty(), "BR-06", "seller name (BT-27) missing");
+        check(!self.buyer.name.trim().is_empty(), "BR-07", "buyer name (BT-44) missing");
+        check(!self.seller.address.is_empty(), "BR-08", "seller postal address (BG-5) missing");
+        check(self.seller.address.country_code.is_some(), "BR-09", "seller country code (BT-40) missing or unknown");
+        check(!self.buyer.address.is_empty(), "BR-10", "buyer postal address (BG-8) missing");
+        check(self.buyer.address.country_code.is_some(), "BR-11", "buyer country code (BT-55) missing or unknown");
+        check(!self.lines.is_empty(), "BR-16", "invoice has no lines (BG-25)");
+        for line in self.lines.iter() {
+            check(!line.name.trim().is_empty(), "BR-25", &format!("line {} has no item name (BT-153)", line.id));
+        }
+        for means in self.payment_means.iter().filter(|m| matches!(m.type_code, "30" | "58")) {
+            check(means.account.is_some(), "BR-61", "credit transfer without payment account identifier (BT-84)");
+        }
+        let addresses = [("BR-62", "BT-34-1", &self.seller), ("BR-63", "BT-49-1", &self.buyer)];
+        for (rule, term, party) in addresses {
+            if let Some(address) = &party.electronic_address {
+                check(!address.scheme.is_empty(), rule, &format!("electronic address without scheme identifier ({})", term));
+            }
+        }
+        for party in [&self.seller, &self.buyer] {
+            if let Some(vat_id) = &party.vat_id {
+                check(
+                    vat_id.len() > 2 && vat_id.chars().take(2).all(|c| c.is_ascii_uppercase()),
+                    "BR-CO-09", &format!("VAT identifier {} doesn't start with a country code", vat_id),
+                );
+            }
+        }
+
+        // totals have to add up
+        let line_total: u64 = self.lines.iter().map(|l| l.net_amount).sum();
+        let tax_total: u64 = self.vat_breakdown.iter().map(|b| b.tax_amount).sum();
+        check(self.totals.line_net_total == line_total, "BR-CO-10", "sum of line net amounts (BT-106) doesn't add up");
+        check(
+            self.totals.tax_exclusive == self.totals.line_net_total, "BR-CO-13",
+            "total without VAT (BT-109) isn't the sum of the lines",
+        );
+        check(self.totals.tax_total == tax_total, "BR-CO-14", "VAT total (BT-110) isn't the sum of the breakdown");
+        check(
+            self.totals.tax_inclusive == self.totals.tax_exclusive + self.totals.tax_total, "BR-CO-15",
+            "total with VAT (BT-112) isn't the total without VAT plus the VAT total",
+        );
+        check(
+            self.totals.amount_due + self.totals.paid == self.totals.tax_inclusive, "BR-CO-16",
+            "amount due (BT-115) isn't the total with VAT minus the paid amount",
+        );
+        check(!self.vat_breakdown.is_empty(), "BR-CO-18", "invoice has no VAT breakdown (BG-23)");
+        for breakdown in self.vat_breakdown.iter() {
+            // amounts are whole units, a difference below one unit can't be expressed
+            let expected = breakdown.taxable_amount as f64 * breakdown.rate as f64 / 100.0;
+            check(
+                (breakdown.tax_amount as f64 - expected).abs() < 1.0, "BR-CO-17",
+                &format!("VAT of the {}% {} breakdown isn't its taxable amount times the rate", breakdown.rate, breakdown.category.code()),
+            );
+        }
+
+        for category in TaxCategory::ALL {
+            let lines: Vec<&InvoiceLine> = self.lines.iter().filter(|l| l.category == category).collect();
+            if lines.is_empty() {
+                continue;
+            }
+            violations.extend(self.category_violations(category, &lines));
+        }
+        violations
+    }
+
+    /// Rules of the BR-S, BR-Z, BR-E, BR-AE, BR-IC, BR-G and BR-O groups
+    fn category_violations(&self, category: TaxCategory, lines: &[&InvoiceLine]) -> Vec<RuleViolation> {
+        let mut violations = vec![];
+        let rule = |number: &str| format!("BR-{}-{}", category.rule_prefix(), number);
+        let name = category.label();
+
+        let seller_vat = self.seller.vat_id.is_some();
+        let buyer_vat = self.buyer.vat_id.is_some();
+        match category {
+            TaxCategory::OutsideScope if seller_vat || buyer_vat => {
+                violations.push(RuleViolation {
+                    rule: rule("02"),
+                    message: format!("{} lines can't go with seller or buyer VAT identifiers", name),
+                });
+            },
+            TaxCategory::OutsideScope => {},
+            TaxCategory::ReverseCharge | TaxCategory::IntraCommunity if !(seller_vat && buyer_vat) => {
+                violations.push(RuleViolation {
+                    rule: rule("02"),
+                    message: format!("{} lines need the seller (BT-31) and buyer (BT-48) VAT identifier", name),
+                });
+            },
+            _ if !seller_vat => {
+                violations.push(RuleViolation {
+                    rule: rule("02"),
+                    message: format!("{} lines need the seller VAT identifier (BT-31)", name),
+                });
+            },
+            _ => {},
+        }
+
+        for line in lines {
+            let ok = match category {
+                TaxCategory::Standard => line.rate > 0,
+                _ => line.rate == 0,
+            };
+            if !ok {
+                violations.push(RuleViolation {
+                    rule: rule("05"),
+                    message: format!("line {} is {} at {}%", line.id, name.to_lowercase(), line.rate),
+                });
+            }
+        }
+
+        if matches!(category, TaxCategory::Standard | TaxCategory::ZeroRated) {
+            return violations;
+        }
+        for breakdown in self.vat_breakdown.iter().filter(|b| b.category == category) {
+            if breakdown.exemption_reason.is_none() && breakdown.exemption_reason_code.is_none() {
+                violations.push(RuleViolation {
+                    rule: rule("10"),
+                    message: format!("{} VAT breakdown needs an exemption reason (BT-120) or code (BT-121)", name),
+                });
+            }
+        }
+        violations
+    }
+}
+
+impl PostalAddress {
+    pub fn is_empty(&self) -> bool {
+        self.line_one.is_none() && self.city.is_none() && self.post_code.is_none() && self.country_code.is_none()
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use crate::cii::{invoice_xml, parse_invoice, CiiError, CiiProfile};
+use crate::en16931::SemanticInvoice;
+use crate::pdfa::{attachments, Archive, Attachment, DocumentInfo, PdfA, XmpSchema};
+use crate::types::RawInvoice;
+
+/// Name the CII xml is attached under
+pub const FILE_NAME: &str = "factur-x.xml";
+/// Namespace of the Factur-X xmp properties
+pub const NAMESPACE: &str = "urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#";
+/// Names earlier ZUGFeRD versions and XRechnung attach the xml under, accepted when reading
+const OTHER_FILE_NAMES: [&str; 2] = ["zugferd-invoice.xml", "xrechnung.xml"];
+
+/// Hybrid pdf that can't be read back into an invoice.
+#[derive(Debug)]
+pub enum FacturXError {
+    Pdf(lopdf::Error),
+    /// No xml invoice is attached to the pdf
+    MissingAttachment,
+    Cii(CiiError),
+}
+
+impl fmt::Display for FacturXError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FacturXError::Pdf(e) => write!(f, "invalid pdf: {}", e),
+            FacturXError::MissingAttachment => write!(f, "no {} attached to the pdf", FILE_NAME),
+            FacturXError::Cii(e) => write!(f, "invalid {}: {}", FILE_NAME, e),
+        }
+    }
+}
+
+impl std::error::Error for FacturXError {}
+
+/// Turns a rendered pdf of the invoice into a Factur-X / ZUGFeRD hybrid: a PDF/A-3 with the CII
+/// xml of the profile attached as `factur-x.xml`, and declared in the xmp metadata.
+///
+/// The pdf has to be rendered with genpdf's minimal conformance, see `pdfa::Archive`.
+pub(crate) fn embed(pdf: Vec<u8>, invoice: &SemanticInvoice, profile: CiiProfile, info: DocumentInfo) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let archive = Archive {
+        level: PdfA::A3b,
+        info,
+        attachments: vec![Attachment {
+            name: FILE_NAME.to_string(),
+            mime: "text/xml",
+            description: format!("Factur-X invoice, {} profile", profile.name()),
+            relationship: relationship(profile),
+            data: invoice_xml(invoice, profile).into_bytes(),
+        }],
+        schemas: vec![XmpSchema {
+            name: "Factur-X PDFA Extension Schema",
+            namespace: NAMESPACE,
+            prefix: "fx",
+            properties: vec![
+                ("DocumentFileName", FILE_NAME.to_string(), "name of the embedded XML invoice file"),
+                ("DocumentType", String::from("INVOICE"), "INVOICE"),
+                ("Version", String::from("1.0"), "The actual version of the Factur-X XML schema"),
+                ("ConformanceLevel", profile.name().to_string(), "The conformance level of the embedded Factur-X data"),
+            ],
+        }],
+    };
+    archive.convert(pdf)
+}
+
+/// The profiles without lines only carry data about the invoice, the rest can stand in for it
+fn relationship(profile: CiiProfile) -> &'static str {
+    match profile {
+        CiiProfile::Minimum | CiiProfile::BasicWL => "Data",
+        _ => "Alternative",
+    }
+}
+
+/// The xml invoice attached to a Factur-X, ZUGFeRD or XRechnung pdf
+pub fn attached_xml(pdf: &[u8]) -> Result<String, FacturXError> {
+    attachments(pdf).map_err(FacturXError::Pdf)?
+        .into_iter()
+        .find(|(name, _)| name == FILE_NAME || OTHER_FILE_NAMES.contains(&name.as_str()))
+        .map(|(_, data)| String::from_utf8_lossy(&data).into_owned())
+        .ok_or(FacturXError::MissingAttachment)
+}
+
+/// Reads the invoice attached to a hybrid pdf, it has to be of the Basic profile or up
+pub fn parse_pdf(pdf: &[u8]) -> Result<RawInvoice, FacturXError> {
+    parse_invoice(&attached_xml(pdf)?).map_err(FacturXError::Cii)
+}
+
+#[cfg(feature = "from_file")]
+impl crate::InvoiceReader {
+    /// Read the CII invoice attached to a Factur-X / ZUGFeRD pdf and convert it to raw type
+    pub fn from_facturx(file: String) -> Result<Vec<RawInvoice>, Box<dyn std::error::Error>> {
+        let raw = parse_pdf(&std::fs::read(file)?)?;
+        raw.payment.validate()?;
+        Ok(vec![raw])
+    }
+}
@@ -0,0 +1,50 @@
+use crate::payment::{normalize, PaymentError};
+use crate::qr_bill::validate_creditor_reference;
+use crate::types::BankTransfer;
+
+/// Builds the EPC069-12 ("GiroCode") payload for a SEPA credit transfer.
+///
+/// The amount is taken in whole units of `currency`, which has to be EUR for SEPA,
+/// the bank reference is used as remittance information, falling back to the invoice number.
+pub fn epc_payload(
+    bank: &BankTransfer,
+    currency: &str,
+    amount_due: u32,
+    invoice_number: u16,
+) -> Result<String, PaymentError> {
+    if !currency.eq_ignore_ascii_case("EUR") {
+        return Err(PaymentError::QrPayload(format!("GiroCode requires EUR, not {}", currency)));
+    }
+    if amount_due == 0 || amount_due > 999_999_999 {
+        return Err(PaymentError::QrPayload(format!("GiroCode amount out of range: {}", amount_due)));
+    }
+    bank.validate()?;
+
+    let reference = bank.reference.clone().unwrap_or(format!("Invoice {}", invoice_number));
+    // creditor references (ISO 11649) go in the structured field, anything else is free text
+    let (structured, unstructured) = if validate_creditor_reference(&reference).is_ok() {
+        (normalize(&reference), String::new())
+    } else {
+        (String::new(), truncate(&reference, 140))
+    };
+
+    let lines = [
+        String::from("BCD"),
+        String::from("002"),
+        String::from("1"),
+        String::from("SCT"),
+        bank.bic.clone().unwrap_or_default().replace(' ', ""),
+        truncate(&bank.account_holder, 70),
+        bank.iban.replace(' ', "").to_ascii_uppercase(),
+        format!("EUR{}.00", amount_due),
+        String::new(),
+        structured,
+        unstructured,
+    ];
+
+    Ok(lines.join("\n").trim_end().to_string())
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
@@ -18,8 +18,8 @@ pub trait Invoice {
         };
 
         // add to items 
-        for (_, item) in data.items.iter().enumerate() { 
-            let tax_value = (item.amount as u32 * item.tax_percent as u32 / 100) as u32;
+        for item in data.items.iter() { 
+            let tax_value = item.amount * item.tax_percent as u32 / 100;
             let item_total = (item.amount + tax_value) * item.quantity as u32;
 
             let new_item = Item {
@@ -27,23 +27,23 @@ pub trait Invoice {
                 quantity: item.quantity,
                 unit_price: item.amount,
                 tax_percent: item.tax_percent,
+                tax_category: item.tax_category,
                 total: item_total,
             };
 
-            total.no_tax = total.no_tax + (new_item.unit_price * new_item.quantity as u32);
-            total.tax_amount = total.tax_amount + (tax_value * new_item.quantity as u32);
-            total.amount = total.amount + item_total;
-            total.amount_due = total.amount_due + item_total;
+            total.no_tax += new_item.unit_price * new_item.quantity as u32;
+            total.tax_amount += tax_value * new_item.quantity as u32;
+            total.amount += item_total;
+            total.amount_due += item_total;
 
             items.push(new_item);
         }
         
-        let mut payment = data.payment.clone();
-        payment.tx = format!("\"{}\"", payment.tx).to_string();
-
         ParsedInvoice {
             from: data.from.clone(),
             to: data.to,
+            ship_to: data.ship_to,
+            contact: data.contact,
             items,
             total,
             payment: data.payment.clone(),
@@ -59,6 +59,36 @@ pub trait ExportsHTML {
     fn to_html(self, file_name: String) -> Result<(), Box<dyn std::error::Error>>;
 }
 
+/// Allows an invoice format to be rendered as plain text or markdown, for email bodies and chat tools
+pub trait ExportsText {
+    fn to_text(self, file_name: String, format: crate::text::TextFormat) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+#[cfg(feature = "ubl")]
+/// Allows an invoice format to be exported as a UBL 2.1 xml invoice
+pub trait ExportsUBL {
+    fn to_ubl(self, file_name: String) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+#[cfg(feature = "cii")]
+/// Allows an invoice format to be exported as a UN/CEFACT cross industry invoice, of the given profile
+pub trait ExportsCII {
+    fn to_cii(self, file_name: String, profile: crate::cii::CiiProfile) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+#[cfg(feature = "ubl")]
+/// Allows an invoice format to be exported as a Peppol BIS Billing 3.0 invoice, wrapped in the
+/// Standard Business Document Header an access point sends on
+pub trait ExportsPeppol {
+    fn to_peppol(self, file_name: String) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+#[cfg(any(feature = "ubl", feature = "cii"))]
+/// Allows an invoice format to be exported as an XRechnung for German public authorities, in either syntax
+pub trait ExportsXRechnung {
+    fn to_xrechnung(self, file_name: String, syntax: crate::xrechnung::Syntax) -> Result<(), Box<dyn std::error::Error>>;
+}
+
 #[cfg(feature = "pdf")]
 /// Allows an invoice format to be formatted into a PDF file
 pub trait ExportsPDF { 
@@ -78,7 +108,7 @@ pub trait ExportsPDF {
         }).collect();
         
         Ok(genpdf::fonts::FontFamily {
-            regular: vars.get(0).unwrap().clone(),
+            regular: vars.first().unwrap().clone(),
             bold: vars.get(1).unwrap().clone(),
             italic: vars.get(2).unwrap().clone(),
             bold_italic: vars.get(3).unwrap().clone(),
@@ -91,6 +121,7 @@ pub trait ExportsPDF {
 /// Some open source fonts are provided in this crate to make it easier to run to_pdf 
 struct Fonts;
 
+#[allow(dead_code)]
 enum Liberation {
     Mono,
     Sans,
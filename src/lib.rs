@@ -1,11 +1,58 @@
 #![doc= include_str!("../readme.md")]
 
+pub mod catalog;
+mod country;
+#[cfg(feature = "pdf")]
+mod links;
+pub mod crypto;
+pub mod en16931;
 mod invoice;
 mod reader;
 mod simple_invoice;
+pub mod girocode;
+pub mod payment;
+#[cfg(any(feature = "pdf", feature = "html"))]
+mod qr;
+pub mod qr_bill;
+pub mod status;
+pub mod aging;
+pub mod vat;
+#[cfg(feature = "from_file")]
+pub mod audit;
+#[cfg(feature = "from_file")]
+pub mod store;
+#[cfg(feature = "from_file")]
+pub mod reconcile;
 pub mod types;
+pub mod text;
+#[cfg(feature = "ubl")]
+pub mod ubl;
+#[cfg(feature = "cii")]
+pub mod cii;
+#[cfg(any(feature = "ubl", feature = "cii"))]
+pub mod xrechnung;
+#[cfg(feature = "ubl")]
+pub mod peppol;
+#[cfg(feature = "pdf")]
+pub mod pdfa;
+#[cfg(feature = "sign")]
+pub mod signature;
+#[cfg(feature = "sign")]
+mod cms;
+#[cfg(all(feature = "pdf", feature = "cii"))]
+pub mod facturx;
+#[cfg(any(feature = "pdf", feature = "ubl", feature = "cii"))]
+mod xml;
 
-pub use invoice::{Invoice, ExportsPDF, ExportsHTML};
-pub use reader::reader::InvoiceReader;
+pub use invoice::{Invoice, ExportsPDF, ExportsHTML, ExportsText};
+#[cfg(feature = "ubl")]
+pub use invoice::ExportsUBL;
+#[cfg(feature = "cii")]
+pub use invoice::ExportsCII;
+#[cfg(any(feature = "ubl", feature = "cii"))]
+pub use invoice::ExportsXRechnung;
+#[cfg(feature = "ubl")]
+pub use invoice::ExportsPeppol;
+pub use reader::reader::{InvoiceReader, InvoiceWriter};
 pub use simple_invoice::SimpleInvoice;
 
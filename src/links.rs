@@ -0,0 +1,137 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use genpdf::{
+    error::Error, render::Area, style::Style, Context, Element, Mm, PageDecorator, RenderResult,
+    SimplePageDecorator,
+};
+use lopdf::{dictionary, Object};
+
+/// genpdf can't create link annotations, so elements that should be clickable record where they
+/// ended up while rendering, and the links are added to the rendered pdf afterwards with lopdf.
+///
+/// That requires the link elements to be direct children of the document (or of a vertical
+/// layout spanning the page width), since an area doesn't tell its position on the page.
+/// Elements can also just be tracked without a link, to place other annotations over them.
+#[derive(Clone, Default)]
+pub(crate) struct LinkTracker {
+    page: Rc<Cell<usize>>,
+    /// page margin in mm, the same on every side
+    margin: Rc<Cell<Mm>>,
+    links: Rc<RefCell<Vec<LinkArea>>>,
+}
+
+struct LinkArea {
+    page: usize,
+    /// None for areas that are only tracked
+    url: Option<String>,
+    /// lower left and upper right corner, in mm from the bottom left of the page
+    rect: [f64; 4],
+}
+
+impl LinkTracker {
+    /// Page decorator that keeps count of the pages, so links know which page they're on
+    pub(crate) fn decorator(&self, margin: impl Into<Mm>) -> CountingDecorator {
+        let mut inner = SimplePageDecorator::new();
+        let margin = margin.into();
+        inner.set_margins(margin);
+        self.margin.set(margin);
+        CountingDecorator { inner, page: self.page.clone() }
+    }
+
+    /// Wraps an element, making the area it's rendered in link to `url`
+    pub(crate) fn link<E: Element>(&self, element: E, url: impl Into<String>) -> Linked<E> {
+        Linked { element, url: Some(url.into()), tracker: self.clone() }
+    }
+
+    /// Wraps an element, recording the area it's rendered in without linking it
+    #[cfg_attr(not(feature = "sign"), allow(dead_code))]
+    pub(crate) fn track<E: Element>(&self, element: E) -> Linked<E> {
+        Linked { element, url: None, tracker: self.clone() }
+    }
+
+    /// Page and lower left and upper right corner in mm of the areas of tracked elements
+    #[cfg_attr(not(feature = "sign"), allow(dead_code))]
+    pub(crate) fn tracked(&self) -> Vec<(usize, [f64; 4])> {
+        self.links.borrow().iter().filter(|l| l.url.is_none()).map(|l| (l.page, l.rect)).collect()
+    }
+
+    /// Adds the recorded links as URI annotations to the rendered pdf
+    pub(crate) fn annotate(&self, pdf: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let links = self.links.borrow();
+        if links.iter().all(|l| l.url.is_none()) {
+            return Ok(pdf);
+        }
+
+        let mut doc = lopdf::Document::load_mem(&pdf)?;
+        let pages = doc.get_pages();
+        // pdf user space is in points
+        let pt = |mm: f64| Object::Real(mm * 72.0 / 25.4);
+
+        for link in links.iter() {
+            let Some(url) = &link.url else { continue };
+            let Some(page_id) = pages.get(&(link.page as u32)) else { continue };
+            let annotation = doc.add_object(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Link",
+                "Rect" => link.rect.iter().map(|v| pt(*v)).collect::<Vec<Object>>(),
+                "Border" => vec![0.into(), 0.into(), 0.into()],
+                // printable, PDF/A doesn't allow annotations without it
+                "F" => 4,
+                "A" => dictionary! {
+                    "S" => "URI",
+                    "URI" => Object::string_literal(url.clone()),
+                },
+            });
+
+            let page = doc.get_object_mut(*page_id)?.as_dict_mut()?;
+            match page.get_mut(b"Annots") {
+                Ok(Object::Array(annots)) => annots.push(annotation.into()),
+                _ => page.set("Annots", vec![Object::from(annotation)]),
+            }
+        }
+
+        let mut out = Vec::new();
+        doc.save_to(&mut out)?;
+        Ok(out)
+    }
+}
+
+/// SimplePageDecorator that counts the pages it decorates
+pub(crate) struct CountingDecorator {
+    inner: SimplePageDecorator,
+    page: Rc<Cell<usize>>,
+}
+
+impl PageDecorator for CountingDecorator {
+    fn decorate_page<'a>(&mut self, context: &Context, area: Area<'a>, style: Style) -> Result<Area<'a>, Error> {
+        self.page.set(self.page.get() + 1);
+        self.inner.decorate_page(context, area, style)
+    }
+}
+
+/// Element rendered as usual, whose area is recorded as a link
+pub(crate) struct Linked<E: Element> {
+    element: E,
+    url: Option<String>,
+    tracker: LinkTracker,
+}
+
+impl<E: Element> Element for Linked<E> {
+    fn render(&mut self, context: &Context, area: Area<'_>, style: Style) -> Result<RenderResult, Error> {
+        let remaining = area.size();
+        let result = self.element.render(context, area, style)?;
+
+        // the area always reaches down to the bottom margin, which gives its top on the page
+        let margin = self.tracker.margin.get();
+        let top = margin + remaining.height;
+        let left = margin;
+        self.tracker.links.borrow_mut().push(LinkArea {
+            page: self.tracker.page.get(),
+            url: self.url.clone(),
+            rect: [left, top - result.size.height, left + result.size.width, top].map(f64::from),
+        });
+        Ok(result)
+    }
+}
+
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use crate::types::{BankTransfer, Payment, PaymentMethod};
+
+/// Payment data that can't be used to actually pay the invoice.
+#[derive(Debug)]
+pub enum PaymentError {
+    InvalidIban(String),
+    InvalidBic(String),
+    /// Wallet or token address that doesn't match the format of its chain
+    InvalidAddress(String),
+    /// Payment reference with a wrong format or check digits
+    InvalidReference(String),
+    /// A payment QR code can't be built from the invoice data
+    QrPayload(String),
+    /// Transaction hash that doesn't belong to any of the accepted chains
+    InvalidTransaction(String),
+}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentError::InvalidIban(iban) => write!(f, "invalid IBAN: {}", iban),
+            PaymentError::InvalidBic(bic) => write!(f, "invalid BIC: {}", bic),
+            PaymentError::InvalidAddress(address) => write!(f, "invalid wallet address: {}", address),
+            PaymentError::InvalidReference(reference) => write!(f, "invalid payment reference: {}", reference),
+            PaymentError::QrPayload(reason) => write!(f, "can't build payment QR code: {}", reason),
+            PaymentError::InvalidTransaction(tx) => write!(f, "invalid transaction hash: {}", tx),
+        }
+    }
+}
+
+impl std::error::Error for PaymentError {}
+
+impl Payment {
+    /// Validates every accepted payment method, and the transaction hash if there is one
+    pub fn validate(&self) -> Result<(), PaymentError> {
+        self.methods.iter().try_for_each(PaymentMethod::validate)?;
+        self.validate_tx()
+    }
+
+    /// Checks that the transaction hash, if there is one, belongs to one of the accepted chains
+    pub fn validate_tx(&self) -> Result<(), PaymentError> {
+        if let Some(tx) = &self.tx
+            && self.tx_chain().is_none()
+        {
+            return Err(PaymentError::InvalidTransaction(tx.clone()));
+        }
+        Ok(())
+    }
+
+    /// Whether the invoice has been paid, either on-chain or by a recorded settlement
+    pub fn is_paid(&self) -> bool {
+        self.tx.is_some() || self.settled_on.is_some()
+    }
+}
+
+impl PaymentMethod {
+    /// Checks the account details of bank transfers and crypto wallets
+    pub fn validate(&self) -> Result<(), PaymentError> {
+        match self {
+            PaymentMethod::BankTransfer(bank) => bank.validate(),
+            PaymentMethod::Crypto(wallet) => wallet.validate(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Short name of the method, used as its heading when rendering
+    pub fn title(&self) -> String {
+        match self {
+            PaymentMethod::BankTransfer(_) => String::from("Bank transfer"),
+            PaymentMethod::CardLink { .. } => String::from("Card"),
+            PaymentMethod::PayPal { .. } => String::from("PayPal"),
+            PaymentMethod::Crypto(wallet) => format!("{} on {}", wallet.currency, wallet.chain.name()),
+        }
+    }
+}
+
+impl BankTransfer {
+    /// Checks the IBAN checksum and the BIC format, if one is given
+    pub fn validate(&self) -> Result<(), PaymentError> {
+        validate_iban(&self.iban)?;
+        if let Some(bic) = &self.bic {
+            validate_bic(bic)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the IBAN belongs to a country of the SEPA scheme
+    pub fn is_sepa(&self) -> bool {
+        let iban = normalize(&self.iban);
+        IBAN_LENGTHS.iter().any(|(country, _)| iban.starts_with(country))
+    }
+
+    /// IBAN in its printed form, groups of four characters separated by spaces
+    pub fn iban_display(&self) -> String {
+        let iban = normalize(&self.iban);
+        iban.as_bytes()
+            .chunks(4)
+            .map(|c| String::from_utf8_lossy(c).into_owned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// IBAN lengths of the SEPA countries, used to catch typos the checksum alone might let through
+const IBAN_LENGTHS: [(&str, usize); 36] = [
+    ("AD", 24), ("AT", 20), ("BE", 16), ("BG", 22), ("CH", 21), ("CY", 28),
+    ("CZ", 24), ("DE", 22), ("DK", 18), ("EE", 20), ("ES", 24), ("FI", 18),
+    ("FR", 27), ("GB", 22), ("GI", 23), ("GR", 27), ("HR", 21), ("HU", 28),
+    ("IE", 22), ("IS", 26), ("IT", 27), ("LI", 21), ("LT", 20), ("LU", 20),
+    ("LV", 21), ("MC", 27), ("MT", 31), ("NL", 18), ("NO", 15), ("PL", 28),
+    ("PT", 25), ("RO", 24), ("SE", 24), ("SI", 19), ("SK", 24), ("SM", 27),
+];
+
+/// Validates an IBAN (ISO 13616), spaces are allowed and ignored
+pub fn validate_iban(iban: &str) -> Result<(), PaymentError> {
+    let err = || PaymentError::InvalidIban(iban.to_string());
+    let iban = normalize(iban);
+
+    if iban.len() < 15 || iban.len() > 34 || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(err());
+    }
+    let (country, check) = (&iban[0..2], &iban[2..4]);
+    if !country.chars().all(|c| c.is_ascii_uppercase()) || !check.chars().all(|c| c.is_ascii_digit()) {
+        return Err(err());
+    }
+    if let Some((_, len)) = IBAN_LENGTHS.iter().find(|(c, _)| *c == country)
+        && iban.len() != *len
+    {
+        return Err(err());
+    }
+
+    if mod97(&iban) == 1 { Ok(()) } else { Err(err()) }
+}
+
+/// ISO 7064 MOD 97-10 remainder as used by IBANs and creditor references (ISO 11649).
+///
+/// The first four characters are moved to the end and letters become 10..35, valid codes give 1.
+/// `code` has to be normalized ascii alphanumeric, with at least four characters.
+pub(crate) fn mod97(code: &str) -> u32 {
+    let rearranged = code[4..].chars().chain(code[0..4].chars());
+    rearranged.fold(0u32, |acc, c| {
+        let value = c.to_digit(36).unwrap();
+        if value < 10 { (acc * 10 + value) % 97 } else { (acc * 100 + value) % 97 }
+    })
+}
+
+/// Validates the format of a BIC (ISO 9362), either 8 or 11 characters long
+pub fn validate_bic(bic: &str) -> Result<(), PaymentError> {
+    let bic = normalize(bic);
+    let valid = bic.is_ascii()
+        && (bic.len() == 8 || bic.len() == 11)
+        && bic[0..6].chars().all(|c| c.is_ascii_uppercase())
+        && bic[6..].chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+
+    if valid { Ok(()) } else { Err(PaymentError::InvalidBic(bic)) }
+}
+
+pub(crate) fn normalize(code: &str) -> String {
+    code.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase()
+}
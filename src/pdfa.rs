@@ -0,0 +1,430 @@
+use chrono::{DateTime, Utc};
+use lopdf::content::Content;
+use lopdf::{dictionary, Object, Stream, StringFormat};
+
+use crate::types::ParsedInvoice;
+use crate::xml::escape;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// PDF/A conformance level of archived invoices, both only ask for the visual appearance to be
+/// preserved. Fonts are embedded either way.
+pub enum PdfA {
+    /// PDF/A-1b, based on PDF 1.4, without optional content or attachments
+    A1b,
+    /// PDF/A-3b, based on PDF 1.7, which can carry attachments like the invoice xml
+    A3b,
+}
+
+impl PdfA {
+    pub fn part(&self) -> u8 {
+        match self {
+            PdfA::A1b => 1,
+            PdfA::A3b => 3,
+        }
+    }
+
+    fn pdf_version(&self) -> &'static str {
+        match self {
+            PdfA::A1b => "1.4",
+            PdfA::A3b => "1.7",
+        }
+    }
+}
+
+/// Document info dictionary entries, mirrored in the xmp metadata
+pub(crate) struct DocumentInfo {
+    pub title: String,
+    pub author: String,
+    pub subject: String,
+    pub keywords: String,
+}
+
+impl DocumentInfo {
+    /// Info of an invoice: its number as title, issued by the seller
+    pub fn of(invoice: &ParsedInvoice) -> Self {
+        let number = invoice.data.invoice_number;
+        DocumentInfo {
+            title: format!("Invoice {}", number),
+            author: invoice.from.name.clone(),
+            subject: format!("Invoice {} to {}, issued {}", number, invoice.to.name, invoice.data.issue_date),
+            keywords: format!("invoice, {}", number),
+        }
+    }
+}
+
+/// Turns a rendered genpdf document into a PDF/A archive: xmp metadata, a document info
+/// dictionary matching it, an sRGB output intent, and for PDF/A-3 optionally attached files.
+///
+/// The document has to be rendered with `set_minimal_conformance`, otherwise printpdf adds its
+/// own PDF/X metadata and CMYK output intent, which PDF/A doesn't accept for rgb content.
+pub(crate) struct Archive {
+    pub level: PdfA,
+    pub info: DocumentInfo,
+    pub attachments: Vec<Attachment>,
+    /// Custom xmp properties, each one needs an extension schema describing it in PDF/A
+    pub schemas: Vec<XmpSchema>,
+}
+
+/// File embedded into the pdf, associated with the document as a whole
+pub(crate) struct Attachment {
+    pub name: String,
+    pub mime: &'static str,
+    pub description: String,
+    /// How the file relates to the document: Source, Data, Alternative, Supplement or Unspecified
+    pub relationship: &'static str,
+    pub data: Vec<u8>,
+}
+
+pub(crate) struct XmpSchema {
+    pub name: &'static str,
+    pub namespace: &'static str,
+    pub prefix: &'static str,
+    /// Text properties by name, with their value and description
+    pub properties: Vec<(&'static str, String, &'static str)>,
+}
+
+const PRODUCER: &str = concat!("factura ", env!("CARGO_PKG_VERSION"));
+const OUTPUT_CONDITION: &str = "sRGB IEC61966-2.1";
+
+impl Archive {
+    pub fn convert(&self, pdf: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        if self.level == PdfA::A1b && !self.attachments.is_empty() {
+            return Err("PDF/A-1 can't carry attachments".into());
+        }
+        let mut doc = lopdf::Document::load_mem(&pdf)?;
+        doc.version = self.level.pdf_version().to_string();
+        let now = Utc::now();
+
+        let info = doc.add_object(dictionary! {
+            "Title" => text_string(&self.info.title),
+            "Author" => text_string(&self.info.author),
+            "Subject" => text_string(&self.info.subject),
+            "Keywords" => text_string(&self.info.keywords),
+            "Creator" => text_string(PRODUCER),
+            "Producer" => text_string(PRODUCER),
+            "CreationDate" => Object::string_literal(now.format("D:%Y%m%d%H%M%S+00'00'").to_string()),
+            "ModDate" => Object::string_literal(now.format("D:%Y%m%d%H%M%S+00'00'").to_string()),
+        });
+        doc.trailer.set("Info", info);
+
+        let mut metadata = Stream::new(
+            dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+            self.xmp(now).into_bytes(),
+        );
+        // the metadata has to stay readable without decoding the pdf
+        metadata = metadata.with_compression(false);
+        let metadata = doc.add_object(metadata);
+
+        let profile = doc.add_object(Stream::new(dictionary! { "N" => 3 }, srgb_profile()));
+        let intent = dictionary! {
+            "Type" => "OutputIntent",
+            "S" => "GTS_PDFA1",
+            "OutputConditionIdentifier" => Object::string_literal(OUTPUT_CONDITION),
+            "Info" => Object::string_literal(OUTPUT_CONDITION),
+            "RegistryName" => Object::string_literal("http://www.color.org"),
+            "DestOutputProfile" => profile,
+        };
+
+        let mut specs = Vec::new();
+        for attachment in &self.attachments {
+            specs.push(attachment.add_to(&mut doc, now)?);
+        }
+
+        let root = doc.trailer.get(b"Root")?.as_reference()?;
+        let catalog = doc.get_object_mut(root)?.as_dict_mut()?;
+        catalog.set("Metadata", metadata);
+        catalog.set("OutputIntents", vec![Object::Dictionary(intent)]);
+        // printpdf puts every page into a layer. PDF/A-1 has no optional content, and PDF/A-3 would
+        // want a name for printpdf's layer configuration, none of which the invoice needs.
+        catalog.remove(b"OCProperties");
+        if !specs.is_empty() {
+            let names = self.attachments.iter().zip(&specs)
+                .flat_map(|(a, spec)| [Object::string_literal(a.name.clone()), Object::from(*spec)])
+                .collect::<Vec<Object>>();
+            catalog.set("Names", dictionary! { "EmbeddedFiles" => dictionary! { "Names" => names } });
+            catalog.set("AF", specs.into_iter().map(Object::from).collect::<Vec<Object>>());
+        }
+
+        remove_layers(&mut doc)?;
+
+        // printpdf's info dictionary is replaced, don't leave it behind
+        doc.prune_objects();
+        Ok(save(&mut doc)?)
+    }
+
+    fn xmp(&self, now: DateTime<Utc>) -> String {
+        let date = now.format("%Y-%m-%dT%H:%M:%S+00:00");
+        let mut xmp = String::from("<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n");
+        xmp.push_str("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n");
+        xmp.push_str(&format!(
+            concat!(
+                "<rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n",
+                "  <pdfaid:part>{part}</pdfaid:part>\n  <pdfaid:conformance>B</pdfaid:conformance>\n",
+                "</rdf:Description>\n",
+                "<rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+                "  <dc:format>application/pdf</dc:format>\n",
+                "  <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{title}</rdf:li></rdf:Alt></dc:title>\n",
+                "  <dc:creator><rdf:Seq><rdf:li>{author}</rdf:li></rdf:Seq></dc:creator>\n",
+                "  <dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{subject}</rdf:li></rdf:Alt></dc:description>\n",
+                "</rdf:Description>\n",
+                "<rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">\n",
+                "  <xmp:CreatorTool>{producer}</xmp:CreatorTool>\n  <xmp:CreateDate>{date}</xmp:CreateDate>\n",
+                "  <xmp:ModifyDate>{date}</xmp:ModifyDate>\n  <xmp:MetadataDate>{date}</xmp:MetadataDate>\n",
+                "</rdf:Description>\n",
+                "<rdf:Description rdf:about=\"\" xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\">\n",
+                "  <pdf:Producer>{producer}</pdf:Producer>\n  <pdf:Keywords>{keywords}</pdf:Keywords>\n",
+                "</rdf:Description>\n",
+            ),
+            part = self.level.part(), title = escape(&self.info.title), author = escape(&self.info.author),
+            subject = escape(&self.info.subject), keywords = escape(&self.info.keywords), producer = PRODUCER, date = date,
+        ));
+
+        for schema in &self.schemas {
+            xmp.push_str(&format!("<rdf:Description rdf:about=\"\" xmlns:{}=\"{}\">\n", schema.prefix, schema.namespace));
+            for (name, value, _) in &schema.properties {
+                xmp.push_str(&format!("  <{0}:{1}>{2}</{0}:{1}>\n", schema.prefix, name, escape(value)));
+            }
+            xmp.push_str("</rdf:Description>\n");
+        }
+        if !self.schemas.is_empty() {
+            xmp.push_str(&self.extension_schemas());
+        }
+
+        xmp.push_str("</rdf:RDF>\n</x:xmpmeta>\n<?xpacket end=\"w\"?>");
+        xmp
+    }
+
+    /// Description of the custom schemas, PDF/A only accepts properties of schemas it knows
+    fn extension_schemas(&self) -> String {
+        let mut xmp = String::from(concat!(
+            "<rdf:Description rdf:about=\"\" xmlns:pdfaExtension=\"http://www.aiim.org/pdfa/ns/extension/\"",
+            " xmlns:pdfaSchema=\"http://www.aiim.org/pdfa/ns/schema#\" xmlns:pdfaProperty=\"http://www.aiim.org/pdfa/ns/property#\">\n",
+            "  <pdfaExtension:schemas>\n    <rdf:Bag>\n",
+        ));
+        for schema in &self.schemas {
+            xmp.push_str(&format!(
+                concat!(
+                    "      <rdf:li rdf:parseType=\"Resource\">\n",
+                    "        <pdfaSchema:schema>{}</pdfaSchema:schema>\n",
+                    "        <pdfaSchema:namespaceURI>{}</pdfaSchema:namespaceURI>\n",
+                    "        <pdfaSchema:prefix>{}</pdfaSchema:prefix>\n",
+                    "        <pdfaSchema:property>\n          <rdf:Seq>\n",
+                ),
+                escape(schema.name), escape(schema.namespace), schema.prefix,
+            ));
+            for (name, _, description) in &schema.properties {
+                xmp.push_str(&format!(
+                    concat!(
+                        "            <rdf:li rdf:parseType=\"Resource\">\n",
+                        "              <pdfaProperty:name>{}</pdfaProperty:name>\n",
+                        "              <pdfaProperty:valueType>Text</pdfaProperty:valueType>\n",
+                        "              <pdfaProperty:category>external</pdfaProperty:category>\n",
+                        "              <pdfaProperty:description>{}</pdfaProperty:description>\n",
+                        "            </rdf:li>\n",
+                    ),
+                    name, escape(description),
+                ));
+            }
+            xmp.push_str("          </rdf:Seq>\n        </pdfaSchema:property>\n      </rdf:li>\n");
+        }
+        xmp.push_str("    </rdf:Bag>\n  </pdfaExtension:schemas>\n</rdf:Description>\n");
+        xmp
+    }
+}
+
+impl Attachment {
+    /// Adds the embedded file stream and its file specification, returning the specification
+    fn add_to(&self, doc: &mut lopdf::Document, now: DateTime<Utc>) -> Result<lopdf::ObjectId, lopdf::Error> {
+        let file = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "EmbeddedFile",
+                "Subtype" => Object::Name(self.mime.as_bytes().to_vec()),
+                "Params" => dictionary! {
+                    "Size" => self.data.len() as i64,
+                    "ModDate" => Object::string_literal(now.format("D:%Y%m%d%H%M%S+00'00'").to_string()),
+                },
+            },
+            self.data.clone(),
+        ));
+        Ok(doc.add_object(dictionary! {
+            "Type" => "Filespec",
+            "F" => Object::string_literal(self.name.clone()),
+            "UF" => text_string(&self.name),
+            "Desc" => text_string(&self.description),
+            "AFRelationship" => Object::Name(self.relationship.as_bytes().to_vec()),
+            "EF" => dictionary! { "F" => file, "UF" => file },
+        }))
+    }
+}
+
+/// Takes the marked content of printpdf's layers out of the pages, along with the layers in
+/// their resources. Whatever was drawn in a layer stays where it is.
+fn remove_layers(doc: &mut lopdf::Document) -> Result<(), lopdf::Error> {
+    for page in doc.get_pages().into_values() {
+        for id in doc.get_page_contents(page) {
+            let stream = doc.get_object_mut(id)?.as_stream_mut()?;
+            let content = Content::decode(&stream.decompressed_content().unwrap_or_else(|_| stream.content.clone()))?;
+            // whether each open marked content sequence is a layer, to drop its end as well
+            let mut open = Vec::new();
+            let operations: Vec<_> = content.operations.into_iter()
+                .filter(|operation| match operation.operator.as_str() {
+                    "BDC" | "BMC" => {
+                        let layer = operation.operands.first().and_then(|tag| tag.as_name().ok()) == Some(b"OC".as_slice());
+                        open.push(layer);
+                        !layer
+                    },
+                    "EMC" => !open.pop().unwrap_or(false),
+                    _ => true,
+                })
+                .collect();
+            stream.set_plain_content(Content { operations }.encode()?);
+            // left uncompressed when it doesn't get any smaller
+            let _ = stream.compress();
+        }
+
+        let shared = match doc.get_dictionary(page)?.get(b"Resources") {
+            Ok(Object::Reference(id)) => Some(*id),
+            _ => None,
+        };
+        let resources = match shared {
+            Some(id) => doc.get_object_mut(id)?.as_dict_mut()?,
+            None => match doc.get_object_mut(page)?.as_dict_mut()?.get_mut(b"Resources") {
+                Ok(Object::Dictionary(resources)) => resources,
+                _ => continue,
+            },
+        };
+        resources.remove(b"Properties");
+    }
+    Ok(())
+}
+
+/// Comment right after the `%PDF-1.x` header, PDF/A asks for at least four bytes above 127 there
+/// so the file is taken as binary. Written in UTF-8, each of these characters takes two of them.
+const BINARY_COMMENT: &str = "%\u{e2}\u{e3}\u{cf}\u{d3}";
+
+/// Writes the document with the binary comment after its header. lopdf only writes the header
+/// line itself, so the comment is passed along with the version.
+pub(crate) fn save(doc: &mut lopdf::Document) -> std::io::Result<Vec<u8>> {
+    let version = doc.version.clone();
+    doc.version = format!("{}\n{}", version, BINARY_COMMENT);
+    let mut out = Vec::new();
+    let saved = doc.save_to(&mut out);
+    doc.version = version;
+    saved.map(|_| out)
+}
+
+/// Files attached to the document, by name, as listed in the embedded files name tree
+#[cfg(feature = "cii")]
+pub(crate) fn attachments(pdf: &[u8]) -> Result<Vec<(String, Vec<u8>)>, lopdf::Error> {
+    let doc = lopdf::Document::load_mem(pdf)?;
+    let Ok(names) = doc.catalog()?.get_deref(b"Names", &doc).and_then(Object::as_dict) else {
+        return Ok(Vec::new());
+    };
+    let Ok(tree) = names.get_deref(b"EmbeddedFiles", &doc).and_then(Object::as_dict) else {
+        return Ok(Vec::new());
+    };
+    let mut files = Vec::new();
+    collect_attachments(&doc, tree, &mut files)?;
+    Ok(files)
+}
+
+#[cfg(feature = "cii")]
+fn collect_attachments(doc: &lopdf::Document, node: &lopdf::Dictionary, files: &mut Vec<(String, Vec<u8>)>) -> Result<(), lopdf::Error> {
+    if let Ok(kids) = node.get_deref(b"Kids", doc).and_then(Object::as_array) {
+        for kid in kids {
+            collect_attachments(doc, doc.dereference(kid)?.1.as_dict()?, files)?;
+        }
+    }
+    let Ok(names) = node.get_deref(b"Names", doc).and_then(Object::as_array) else { return Ok(()) };
+    for pair in names.chunks(2) {
+        let [name, spec] = pair else { continue };
+        let spec = doc.dereference(spec)?.1.as_dict()?;
+        let stream = spec.get_deref(b"EF", doc)?.as_dict()?.get_deref(b"F", doc)?.as_stream()?;
+        // streams without a filter are stored as they are
+        let data = match stream.filters() {
+            Ok(_) => stream.decompressed_content()?,
+            Err(_) => stream.content.clone(),
+        };
+        files.push((String::from_utf8_lossy(doc.dereference(name)?.1.as_str()?).into_owned(), data));
+    }
+    Ok(())
+}
+
+/// Ascii text stays a plain string, anything else is written as UTF-16BE with a byte order mark
+pub(crate) fn text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        return Object::string_literal(text);
+    }
+    let mut bytes = vec![0xfe, 0xff];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
+
+/// ICC v2 display profile with the sRGB primaries (adapted to D50) and a 2.2 gamma curve,
+/// small enough to build here instead of shipping the full profile.
+fn srgb_profile() -> Vec<u8> {
+    fn xyz(values: [f64; 3]) -> Vec<u8> {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        for value in values {
+            tag.extend(((value * 65536.0).round() as i32).to_be_bytes());
+        }
+        tag
+    }
+    fn text(text: &str) -> Vec<u8> {
+        let mut tag = b"text\0\0\0\0".to_vec();
+        tag.extend(text.as_bytes());
+        tag.push(0);
+        tag
+    }
+    fn desc(text: &str) -> Vec<u8> {
+        let mut tag = b"desc\0\0\0\0".to_vec();
+        tag.extend((text.len() as u32 + 1).to_be_bytes());
+        tag.extend(text.as_bytes());
+        tag.push(0);
+        // no unicode or scriptcode description
+        tag.extend([0; 4 + 4 + 2 + 1 + 67]);
+        tag
+    }
+    // a single gamma value, as u8Fixed8
+    let gamma = [b"curv\0\0\0\0".as_slice(), &1u32.to_be_bytes(), &0x0233u16.to_be_bytes()].concat();
+
+    let tags: [(&[u8; 4], Vec<u8>); 9] = [
+        (b"desc", desc(OUTPUT_CONDITION)),
+        (b"cprt", text("No copyright, use freely")),
+        (b"wtpt", xyz([0.9642, 1.0, 0.8249])),
+        (b"rXYZ", xyz([0.4361, 0.2225, 0.0139])),
+        (b"gXYZ", xyz([0.3851, 0.7169, 0.0971])),
+        (b"bXYZ", xyz([0.1431, 0.0606, 0.7141])),
+        (b"rTRC", gamma.clone()),
+        (b"gTRC", gamma.clone()),
+        (b"bTRC", gamma),
+    ];
+
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let data_start = 128 + 4 + 12 * tags.len();
+    for (signature, tag) in &tags {
+        table.extend(*signature);
+        table.extend(((data_start + data.len()) as u32).to_be_bytes());
+        table.extend((tag.len() as u32).to_be_bytes());
+        data.extend(tag);
+        // tags start on 4 byte boundaries
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+
+    let mut header = Vec::with_capacity(128);
+    header.extend(((data_start + data.len()) as u32).to_be_bytes());
+    header.extend([0; 4]); // preferred cmm
+    header.extend([2, 0x10, 0, 0]); // version 2.1
+    header.extend(b"mntrRGB XYZ ");
+    for part in [2025u16, 1, 1, 0, 0, 0] {
+        header.extend(part.to_be_bytes());
+    }
+    header.extend(b"acsp");
+    header.extend([0; 24]); // platform, flags, manufacturer, model, attributes
+    header.extend(0u32.to_be_bytes()); // perceptual rendering intent
+    header.extend(&xyz([0.9642, 1.0, 0.8249])[8..]); // D50 illuminant
+    header.resize(128, 0);
+
+    [header, table, data].concat()
+}
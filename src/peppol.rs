@@ -0,0 +1,192 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::en16931::{ElectronicAddress, RuleViolation, SemanticInvoice};
+use crate::types::ParsedInvoice;
+use crate::ubl::{write_invoice, INVOICE_NAMESPACE, SBDH_NAMESPACE};
+use crate::xml::XmlWriter;
+
+/// Specification identifier (BT-24) of Peppol BIS Billing 3.0
+pub const SPECIFICATION: &str = "urn:cen.eu:en16931:2017#compliant#urn:fdc:peppol.eu:2017:poacc:billing:3.0";
+/// Business process (BT-23) of the Peppol billing profile
+pub const PROFILE: &str = "urn:fdc:peppol.eu:2017:poacc:billing:01:1.0";
+/// Invoice type codes (BT-3) Peppol BIS accepts for invoices
+pub const TYPE_CODES: [u16; 24] = [
+    71, 80, 82, 84, 102, 218, 219, 331, 380, 382, 383, 386, 388, 393, 395, 553, 575, 623, 780, 817, 870, 875, 876, 877,
+];
+/// Electronic address schemes that aren't ISO 6523 codes, the rest of the EAS list is four digits.
+/// None of them is a participant identifier access points can route to.
+const EAS_LETTER_CODES: [&str; 5] = ["AN", "AQ", "AS", "AU", "EM"];
+
+/// Invoice that breaks Peppol BIS rules, so it can't be sent through the network.
+#[derive(Debug)]
+pub struct PeppolError(pub Vec<RuleViolation>);
+
+impl fmt::Display for PeppolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules: Vec<String> = self.0.iter().map(|v| v.to_string()).collect();
+        write!(f, "not a valid Peppol BIS invoice: {}", rules.join(", "))
+    }
+}
+
+impl std::error::Error for PeppolError {}
+
+impl ParsedInvoice {
+    /// Maps the invoice onto EN 16931 with the Peppol BIS specification and business process
+    pub fn to_peppol(&self) -> SemanticInvoice {
+        let mut invoice = self.to_en16931();
+        invoice.specification = SPECIFICATION.to_string();
+        invoice.business_process = Some(PROFILE.to_string());
+        invoice
+    }
+}
+
+impl SemanticInvoice {
+    /// Rules of EN 16931 and the Peppol BIS Billing 3.0 rules the invoice doesn't satisfy, as far
+    /// as they can be checked without the network
+    pub fn validate_peppol(&self) -> Vec<RuleViolation> {
+        let mut violations = self.validate();
+        let mut check = |ok: bool, rule: &str, message: &str| {
+            if !ok {
+                violations.push(RuleViolation { rule: rule.to_string(), message: message.to_string() });
+            }
+        };
+
+        check(self.business_process.is_some(), "PEPPOL-EN16931-R001", "business process (BT-23) missing");
+        if let Some(process) = &self.business_process {
+            let number = process.strip_prefix("urn:fdc:peppol.eu:2017:poacc:billing:").and_then(|p| p.strip_suffix(":1.0"));
+            check(
+                number.is_some_and(|n| n.len() == 2 && n.chars().all(|c| c.is_ascii_digit())),
+                "PEPPOL-EN16931-R007", "business process (BT-23) isn't a Peppol billing profile",
+            );
+        }
+        check(self.buyer_reference.is_some(), "PEPPOL-EN16931-R003", "buyer reference (BT-10) missing");
+        check(self.specification == SPECIFICATION, "PEPPOL-EN16931-R004", "specification identifier (BT-24) isn't the Peppol BIS one");
+        check(self.buyer.electronic_address.is_some(), "PEPPOL-EN16931-R010", "buyer electronic address (BT-49) missing");
+        check(self.seller.electronic_address.is_some(), "PEPPOL-EN16931-R020", "seller electronic address (BT-34) missing");
+        check(TYPE_CODES.contains(&self.type_code), "PEPPOL-EN16931-P0100", "invoice type code (BT-3) isn't one Peppol BIS accepts");
+
+        for address in [&self.seller, &self.buyer].into_iter().filter_map(|p| p.electronic_address.as_ref()) {
+            let is_icd = address.scheme.len() == 4 && address.scheme.chars().all(|c| c.is_ascii_digit());
+            if EAS_LETTER_CODES.contains(&address.scheme.as_str()) {
+                // the SBDH addresses both parties by their participant identifier, an email can't be one
+                check(false, "PEPPOL-EN16931-CL008", &format!(
+                    "{} can't be routed, Peppol participants need an endpoint ID with an ISO 6523 scheme", address
+                ));
+            } else {
+                check(is_icd, "PEPPOL-EN16931-CL008", &format!("{} isn't an electronic address scheme (EAS)", address.scheme));
+            }
+            match address.scheme.as_str() {
+                "0088" => check(is_gln(&address.id), "PEPPOL-COMMON-R040", &format!("{} isn't a valid GLN", address.id)),
+                "0192" => check(
+                    is_norwegian_organization(&address.id), "PEPPOL-COMMON-R041",
+                    &format!("{} isn't a valid Norwegian organization number", address.id),
+                ),
+                _ => {},
+            }
+        }
+        violations
+    }
+}
+
+/// GS1 check digit: the digits are weighted 3 and 1 from the right
+fn is_gln(id: &str) -> bool {
+    let digits: Vec<u32> = id.chars().filter_map(|c| c.to_digit(10)).collect();
+    if id.len() != 13 || digits.len() != 13 {
+        return false;
+    }
+    let sum: u32 = digits[..12].iter().rev().enumerate().map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d }).sum();
+    (10 - sum % 10) % 10 == digits[12]
+}
+
+/// Nine digits, the last one a MOD 11 check digit
+fn is_norwegian_organization(id: &str) -> bool {
+    let digits: Vec<u32> = id.chars().filter_map(|c| c.to_digit(10)).collect();
+    if id.len() != 9 || digits.len() != 9 {
+        return false;
+    }
+    let sum: u32 = digits[..8].iter().zip([3, 2, 7, 6, 5, 4, 3, 2]).map(|(d, w)| d * w).sum();
+    match 11 - sum % 11 {
+        11 => digits[8] == 0,
+        10 => false,
+        check => digits[8] == check,
+    }
+}
+
+/// Checks the invoice against the Peppol rules and maps it, ready to be written
+fn checked(invoice: &ParsedInvoice) -> Result<SemanticInvoice, PeppolError> {
+    let invoice = invoice.to_peppol();
+    let violations = invoice.validate_peppol();
+    if !violations.is_empty() {
+        return Err(PeppolError(violations));
+    }
+    Ok(invoice)
+}
+
+/// Peppol BIS Billing 3.0 UBL invoice, after checking it against the rules
+pub fn invoice_xml(invoice: &ParsedInvoice) -> Result<String, PeppolError> {
+    let invoice = checked(invoice)?;
+    Ok(crate::ubl::invoice_xml(&invoice))
+}
+
+/// Peppol BIS invoice wrapped in the Standard Business Document Header access points exchange,
+/// addressed from the seller's to the buyer's electronic address
+pub fn envelope_xml(invoice: &ParsedInvoice) -> Result<String, PeppolError> {
+    let invoice = checked(invoice)?;
+    Ok(write_envelope(&invoice, Utc::now()))
+}
+
+fn write_envelope(invoice: &SemanticInvoice, created: DateTime<Utc>) -> String {
+    // both are there, the rules ask for them
+    let sender = invoice.seller.electronic_address.as_ref().map(ElectronicAddress::to_string).unwrap_or_default();
+    let receiver = invoice.buyer.electronic_address.as_ref().map(ElectronicAddress::to_string).unwrap_or_default();
+    let document_type = format!("{}::Invoice##{}::2.1", INVOICE_NAMESPACE, invoice.specification);
+
+    let mut xml = XmlWriter::new();
+    xml.open("StandardBusinessDocument", &[("xmlns", SBDH_NAMESPACE)]);
+    xml.open("StandardBusinessDocumentHeader", &[]);
+    xml.leaf("HeaderVersion", &[], "1.0");
+    xml.open("Sender", &[]);
+    xml.leaf("Identifier", &[("Authority", "iso6523-actorid-upis")], &sender);
+    xml.close("Sender");
+    xml.open("Receiver", &[]);
+    xml.leaf("Identifier", &[("Authority", "iso6523-actorid-upis")], &receiver);
+    xml.close("Receiver");
+
+    xml.open("DocumentIdentification", &[]);
+    xml.leaf("Standard", &[], INVOICE_NAMESPACE);
+    xml.leaf("TypeVersion", &[], "2.1");
+    xml.leaf("InstanceIdentifier", &[], &instance_identifier(&sender, &invoice.number, created));
+    xml.leaf("Type", &[], "Invoice");
+    xml.leaf("CreationDateAndTime", &[], &created.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+    xml.close("DocumentIdentification");
+
+    xml.open("BusinessScope", &[]);
+    let scopes = [
+        ("DOCUMENTID", document_type.as_str(), Some("busdox-docid-qns")),
+        ("PROCESSID", invoice.business_process.as_deref().unwrap_or(PROFILE), Some("cenbii-procid-ubl")),
+        ("COUNTRY_C1", invoice.seller.address.country_code.as_deref().unwrap_or_default(), None),
+    ];
+    for (kind, value, identifier) in scopes {
+        xml.open("Scope", &[]);
+        xml.leaf("Type", &[], kind);
+        xml.leaf("InstanceIdentifier", &[], value);
+        xml.optional("Identifier", identifier);
+        xml.close("Scope");
+    }
+    xml.close("BusinessScope");
+    xml.close("StandardBusinessDocumentHeader");
+
+    write_invoice(&mut xml, invoice);
+    xml.close("StandardBusinessDocument");
+    xml.finish()
+}
+
+/// UUID shaped identifier of the envelope, unique per sender, invoice and time it's created
+fn instance_identifier(sender: &str, number: &str, created: DateTime<Utc>) -> String {
+    let hash = Sha256::digest(format!("{}|{}|{}", sender, number, created.timestamp_nanos_opt().unwrap_or_default()));
+    let hex: String = hash[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
@@ -0,0 +1,89 @@
+use qrcode::{EcLevel, QrCode};
+
+use crate::payment::PaymentError;
+
+/// Encodes the payload, the error correction level M is what the payment QR standards ask for
+fn encode(payload: &str) -> Result<QrCode, PaymentError> {
+    QrCode::with_error_correction_level(payload.as_bytes(), EcLevel::M)
+        .map_err(|e| PaymentError::QrPayload(e.to_string()))
+}
+
+#[cfg(feature = "pdf")]
+const MODULE_PX: u32 = 8;
+
+#[cfg(feature = "pdf")]
+/// QR code as a genpdf image element, `size` is the printed width in mm of the code itself,
+/// a quiet zone of four modules is added around it
+pub(crate) fn pdf_image(payload: &str, size: f64) -> Result<genpdf::elements::Image, Box<dyn std::error::Error>> {
+    let code = encode(payload)?;
+    let pixels = luma_pixels(&code, 4);
+    sized_image(pixels, code.width() as u32, size)
+}
+
+#[cfg(feature = "pdf")]
+/// QR code with the swiss cross in its center, as required on the swiss QR-bill.
+/// There's no quiet zone, the payment part leaves enough blank space around the code.
+pub(crate) fn pdf_swiss_image(payload: &str, size: f64) -> Result<genpdf::elements::Image, Box<dyn std::error::Error>> {
+    let code = encode(payload)?;
+    let mut pixels = luma_pixels(&code, 0);
+    draw_swiss_cross(&mut pixels);
+    sized_image(pixels, code.width() as u32, size)
+}
+
+#[cfg(feature = "pdf")]
+fn luma_pixels(code: &QrCode, quiet_zone: u32) -> image::GrayImage {
+    let width = code.width() as u32;
+    let colors = code.to_colors();
+    let side = (width + 2 * quiet_zone) * MODULE_PX;
+    let modules = quiet_zone..width + quiet_zone;
+
+    image::GrayImage::from_fn(side, side, |x, y| {
+        let (mx, my) = (x / MODULE_PX, y / MODULE_PX);
+        let dark = modules.contains(&mx) && modules.contains(&my)
+            && colors[((my - quiet_zone) * width + (mx - quiet_zone)) as usize] == qrcode::Color::Dark;
+        image::Luma([if dark { 0 } else { 255 }])
+    })
+}
+
+#[cfg(feature = "pdf")]
+/// The cross is 7mm wide on a 46mm code: a black square with a white border and a white cross on it
+fn draw_swiss_cross(pixels: &mut image::GrayImage) {
+    let side = pixels.width() as f64;
+    let square = side * 7.0 / 46.0;
+    let border = side * 0.5 / 46.0;
+    let center = side / 2.0;
+    let (arm, bar) = (square * 0.6 / 2.0, square * 0.19 / 2.0);
+
+    for (x, y, pixel) in pixels.enumerate_pixels_mut() {
+        let (dx, dy) = ((x as f64 + 0.5 - center).abs(), (y as f64 + 0.5 - center).abs());
+        let in_border = dx <= square / 2.0 + border && dy <= square / 2.0 + border;
+        let in_square = dx <= square / 2.0 && dy <= square / 2.0;
+        let in_cross = (dx <= arm && dy <= bar) || (dx <= bar && dy <= arm);
+
+        if in_border {
+            pixel.0 = [if in_square && !in_cross { 0 } else { 255 }];
+        }
+    }
+}
+
+#[cfg(feature = "pdf")]
+fn sized_image(pixels: image::GrayImage, modules: u32, size: f64) -> Result<genpdf::elements::Image, Box<dyn std::error::Error>> {
+    let image = genpdf::elements::Image::from_dynamic_image(image::DynamicImage::ImageLuma8(pixels))
+        .map_err(crate::simple_invoice::genpdf_error_convert)?;
+    // the image is sized through its dpi, 25.4 mm per inch
+    Ok(image.with_dpi((modules * MODULE_PX) as f64 * 25.4 / size))
+}
+
+#[cfg(feature = "html")]
+/// QR code as inline SVG markup, `size` is the minimum width in px
+pub(crate) fn svg(payload: &str, size: u32) -> Result<String, PaymentError> {
+    let svg = encode(payload)?
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(size, size)
+        .build();
+    // drop the xml declaration, the svg is embedded in an html document
+    Ok(match svg.find("<svg") {
+        Some(start) => svg[start..].to_string(),
+        None => svg,
+    })
+}
@@ -0,0 +1,366 @@
+use crate::country::iso_code;
+use crate::payment::{mod97, normalize, validate_iban, PaymentError};
+use crate::types::{BankTransfer, ParsedInvoice, Payment, PaymentMethod, PersonalInfo};
+
+/// Swiss QR-bill (Swiss Implementation Guidelines for the QR-bill, version 2.x).
+///
+/// Built from an invoice that accepts a bank transfer to a swiss or liechtenstein account,
+/// holds the validated data for both the QR code (SPC payload) and the printed payment part.
+#[derive(Clone)]
+pub struct QrBill {
+    pub iban: String,
+    pub creditor: QrBillAddress,
+    pub debtor: QrBillAddress,
+    pub currency: String,
+    pub amount: u32,
+    pub reference: QrBillReference,
+    pub message: String,
+}
+
+/// Structured address ("S" type) of a party on the QR-bill.
+#[derive(Clone)]
+pub struct QrBillAddress {
+    pub name: String,
+    pub street: String,
+    pub building_number: String,
+    pub postal_code: String,
+    pub town: String,
+    pub country: String,
+}
+
+/// Reference the payment has to be made with.
+#[derive(Clone)]
+pub enum QrBillReference {
+    /// 27 digit QR reference, required for QR-IBANs
+    Qr(String),
+    /// ISO 11649 creditor reference, starting with "RF"
+    Creditor(String),
+    None,
+}
+
+impl QrBill {
+    /// Builds the QR-bill for the invoice, the reference of the bank transfer is used when set,
+    /// otherwise a QR reference is derived from the invoice number (QR-IBAN only).
+    pub fn from_invoice(invoice: &ParsedInvoice) -> Result<Self, PaymentError> {
+        let bank = swiss_account(&invoice.payment)
+            .ok_or(PaymentError::QrPayload(String::from("no swiss bank account to pay to")))?;
+
+        let currency = invoice.payment.currency.to_ascii_uppercase();
+        if currency != "CHF" && currency != "EUR" {
+            return Err(PaymentError::QrPayload(format!("QR-bill requires CHF or EUR, not {}", currency)));
+        }
+        if invoice.total.amount_due > 999_999_999 {
+            return Err(PaymentError::QrPayload(format!("QR-bill amount out of range: {}", invoice.total.amount_due)));
+        }
+
+        let iban = normalize(&bank.iban);
+        validate_iban(&iban)?;
+        let message = format!("Invoice {}", invoice.data.invoice_number);
+
+        let (reference, message) = if is_qr_iban(&iban) {
+            let reference = match &bank.reference {
+                Some(r) => normalize(r),
+                None => qr_reference(invoice.data.invoice_number as u64),
+            };
+            validate_qr_reference(&reference)?;
+            (QrBillReference::Qr(reference), message)
+        } else {
+            match &bank.reference {
+                // "RF" and two check digits make it a creditor reference, printed with spaces or not
+                Some(r) if is_creditor_reference_like(&normalize(r)) => {
+                    let reference = normalize(r);
+                    validate_creditor_reference(&reference)?;
+                    (QrBillReference::Creditor(reference), message)
+                },
+                // a free text reference can't go in the reference field, it becomes the message instead
+                Some(r) => (QrBillReference::None, r.clone()),
+                None => (QrBillReference::None, message),
+            }
+        };
+
+        Ok(QrBill {
+            iban,
+            creditor: QrBillAddress::from_party(&invoice.from, Some(&bank.account_holder))?,
+            debtor: QrBillAddress::from_party(&invoice.to, None)?,
+            currency,
+            amount: invoice.total.amount_due,
+            reference,
+            message: message.chars().take(140).collect(),
+        })
+    }
+
+    /// SPC payload encoded in the QR code of the bill
+    pub fn payload(&self) -> String {
+        let (reference_type, reference) = match &self.reference {
+            QrBillReference::Qr(r) => ("QRR", r.as_str()),
+            QrBillReference::Creditor(r) => ("SCOR", r.as_str()),
+            QrBillReference::None => ("NON", ""),
+        };
+
+        let mut lines: Vec<String> = vec![
+            String::from("SPC"),
+            String::from("0200"),
+            String::from("1"),
+            self.iban.clone(),
+        ];
+        lines.extend(self.creditor.payload_lines());
+        // ultimate creditor, reserved for future use and left empty
+        lines.extend(std::iter::repeat_n(String::new(), 7));
+        lines.push(format!("{}.00", self.amount));
+        lines.push(self.currency.clone());
+        lines.extend(self.debtor.payload_lines());
+        lines.push(String::from(reference_type));
+        lines.push(String::from(reference));
+        lines.push(self.message.clone());
+        lines.push(String::from("EPD"));
+
+        lines.join("\n")
+    }
+
+    /// IBAN in its printed form, groups of four characters
+    pub fn iban_display(&self) -> String {
+        group(&self.iban, 4, false)
+    }
+
+    /// Reference in its printed form, QR references are grouped by five from the right
+    pub fn reference_display(&self) -> Option<String> {
+        match &self.reference {
+            QrBillReference::Qr(r) => Some(group(r, 5, true)),
+            QrBillReference::Creditor(r) => Some(group(r, 4, false)),
+            QrBillReference::None => None,
+        }
+    }
+}
+
+impl QrBillAddress {
+    fn from_party(info: &PersonalInfo, name: Option<&String>) -> Result<Self, PaymentError> {
+        let country = iso_code(&info.country)
+            .ok_or(PaymentError::QrPayload(format!("unknown country: {}", info.country)))?;
+        let limit = |text: &str, max: usize| text.chars().take(max).collect::<String>();
+
+        Ok(QrBillAddress {
+            name: limit(name.unwrap_or(&info.name), 70),
+            street: limit(&info.addr_one, 70),
+            building_number: limit(&info.addr_two, 16),
+            postal_code: limit(&info.postal, 16),
+            town: limit(&info.state, 35),
+            country,
+        })
+    }
+
+    fn payload_lines(&self) -> [String; 7] {
+        [
+            String::from("S"),
+            self.name.clone(),
+            self.street.clone(),
+            self.building_number.clone(),
+            self.postal_code.clone(),
+            self.town.clone(),
+            self.country.clone(),
+        ]
+    }
+
+    /// Lines as printed on the payment part
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            format!("{} {}", self.street, self.building_number).trim().to_string(),
+            format!("{}-{} {}", self.country, self.postal_code, self.town),
+        ]
+    }
+}
+
+/// First accepted bank transfer to a swiss or liechtenstein account
+pub fn swiss_account(payment: &Payment) -> Option<&BankTransfer> {
+    payment.methods.iter().find_map(|method| match method {
+        PaymentMethod::BankTransfer(bank) => {
+            let country = normalize(&bank.iban);
+            (country.starts_with("CH") || country.starts_with("LI")).then_some(bank)
+        },
+        _ => None,
+    })
+}
+
+/// QR-IBANs are swiss/liechtenstein IBANs with an institution id (QR-IID) between 30000 and 31999
+pub fn is_qr_iban(iban: &str) -> bool {
+    let iban = normalize(iban);
+    (iban.starts_with("CH") || iban.starts_with("LI"))
+        && iban.is_ascii()
+        && iban.len() == 21
+        && iban[4..9].parse::<u32>().is_ok_and(|iid| (30000..=31999).contains(&iid))
+}
+
+/// Validates a QR-IBAN, a valid IBAN with a QR-IID
+pub fn validate_qr_iban(iban: &str) -> Result<(), PaymentError> {
+    validate_iban(iban)?;
+    if is_qr_iban(iban) { Ok(()) } else { Err(PaymentError::InvalidIban(iban.to_string())) }
+}
+
+/// Validates a 27 digit QR reference, the last digit is a recursive mod 10 check digit
+pub fn validate_qr_reference(reference: &str) -> Result<(), PaymentError> {
+    let reference = normalize(reference);
+    let valid = reference.len() == 27
+        && reference.chars().all(|c| c.is_ascii_digit())
+        && mod10_check_digit(&reference[..26]) == reference[26..].parse::<u8>().unwrap();
+
+    if valid { Ok(()) } else { Err(PaymentError::InvalidReference(reference)) }
+}
+
+/// Validates an ISO 11649 creditor reference, "RF", two check digits and up to 21 characters
+pub fn validate_creditor_reference(reference: &str) -> Result<(), PaymentError> {
+    let reference = normalize(reference);
+    let valid = reference.starts_with("RF")
+        && (5..=25).contains(&reference.len())
+        && reference.chars().all(|c| c.is_ascii_alphanumeric())
+        && mod97(&reference) == 1;
+
+    if valid { Ok(()) } else { Err(PaymentError::InvalidReference(reference)) }
+}
+
+fn is_creditor_reference_like(reference: &str) -> bool {
+    reference.starts_with("RF") && reference[2..].chars().take(2).filter(|c| c.is_ascii_digit()).count() == 2
+}
+
+/// QR reference for a number, zero padded to 26 digits followed by its check digit
+pub fn qr_reference(number: u64) -> String {
+    let digits = format!("{:026}", number);
+    format!("{}{}", digits, mod10_check_digit(&digits))
+}
+
+/// Recursive mod 10 check digit, as used for swiss payment references
+fn mod10_check_digit(digits: &str) -> u8 {
+    const TABLE: [u8; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+    let carry = digits.bytes().fold(0u8, |carry, d| TABLE[((carry + d - b'0') % 10) as usize]);
+    (10 - carry) % 10
+}
+
+/// Splits a code into blocks of `size`, starting from the end when `from_right`
+fn group(code: &str, size: usize, from_right: bool) -> String {
+    let chars: Vec<char> = code.chars().collect();
+    let first = if from_right { chars.len() % size } else { 0 };
+    let mut blocks: Vec<String> = vec![];
+    if first > 0 {
+        blocks.push(chars[..first].iter().collect());
+    }
+    blocks.extend(chars[first..].chunks(size).map(|c| c.iter().collect::<String>()));
+    blocks.join(" ")
+}
+
+#[cfg(feature = "pdf")]
+pub(crate) use payment_part::PaymentPart;
+
+#[cfg(feature = "pdf")]
+mod payment_part {
+    use genpdf::{
+        error::{Error, ErrorKind}, render::Area, style::Style, Context, Element, Mm, Position, RenderResult, Size,
+    };
+
+    use super::QrBill;
+    use crate::qr;
+
+    /// The payment part and receipt of a QR-bill, drawn over the bottom 105mm of the page.
+    ///
+    /// genpdf only hands out the area inside of the page margins, so they have to be known here
+    /// to line the perforation up with the edges of the page.
+    pub(crate) struct PaymentPart {
+        bill: QrBill,
+        page_margin: Mm,
+    }
+
+    impl PaymentPart {
+        pub(crate) fn new(bill: QrBill, page_margin: impl Into<Mm>) -> Self {
+            PaymentPart { bill, page_margin: page_margin.into() }
+        }
+    }
+
+    impl Element for PaymentPart {
+        fn render(&mut self, context: &Context, area: Area<'_>, style: Style) -> Result<RenderResult, Error> {
+            let margin = self.page_margin;
+            let mut slip = area.clone();
+            slip.add_offset(Position::new(Mm::from(0) - margin, area.size().height + margin - Mm::from(105)));
+            slip.set_size(Size::new(210, 105));
+
+            // the receipt uses smaller fonts than the payment part
+            let title = style.bold().with_font_size(11);
+            let heading = style.bold().with_font_size(8);
+            let text = style.with_font_size(10);
+            let receipt_heading = style.bold().with_font_size(6);
+            let receipt_text = style.with_font_size(8);
+
+            // perforation between the invoice and the slip, and between receipt and payment part
+            slip.draw_line(vec![Position::new(0, 0), Position::new(210, 0)], style);
+            slip.draw_line(vec![Position::new(62, 0), Position::new(62, 105)], style);
+
+            let bill = &self.bill;
+            let mut blocks = vec![(String::from("Account / Payable to"), {
+                let mut lines = vec![bill.iban_display()];
+                lines.extend(bill.creditor.lines());
+                lines
+            })];
+            if let Some(reference) = bill.reference_display() {
+                blocks.push((String::from("Reference"), vec![reference]));
+            }
+
+            // RECEIPT
+            let print = |x: f64, y: &mut f64, s: Style, line: &str, step: f64| -> Result<(), Error> {
+                slip.print_str(&context.font_cache, Position::new(x, *y), s, line)?;
+                *y += step;
+                Ok(())
+            };
+
+            let mut y = 5.0;
+            print(5.0, &mut y, title, "Receipt", 7.0)?;
+            for (name, lines) in blocks.iter() {
+                print(5.0, &mut y, receipt_heading, name, 3.0)?;
+                for line in lines {
+                    print(5.0, &mut y, receipt_text, line, 3.5)?;
+                }
+                y += 2.0;
+            }
+            print(5.0, &mut y, receipt_heading, "Payable by", 3.0)?;
+            for line in bill.debtor.lines() {
+                print(5.0, &mut y, receipt_text, &line, 3.5)?;
+            }
+
+            let mut y = 68.0;
+            print(5.0, &mut y, receipt_heading, "Currency", 3.0)?;
+            print(5.0, &mut y, receipt_text, &bill.currency, 0.0)?;
+            let mut y = 68.0;
+            print(20.0, &mut y, receipt_heading, "Amount", 3.0)?;
+            print(20.0, &mut y, receipt_text, &format!("{}.00", bill.amount), 0.0)?;
+            let mut y = 82.0;
+            print(38.0, &mut y, receipt_heading, "Acceptance point", 0.0)?;
+
+            // PAYMENT PART
+            let mut y = 5.0;
+            print(67.0, &mut y, title, "Payment part", 0.0)?;
+
+            let code = qr::pdf_swiss_image(&bill.payload(), 46.0)
+                .map_err(|e| Error::new(e.to_string(), ErrorKind::InvalidData))?;
+            let mut code_area = slip.clone();
+            code_area.add_offset(Position::new(67, 17));
+            code_area.set_size(Size::new(46, 46));
+            code.with_position(Position::new(0, 0)).render(context, code_area, style)?;
+
+            let mut y = 68.0;
+            print(67.0, &mut y, heading, "Currency", 4.0)?;
+            print(67.0, &mut y, text, &bill.currency, 0.0)?;
+            let mut y = 68.0;
+            print(87.0, &mut y, heading, "Amount", 4.0)?;
+            print(87.0, &mut y, text, &format!("{}.00", bill.amount), 0.0)?;
+
+            let mut y = 5.0;
+            blocks.push((String::from("Additional information"), vec![bill.message.clone()]));
+            blocks.push((String::from("Payable by"), bill.debtor.lines()));
+            for (name, lines) in blocks.iter() {
+                print(118.0, &mut y, heading, name, 3.5)?;
+                for line in lines {
+                    print(118.0, &mut y, text, line, 4.0)?;
+                }
+                y += 2.0;
+            }
+
+            Ok(RenderResult { size: area.size(), has_more: false })
+        }
+    }
+}
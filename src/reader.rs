@@ -1,102 +1,533 @@
 #[cfg(feature = "from_file")]
+#[allow(clippy::module_inception)]
 pub mod reader {
 
+use std::collections::HashMap;
 use std::fs;
 use serde::{Deserialize, Serialize};
 use toml;
 use serde_json;
 
-use crate::types::{InvoiceData, ItemRaw, Payment, PersonalInfo, RawInvoice};
+use crate::catalog::CatalogError;
+use crate::types::{
+    BankTransfer, Catalog, Chain, Contact, CryptoWallet, InvoiceData, InvoiceStatus, ItemRaw, ParsedInvoice, Payment,
+    PaymentMethod, PersonalInfo, Product, RawInvoice, StatusChange, TaxCategory,
+};
 
 /// Allows generating an invoice from a json/toml file.
 pub struct InvoiceReader {}
 impl InvoiceReader {
     /// Read a json file with a slice of Invoices and convert it to raw type 
     pub fn from_json(file: String) -> Result<Vec<RawInvoice>, Box<dyn std::error::Error>> {
-        let parser = |s: &str| serde_json::from_str::<Vec<FileInvoice>>(s).map_err(|e| Box::new(e));
-        let invoices: Vec<RawInvoice> = list_raw_invoices(file, parser).unwrap();
-        Ok(invoices)
+        Self::from_json_with_catalog(file, &Catalog::default())
     }
     /// Read a toml file with a slice of Invoices and convert it to raw type 
     pub fn from_toml(file: String) -> Result<Vec<RawInvoice>, Box<dyn std::error::Error>> {
-        let parser = |s: &str| toml::from_str::<Vec<FileInvoice>>(s).map_err(|e| Box::new(e));
-        let invoices: Vec<RawInvoice> = list_raw_invoices(file, parser).unwrap();
+        Self::from_toml_with_catalog(file, &Catalog::default())
+    }
+    /// Read a json file with a slice of Invoices, which can refer to customers and products of the catalog
+    pub fn from_json_with_catalog(file: String, catalog: &Catalog) -> Result<Vec<RawInvoice>, Box<dyn std::error::Error>> {
+        let parser = |s: &str| serde_json::from_str::<Vec<FileInvoice>>(s).map_err(Box::new);
+        let invoices: Vec<RawInvoice> = list_raw_invoices(file, parser, catalog)?;
         Ok(invoices)
     }
+    /// Read a toml file with a slice of Invoices, which can refer to customers and products of the catalog.
+    /// A toml document can't be an array, the invoices are listed as `[[invoices]]` tables.
+    pub fn from_toml_with_catalog(file: String, catalog: &Catalog) -> Result<Vec<RawInvoice>, Box<dyn std::error::Error>> {
+        let parser = |s: &str| toml::from_str::<FileInvoiceList>(s).map(|list| list.invoices).map_err(Box::new);
+        let invoices: Vec<RawInvoice> = list_raw_invoices(file, parser, catalog)?;
+        Ok(invoices)
+    }
+}
+
+/// Writes invoices back to the json/toml files InvoiceReader reads.
+///
+/// Parsed invoices carry their computed item and invoice totals along, for other systems to pick
+/// up. They're left out when the files are read again, and calculated anew.
+pub struct InvoiceWriter {}
+impl InvoiceWriter {
+    /// Write parsed invoices to a json file, along with their totals
+    pub fn to_json(file: String, invoices: &[ParsedInvoice]) -> Result<(), Box<dyn std::error::Error>> {
+        let invoices: Vec<FileInvoice> = invoices.iter().cloned().map(FileInvoice::with_totals).collect();
+        fs::write(file, serde_json::to_string_pretty(&invoices)?)?;
+        Ok(())
+    }
+    /// Write parsed invoices to a toml file, along with their totals
+    pub fn to_toml(file: String, invoices: &[ParsedInvoice]) -> Result<(), Box<dyn std::error::Error>> {
+        let invoices = invoices.iter().cloned().map(FileInvoice::with_totals).collect();
+        fs::write(file, toml::to_string(&FileInvoiceList { invoices })?)?;
+        Ok(())
+    }
+    /// Write raw invoices to a json file
+    pub fn raw_to_json(file: String, invoices: &[RawInvoice]) -> Result<(), Box<dyn std::error::Error>> {
+        let invoices: Vec<FileInvoice> = invoices.iter().cloned().map(Into::into).collect();
+        fs::write(file, serde_json::to_string_pretty(&invoices)?)?;
+        Ok(())
+    }
+    /// Write raw invoices to a toml file
+    pub fn raw_to_toml(file: String, invoices: &[RawInvoice]) -> Result<(), Box<dyn std::error::Error>> {
+        let invoices = invoices.iter().cloned().map(Into::into).collect();
+        fs::write(file, toml::to_string(&FileInvoiceList { invoices })?)?;
+        Ok(())
+    }
+}
+
+impl Catalog {
+    /// Read a catalog from a json file
+    pub fn from_json(file: String) -> Result<Catalog, Box<dyn std::error::Error>> {
+        let catalog: FileCatalog = serde_json::from_str(&fs::read_to_string(file)?)?;
+        Ok(catalog.into())
+    }
+    /// Read a catalog from a toml file
+    pub fn from_toml(file: String) -> Result<Catalog, Box<dyn std::error::Error>> {
+        let catalog: FileCatalog = toml::from_str(&fs::read_to_string(file)?)?;
+        Ok(catalog.into())
+    }
 }
 
 /// writing this was a big waste of time, but it saved 2 lines of code
-fn list_raw_invoices <F,E> (file: String, parser_func: F) -> 
-    Result<Vec<RawInvoice>, Box<std::io::Error>> 
+fn list_raw_invoices <F,E> (file: String, parser_func: F, catalog: &Catalog) -> 
+    Result<Vec<RawInvoice>, Box<dyn std::error::Error>> 
     where 
         F: FnOnce(&str) -> Result<Vec<FileInvoice>, Box<E>>,
-        E: std::error::Error,
+        E: std::error::Error + 'static,
 { 
     let file_string: String = match fs::read_to_string(file) {
         Ok(v) => v,
         Err(e) => { return Err(Box::new(e)) },
     };
-    let file_raw: Vec<FileInvoice> = parser_func(file_string.as_str()).unwrap();
-    Ok(file_raw.into_iter().map(RawInvoice::from).collect())
-}
-
-
-impl From<FileInvoice> for RawInvoice{
-    fn from(i: FileInvoice) -> RawInvoice {
-        RawInvoice { 
-            from: PersonalInfo { 
-                email: i.from.email, 
-                name: i.from.name, 
-                addr_one: i.from.addr_one, 
-                addr_two: i.from.addr_two, 
-                postal: i.from.postal, 
-                state: i.from.state,
-                country: i.from.country,
-            }, 
-            to: PersonalInfo { 
-                email: i.to.email, 
-                name: i.to.name, 
-                addr_one: i.to.addr_one, 
-                addr_two: i.to.addr_two, 
-                postal: i.to.postal, 
-                state: i.to.state, 
-                country: i.to.country 
-            }, 
-            items: i.items.into_iter().map(Into::into).collect(), 
-            payment: Payment { 
-                wallet_address: i.payment.wallet_address, 
-                currency: i.payment.currency, 
-                tx: i.payment.tx 
-            },
-            data: InvoiceData { 
-                invoice_number: i.data.invoice_number, 
-                due_date: i.data.due_date, 
-                issue_date: i.data.issue_date 
-            }
+    let file_raw: Vec<FileInvoice> = parser_func(file_string.as_str())?;
+    let legacy_wallets: Vec<bool> = file_raw.iter().map(|invoice| invoice.payment.wallet_address.is_some()).collect();
+    let invoices: Vec<RawInvoice> = file_raw.into_iter()
+        .map(|invoice| invoice.resolve(catalog))
+        .collect::<Result<_, _>>()?;
+
+    for (invoice, legacy_wallet) in invoices.iter().zip(legacy_wallets) {
+        // the wallet address of older files could be of any chain, it's taken as it is
+        invoice.payment.methods.iter()
+            .skip(usize::from(legacy_wallet))
+            .try_for_each(PaymentMethod::validate)?;
+        invoice.payment.validate_tx()?;
+        invoice.data.validate_history()?;
+    }
+    Ok(invoices)
+}
+
+
+impl FileInvoice {
+    /// Converts to the raw type, filling in the parties and items that refer to the catalog.
+    /// Whatever the file spells out takes precedence over the catalog.
+    pub(crate) fn resolve(self, catalog: &Catalog) -> Result<RawInvoice, CatalogError> {
+        let from = match self.from {
+            Some(from) => from.into(),
+            None => catalog.company()?.clone(),
+        };
+        let to = match (self.to, self.customer) {
+            (Some(to), _) => to.into(),
+            (None, Some(key)) => catalog.customer(&key)?.clone(),
+            (None, None) => return Err(CatalogError::MissingField("to")),
+        };
+        let items = self.items.into_iter()
+            .map(|item| item.resolve(catalog))
+            .collect::<Result<_, _>>()?;
+
+        Ok(RawInvoice { 
+            from, 
+            to, 
+            ship_to: self.ship_to.map(Into::into),
+            contact: self.contact.map(Into::into),
+            items, 
+            payment: self.payment.into(),
+            data: self.data.into(),
+        })
+    }
+}
+
+impl From<FileInvoiceData> for InvoiceData {
+    fn from(i: FileInvoiceData) -> Self {
+        InvoiceData { 
+            invoice_number: i.invoice_number, 
+            due_date: i.due_date, 
+            issue_date: i.issue_date,
+            history: i.history.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl FileItemRaw {
+    fn resolve(self, catalog: &Catalog) -> Result<ItemRaw, CatalogError> {
+        let product = self.sku.as_deref().map(|sku| catalog.product(sku)).transpose()?;
+        let tax_percent = self.tax_percent
+            .or(product.map(|p| p.tax_percent))
+            .ok_or(CatalogError::MissingField("item tax_percent"))?;
+        // an item setting its own rate doesn't take over the category of the product's rate
+        let tax_category = self.tax_category.map(Into::into)
+            .or(product.filter(|_| self.tax_percent.is_none()).map(|p| p.tax_category))
+            .unwrap_or(TaxCategory::for_rate(tax_percent));
+        Ok(ItemRaw {
+            description: self.description
+                .or_else(|| product.map(|p| p.description.clone()))
+                .ok_or(CatalogError::MissingField("item description"))?,
+            quantity: self.quantity,
+            amount: self.amount.or(product.map(|p| p.amount)).ok_or(CatalogError::MissingField("item amount"))?,
+            tax_percent,
+            tax_category,
+        })
+    }
+}
+
+impl From<FileCatalog> for Catalog {
+    fn from(f: FileCatalog) -> Self {
+        Catalog {
+            company: f.company.map(Into::into),
+            customers: f.customers.into_iter().map(|(key, info)| (key, info.into())).collect(),
+            products: f.products.into_iter().map(|(sku, p)| (sku, Product {
+                description: p.description,
+                amount: p.amount,
+                tax_percent: p.tax_percent,
+                tax_category: p.tax_category.map_or(TaxCategory::for_rate(p.tax_percent), Into::into),
+            })).collect(),
+        }
+    }
+}
+
+impl From<FileStatusChange> for StatusChange {
+    fn from(f: FileStatusChange) -> Self {
+        let status = match f.status {
+            FileInvoiceStatus::Draft => InvoiceStatus::Draft,
+            FileInvoiceStatus::Issued => InvoiceStatus::Issued,
+            FileInvoiceStatus::Paid => InvoiceStatus::Paid,
+            FileInvoiceStatus::Overdue => InvoiceStatus::Overdue,
+            FileInvoiceStatus::Void => InvoiceStatus::Void,
+        };
+        StatusChange { status, at: f.at }
+    }
+}
+
+impl From<FileTaxCategory> for TaxCategory {
+    fn from(f: FileTaxCategory) -> Self {
+        match f {
+            FileTaxCategory::Standard => TaxCategory::Standard,
+            FileTaxCategory::ZeroRated => TaxCategory::ZeroRated,
+            FileTaxCategory::Exempt => TaxCategory::Exempt,
+            FileTaxCategory::ReverseCharge => TaxCategory::ReverseCharge,
+            FileTaxCategory::IntraCommunity => TaxCategory::IntraCommunity,
+            FileTaxCategory::Export => TaxCategory::Export,
+            FileTaxCategory::OutsideScope => TaxCategory::OutsideScope,
+        }
+    }
+}
+
+impl From<FilePersonalInfo> for PersonalInfo {
+    fn from(f: FilePersonalInfo) -> Self {
+        PersonalInfo { 
+            email: f.email, 
+            name: f.name, 
+            addr_one: f.addr_one, 
+            addr_two: f.addr_two, 
+            postal: f.postal, 
+            state: f.state,
+            country: f.country,
+            vat_id: f.vat_id,
+            phone: f.phone,
+            buyer_reference: f.buyer_reference,
+            endpoint_id: f.endpoint_id,
+        }
+    }
+}
+
+impl From<FileContact> for Contact {
+    fn from(f: FileContact) -> Self {
+        Contact {
+            name: f.name,
+            department: f.department,
+            email: f.email,
+            phone: f.phone,
+        }
+    }
+}
+
+impl From<FilePayment> for Payment {
+    fn from(f: FilePayment) -> Self {
+        let mut methods: Vec<PaymentMethod> = f.methods.into_iter().map(Into::into).collect();
+
+        // files written before multiple methods were supported only had a wallet, its chain wasn't recorded
+        if let Some(address) = f.wallet_address {
+            methods.insert(0, PaymentMethod::Crypto(CryptoWallet { 
+                address, 
+                currency: f.currency.clone(),
+                chain: Chain::ETHEREUM,
+                token: None,
+                decimals: None,
+            }));
+        }
+
+        Payment {
+            currency: f.currency,
+            methods,
+            // older files use "none" for invoices that haven't been paid yet
+            tx: f.tx.filter(|tx| !tx.is_empty() && !tx.eq_ignore_ascii_case("none")),
+            settled_on: f.settled_on,
+        }
+    }
+}
+
+impl From<FilePaymentMethod> for PaymentMethod {
+    fn from(f: FilePaymentMethod) -> Self {
+        match f {
+            FilePaymentMethod::BankTransfer(bank) => PaymentMethod::BankTransfer(bank.into()),
+            FilePaymentMethod::CardLink { url } => PaymentMethod::CardLink { url },
+            FilePaymentMethod::PayPal { account } => PaymentMethod::PayPal { account },
+            FilePaymentMethod::Crypto(wallet) => PaymentMethod::Crypto(CryptoWallet {
+                address: wallet.address,
+                currency: wallet.currency,
+                chain: match wallet.chain_id {
+                    Some(chain_id) => Chain::Evm(chain_id),
+                    None => wallet.chain.into(),
+                },
+                token: wallet.token,
+                decimals: wallet.decimals,
+            }),
+        }
+    }
+}
+
+impl From<FileChain> for Chain {
+    fn from(f: FileChain) -> Self {
+        match f {
+            FileChain::Bitcoin => Chain::Bitcoin,
+            FileChain::Ethereum => Chain::ETHEREUM,
+            FileChain::Base => Chain::Evm(8453),
+            FileChain::Polygon => Chain::Evm(137),
+            FileChain::Arbitrum => Chain::Evm(42161),
+            FileChain::Optimism => Chain::Evm(10),
+            FileChain::Solana => Chain::Solana,
+        }
+    }
+}
+
+impl From<FileBankTransfer> for BankTransfer {
+    fn from(f: FileBankTransfer) -> Self {
+        BankTransfer {
+            iban: f.iban,
+            bic: f.bic,
+            account_holder: f.account_holder,
+            bank_name: f.bank_name,
+            reference: f.reference,
+        }
+    }
+}
+
+// the other way around, to write invoices back in the format they're read in
+
+impl From<ParsedInvoice> for FileInvoice {
+    /// Without the totals, this is the canonical form the audit log and the store keep
+    fn from(i: ParsedInvoice) -> Self {
+        FileInvoice {
+            from: Some(i.from.into()),
+            to: Some(i.to.into()),
+            customer: None,
+            ship_to: i.ship_to.map(Into::into),
+            contact: i.contact.map(Into::into),
+            items: i.items.into_iter()
+                .map(|item| FileItemRaw::new(item.description, item.quantity, item.unit_price, item.tax_percent, item.tax_category))
+                .collect(),
+            total: None,
+            payment: i.payment.into(),
+            data: i.data.into(),
+        }
+    }
+}
+
+impl From<RawInvoice> for FileInvoice {
+    fn from(i: RawInvoice) -> Self {
+        FileInvoice {
+            from: Some(i.from.into()),
+            to: Some(i.to.into()),
+            customer: None,
+            ship_to: i.ship_to.map(Into::into),
+            contact: i.contact.map(Into::into),
+            items: i.items.into_iter()
+                .map(|item| FileItemRaw::new(item.description, item.quantity, item.amount, item.tax_percent, item.tax_category))
+                .collect(),
+            total: None,
+            payment: i.payment.into(),
+            data: i.data.into(),
+        }
+    }
+}
+
+impl FileInvoice {
+    fn with_totals(i: ParsedInvoice) -> Self {
+        let item_totals: Vec<u32> = i.items.iter().map(|item| item.total).collect();
+        let total = FileTotal {
+            no_tax: i.total.no_tax,
+            tax_amount: i.total.tax_amount,
+            amount: i.total.amount,
+            amount_due: i.total.amount_due,
+        };
+        let mut file = FileInvoice::from(i);
+        for (item, total) in file.items.iter_mut().zip(item_totals) {
+            item.total = Some(total);
+        }
+        file.total = Some(total);
+        file
+    }
+}
+
+impl FileItemRaw {
+    fn new(description: String, quantity: u8, amount: u32, tax_percent: u8, tax_category: TaxCategory) -> Self {
+        FileItemRaw {
+            sku: None,
+            description: Some(description),
+            quantity,
+            amount: Some(amount),
+            tax_percent: Some(tax_percent),
+            // only written when it isn't the one the rate implies, so older files read back unchanged
+            tax_category: Some(tax_category)
+                .filter(|c| *c != TaxCategory::for_rate(tax_percent))
+                .map(Into::into),
+            total: None,
+        }
+    }
+}
+
+impl From<InvoiceData> for FileInvoiceData {
+    fn from(d: InvoiceData) -> Self {
+        FileInvoiceData {
+            invoice_number: d.invoice_number,
+            due_date: d.due_date,
+            issue_date: d.issue_date,
+            history: d.history.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<PersonalInfo> for FilePersonalInfo {
+    fn from(p: PersonalInfo) -> Self {
+        FilePersonalInfo {
+            email: p.email,
+            name: p.name,
+            addr_one: p.addr_one,
+            addr_two: p.addr_two,
+            postal: p.postal,
+            state: p.state,
+            country: p.country,
+            vat_id: p.vat_id,
+            phone: p.phone,
+            buyer_reference: p.buyer_reference,
+            endpoint_id: p.endpoint_id,
         }
     }
 }
 
-impl From<FileItemRaw> for ItemRaw {
-    fn from(f: FileItemRaw) -> Self {
-        ItemRaw {
-            description: f.description,
-            quantity: f.quantity,
-            amount: f.amount,
-            tax_percent: f.tax_percent,
+impl From<Contact> for FileContact {
+    fn from(c: Contact) -> Self {
+        FileContact { name: c.name, department: c.department, email: c.email, phone: c.phone }
+    }
+}
+
+impl From<TaxCategory> for FileTaxCategory {
+    fn from(c: TaxCategory) -> Self {
+        match c {
+            TaxCategory::Standard => FileTaxCategory::Standard,
+            TaxCategory::ZeroRated => FileTaxCategory::ZeroRated,
+            TaxCategory::Exempt => FileTaxCategory::Exempt,
+            TaxCategory::ReverseCharge => FileTaxCategory::ReverseCharge,
+            TaxCategory::IntraCommunity => FileTaxCategory::IntraCommunity,
+            TaxCategory::Export => FileTaxCategory::Export,
+            TaxCategory::OutsideScope => FileTaxCategory::OutsideScope,
+        }
+    }
+}
+
+impl From<StatusChange> for FileStatusChange {
+    fn from(c: StatusChange) -> Self {
+        let status = match c.status {
+            InvoiceStatus::Draft => FileInvoiceStatus::Draft,
+            InvoiceStatus::Issued => FileInvoiceStatus::Issued,
+            InvoiceStatus::Paid => FileInvoiceStatus::Paid,
+            InvoiceStatus::Overdue => FileInvoiceStatus::Overdue,
+            InvoiceStatus::Void => FileInvoiceStatus::Void,
+        };
+        FileStatusChange { status, at: c.at }
+    }
+}
+
+impl From<Payment> for FilePayment {
+    fn from(p: Payment) -> Self {
+        FilePayment {
+            wallet_address: None,
+            currency: p.currency,
+            tx: p.tx,
+            settled_on: p.settled_on,
+            methods: p.methods.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<PaymentMethod> for FilePaymentMethod {
+    fn from(m: PaymentMethod) -> Self {
+        match m {
+            PaymentMethod::BankTransfer(bank) => FilePaymentMethod::BankTransfer(FileBankTransfer {
+                iban: bank.iban,
+                bic: bank.bic,
+                account_holder: bank.account_holder,
+                bank_name: bank.bank_name,
+                reference: bank.reference,
+            }),
+            PaymentMethod::CardLink { url } => FilePaymentMethod::CardLink { url },
+            PaymentMethod::PayPal { account } => FilePaymentMethod::PayPal { account },
+            PaymentMethod::Crypto(wallet) => {
+                let (chain, chain_id) = match wallet.chain {
+                    Chain::Bitcoin => (FileChain::Bitcoin, None),
+                    Chain::Solana => (FileChain::Solana, None),
+                    Chain::Evm(1) => (FileChain::Ethereum, None),
+                    Chain::Evm(8453) => (FileChain::Base, None),
+                    Chain::Evm(137) => (FileChain::Polygon, None),
+                    Chain::Evm(42161) => (FileChain::Arbitrum, None),
+                    Chain::Evm(10) => (FileChain::Optimism, None),
+                    Chain::Evm(chain_id) => (FileChain::Ethereum, Some(chain_id)),
+                };
+                FilePaymentMethod::Crypto(FileCryptoWallet {
+                    address: wallet.address,
+                    currency: wallet.currency,
+                    chain,
+                    chain_id,
+                    token: wallet.token,
+                    decimals: wallet.decimals,
+                })
+            },
         }
     }
 }
 
 #[derive(Serialize, Deserialize)]
-struct FileInvoice {
-    from: FilePersonalInfo,
-    to: FilePersonalInfo,
+pub(crate) struct FileInvoice {
+    /// left out to use the catalog's company
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    from: Option<FilePersonalInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    to: Option<FilePersonalInfo>,
+    /// catalog key of the customer, when `to` is left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    customer: Option<String>,
+    ship_to: Option<FilePersonalInfo>,
+    contact: Option<FileContact>,
     items: Vec<FileItemRaw>,
+    /// calculated from the items, only written for other systems and left out when reading
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    total: Option<FileTotal>,
     payment: FilePayment,
     data: FileInvoiceData, 
 }
 
+/// Toml documents are tables, so the invoices are listed under a key
+#[derive(Serialize, Deserialize)]
+struct FileInvoiceList {
+    invoices: Vec<FileInvoice>,
+}
+
 #[derive(Serialize, Deserialize)]
 struct FilePersonalInfo {
     email: String,
@@ -106,14 +537,80 @@ struct FilePersonalInfo {
     postal: String,
     state: String,
     country: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vat_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    buyer_reference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    endpoint_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileContact {
+    name: String,
+    department: Option<String>,
+    email: Option<String>,
+    phone: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 struct FileItemRaw {
-    description: String,
+    /// catalog product whose description, amount and tax rate are used for the fields left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sku: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
     quantity: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    amount: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tax_percent: Option<u8>,
+    /// defaults to standard rated for a non zero rate, zero rated otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tax_category: Option<FileTaxCategory>,
+    /// calculated, only written for other systems and left out when reading
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    total: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileTotal {
+    no_tax: u32,
+    tax_amount: u32,
+    amount: u32,
+    amount_due: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileCatalog {
+    company: Option<FilePersonalInfo>,
+    #[serde(default)]
+    customers: HashMap<String, FilePersonalInfo>,
+    #[serde(default)]
+    products: HashMap<String, FileProduct>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileProduct {
+    description: String,
     amount: u32,
     tax_percent: u8,
+    #[serde(default)]
+    tax_category: Option<FileTaxCategory>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum FileTaxCategory {
+    Standard,
+    ZeroRated,
+    Exempt,
+    ReverseCharge,
+    IntraCommunity,
+    Export,
+    OutsideScope,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -121,13 +618,80 @@ struct FileInvoiceData {
     invoice_number: u16,
     due_date: String,
     issue_date: String,
+    #[serde(default)]
+    history: Vec<FileStatusChange>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileStatusChange {
+    status: FileInvoiceStatus,
+    at: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FileInvoiceStatus {
+    Draft,
+    Issued,
+    Paid,
+    Overdue,
+    Void,
 }
 
 #[derive(Serialize, Deserialize)]
 struct FilePayment {
-    wallet_address: String,
+    wallet_address: Option<String>,
+    currency: String,
+    #[serde(default)]
+    tx: Option<String>,
+    #[serde(default)]
+    settled_on: Option<String>,
+    #[serde(default)]
+    methods: Vec<FilePaymentMethod>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FilePaymentMethod {
+    BankTransfer(FileBankTransfer),
+    CardLink { url: String },
+    #[serde(rename = "paypal")]
+    PayPal { account: String },
+    Crypto(FileCryptoWallet),
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileCryptoWallet {
+    address: String,
     currency: String,
-    tx: String,
+    #[serde(default)]
+    chain: FileChain,
+    /// any other EVM network, takes precedence over `chain`
+    chain_id: Option<u64>,
+    token: Option<String>,
+    decimals: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum FileChain {
+    Bitcoin,
+    #[default]
+    Ethereum,
+    Base,
+    Polygon,
+    Arbitrum,
+    Optimism,
+    Solana,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileBankTransfer {
+    iban: String,
+    bic: Option<String>,
+    account_holder: String,
+    bank_name: Option<String>,
+    reference: Option<String>,
 }
 
 }
@@ -0,0 +1,215 @@
+use std::fmt;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::payment::normalize;
+use crate::types::{ParsedInvoice, PaymentMethod};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// A payment received, one line of a bank statement or wallet export.
+pub struct Transfer {
+    #[serde(default)]
+    pub date: Option<String>,
+    pub amount: f64,
+    pub currency: String,
+    /// Remittance information, or the memo of an on-chain transfer
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// IBAN or wallet address the payment was received on
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Transaction hash of on-chain transfers
+    #[serde(default)]
+    pub tx: Option<String>,
+}
+
+/// Reads the ledger of received transfers from a json or csv file.
+pub struct LedgerReader {}
+impl LedgerReader {
+    /// Read a json file with a slice of transfers
+    pub fn from_json(file: String) -> Result<Vec<Transfer>, Box<dyn std::error::Error>> {
+        let transfers = serde_json::from_str(&fs::read_to_string(file)?)?;
+        Ok(transfers)
+    }
+    /// Read a csv file with a header row naming the transfer fields, in any order
+    pub fn from_csv(file: String) -> Result<Vec<Transfer>, Box<dyn std::error::Error>> {
+        let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_path(file)?;
+        let transfers = reader.deserialize().collect::<Result<Vec<Transfer>, csv::Error>>()?;
+        Ok(transfers)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// How much of an invoice the matched transfers cover.
+pub enum Settlement {
+    /// No transfer could be matched to the invoice
+    Unmatched,
+    Partial,
+    Paid,
+    Overpaid,
+}
+
+#[derive(Clone, Debug)]
+/// The transfers matched to a single invoice.
+pub struct InvoiceReconciliation {
+    pub invoice_number: u16,
+    pub customer: String,
+    pub currency: String,
+    pub amount_due: f64,
+    pub received: f64,
+    pub status: Settlement,
+    pub transfers: Vec<Transfer>,
+}
+
+impl InvoiceReconciliation {
+    /// What's left to pay, negative when the invoice was overpaid
+    pub fn outstanding(&self) -> f64 {
+        (cents(self.amount_due) - cents(self.received)) as f64 / 100.0
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Result of matching a ledger against a set of invoices.
+pub struct Reconciliation {
+    pub invoices: Vec<InvoiceReconciliation>,
+    /// Transfers that don't belong to any of the invoices
+    pub unmatched: Vec<Transfer>,
+}
+
+impl Reconciliation {
+    /// Invoices with the given settlement status
+    pub fn with_status(&self, status: Settlement) -> impl Iterator<Item = &InvoiceReconciliation> {
+        self.invoices.iter().filter(move |i| i.status == status)
+    }
+}
+
+/// Matches the transfers of a ledger to the invoices they pay.
+///
+/// A transfer belongs to an invoice when, in this order:
+/// - its transaction hash is the one recorded on the invoice
+/// - its reference is the invoice's bank reference, or names the invoice number ("Invoice 376", "INV-0376", "#376")
+/// - it was received on an account or wallet of the invoice, and its amount is what's still open on it.
+///   Without an exact amount, it's only matched when no other open invoice uses that account.
+///
+/// Transfers in another currency than the invoice are never matched, amounts aren't converted.
+pub fn reconcile(invoices: &[ParsedInvoice], transfers: &[Transfer]) -> Reconciliation {
+    let mut received: Vec<Vec<Transfer>> = vec![vec![]; invoices.len()];
+    let mut unmatched = vec![];
+
+    for transfer in transfers {
+        let open = |i: usize| cents(invoices[i].total.amount_due as f64) - received[i].iter().map(|t| cents(t.amount)).sum::<i64>();
+        let candidates: Vec<usize> = (0..invoices.len())
+            .filter(|i| invoices[*i].payment.currency.eq_ignore_ascii_case(&transfer.currency))
+            .collect();
+
+        let by_tx = candidates.iter().copied().find(|i| {
+            transfer.tx.is_some() && invoices[*i].payment.tx.as_ref().is_some_and(|tx| Some(tx) == transfer.tx.as_ref())
+        });
+        let by_reference = || candidates.iter().copied().find(|i| {
+            transfer.reference.as_ref().is_some_and(|reference| references_invoice(reference, &invoices[*i]))
+        });
+        let by_account = || {
+            let account = transfer.account.as_ref()?;
+            let receiving: Vec<usize> = candidates.iter().copied()
+                .filter(|i| accepts_account(&invoices[*i], account) && open(*i) > 0)
+                .collect();
+            receiving.iter().copied().find(|i| open(*i) == cents(transfer.amount))
+                .or(if receiving.len() == 1 { Some(receiving[0]) } else { None })
+        };
+
+        match by_tx.or_else(by_reference).or_else(by_account) {
+            Some(i) => received[i].push(transfer.clone()),
+            None => unmatched.push(transfer.clone()),
+        }
+    }
+
+    let invoices = invoices.iter().zip(received).map(|(invoice, transfers)| {
+        let due = cents(invoice.total.amount_due as f64);
+        let paid: i64 = transfers.iter().map(|t| cents(t.amount)).sum();
+        let status = if transfers.is_empty() {
+            Settlement::Unmatched
+        } else if paid < due {
+            Settlement::Partial
+        } else if paid == due {
+            Settlement::Paid
+        } else {
+            Settlement::Overpaid
+        };
+
+        InvoiceReconciliation {
+            invoice_number: invoice.data.invoice_number,
+            customer: invoice.to.name.clone(),
+            currency: invoice.payment.currency.clone(),
+            amount_due: invoice.total.amount_due as f64,
+            received: paid as f64 / 100.0,
+            status,
+            transfers,
+        }
+    }).collect();
+
+    Reconciliation { invoices, unmatched }
+}
+
+impl fmt::Display for Reconciliation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for invoice in self.invoices.iter() {
+            writeln!(
+                f, "Invoice #{} ({}): {:?}, received {:.2} of {:.2} {}",
+                invoice.invoice_number, invoice.customer, invoice.status,
+                invoice.received, invoice.amount_due, invoice.currency,
+            )?;
+        }
+        for transfer in self.unmatched.iter() {
+            writeln!(
+                f, "Unmatched transfer: {:.2} {} on {}, reference {}",
+                transfer.amount, transfer.currency,
+                transfer.date.as_deref().unwrap_or("unknown date"),
+                transfer.reference.as_deref().unwrap_or("none"),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Amounts are compared in cents, so 959.999 in a ledger still pays 960
+fn cents(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
+}
+
+/// Whether a transfer reference is the invoice's bank reference, or names its number
+fn references_invoice(reference: &str, invoice: &ParsedInvoice) -> bool {
+    let normalized = normalize(reference);
+    let bank_reference = invoice.payment.methods.iter().any(|method| matches!(
+        method, PaymentMethod::BankTransfer(bank) if bank.reference.as_ref().is_some_and(|r| normalize(r) == normalized)
+    ));
+    bank_reference || mentions_number(reference, invoice.data.invoice_number)
+}
+
+/// Whether the reference names the invoice number after "invoice", "inv" or "#", like
+/// "Invoice no. 376", "INV-0376" or "#376". Other numbers, years or dates aren't taken for it.
+fn mentions_number(reference: &str, number: u16) -> bool {
+    let reference = reference.to_ascii_lowercase();
+    let number = number.to_string();
+    let separators = |c: char| c.is_whitespace() || "-.:#".contains(c);
+    ["invoice", "inv", "#"].iter().any(|marker| {
+        reference.match_indices(marker)
+            .filter(|(at, _)| *marker == "#" || !reference[..*at].ends_with(|c: char| c.is_ascii_alphanumeric()))
+            .any(|(at, _)| {
+                let rest = reference[at + marker.len()..].trim_start_matches(separators);
+                let rest = rest.strip_prefix("no").or_else(|| rest.strip_prefix("nr")).unwrap_or(rest).trim_start_matches(separators);
+                let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+                let whole = !rest[digits.len()..].starts_with(|c: char| c.is_ascii_alphanumeric());
+                whole && !digits.is_empty() && digits.trim_start_matches('0') == number
+            })
+    })
+}
+
+/// Whether the account is one of the IBANs or wallets the invoice can be paid to
+fn accepts_account(invoice: &ParsedInvoice, account: &str) -> bool {
+    invoice.payment.methods.iter().any(|method| match method {
+        PaymentMethod::BankTransfer(bank) => normalize(&bank.iban) == normalize(account),
+        PaymentMethod::Crypto(wallet) => wallet.address.eq_ignore_ascii_case(account.trim()),
+        _ => false,
+    })
+}
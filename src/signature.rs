@@ -0,0 +1,451 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Dictionary, Object, Stream, StringFormat};
+use openssl::cms::{CMSOptions, CmsContentInfo};
+use openssl::error::ErrorStack;
+use openssl::nid::Nid;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkcs7::{Pkcs7, Pkcs7Flags};
+use openssl::pkey::{PKey, Private};
+use openssl::stack::Stack;
+use openssl::x509::X509;
+
+use crate::pdfa::text_string;
+
+/// Bytes reserved for the CMS signature, enough for a 4096 bit key and a few chain certificates
+const SIGNATURE_SIZE: usize = 16384;
+/// Stands in for the byte range until the offsets are known, wide enough for any of them
+const BYTE_RANGE_PLACEHOLDER: &[u8] = b"/ByteRange[0 9999999999 9999999999 9999999999]";
+/// Font size, line height and padding in points of the text in the visible signature
+const APPEARANCE_FONT_SIZE: f64 = 10.0;
+const APPEARANCE_LEADING: f64 = 12.0;
+const APPEARANCE_PADDING: f64 = 6.0;
+
+/// Pdf that can't be signed, or whose signature doesn't hold.
+#[derive(Debug)]
+pub enum SignError {
+    Pdf(lopdf::Error),
+    OpenSsl(ErrorStack),
+    /// The certificate file has no private key or certificate in it
+    IncompleteCertificate,
+    /// Only RSA and EC keys can sign
+    UnsupportedKey,
+    /// The signature with its certificate chain takes more bytes than reserved for it
+    TooLarge(usize),
+    /// Signing rewrites the pdf, which would break the signature that is already there
+    AlreadySigned,
+    /// The pdf carries no signature to verify
+    NotSigned,
+    /// The signature is damaged, or the signed bytes were changed after signing
+    Invalid,
+}
+
+impl fmt::Display for SignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignError::Pdf(e) => write!(f, "invalid pdf: {}", e),
+            SignError::OpenSsl(e) => write!(f, "signature failed: {}", e),
+            SignError::IncompleteCertificate => write!(f, "the certificate file needs both a private key and a certificate"),
+            SignError::UnsupportedKey => write!(f, "only RSA and EC keys can sign"),
+            SignError::TooLarge(size) => write!(f, "the signature takes {} bytes, only {} are reserved", size, SIGNATURE_SIZE),
+            SignError::AlreadySigned => write!(f, "the pdf is signed already"),
+            SignError::NotSigned => write!(f, "the pdf isn't signed"),
+            SignError::Invalid => write!(f, "the signature doesn't match the signed document"),
+        }
+    }
+}
+
+impl std::error::Error for SignError {}
+
+impl From<lopdf::Error> for SignError {
+    fn from(e: lopdf::Error) -> Self {
+        SignError::Pdf(e)
+    }
+}
+
+impl From<ErrorStack> for SignError {
+    fn from(e: ErrorStack) -> Self {
+        SignError::OpenSsl(e)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+/// Where the signature shows up in the document.
+pub enum SignatureField {
+    /// Only listed in the signature panel of pdf viewers
+    Invisible,
+    /// Clickable area on a page, counted from 1, given by its lower left and upper right corner
+    /// in mm from the bottom left of the page. The signer, date and reason are drawn into it,
+    /// `SimpleInvoice::with_signature` leaves room for them below the totals.
+    Visible { page: usize, rect: [f64; 4] },
+}
+
+/// Signs pdfs with a CAdES detached CMS signature (ETSI.CAdES.detached), from the private key and
+/// certificate of a PKCS#12 file.
+///
+/// The signature is PAdES baseline B-B: its signed attributes reference the certificate and
+/// leave out the signing time, which is claimed by the `/M` entry of the signature dictionary.
+pub struct Signer {
+    key: PKey<Private>,
+    certificate: X509,
+    chain: Vec<X509>,
+    reason: Option<String>,
+    location: Option<String>,
+    field: SignatureField,
+}
+
+impl Signer {
+    /// Reads the key, certificate and chain of a PKCS#12 (.p12 / .pfx) file
+    pub fn from_pkcs12(der: &[u8], password: &str) -> Result<Self, SignError> {
+        let parsed = Pkcs12::from_der(der)?.parse2(password)?;
+        let (Some(key), Some(certificate)) = (parsed.pkey, parsed.cert) else {
+            return Err(SignError::IncompleteCertificate);
+        };
+        crate::cms::signature_algorithm(&key)?;
+        let chain = parsed.ca.map(|ca| ca.into_iter().collect()).unwrap_or_default();
+        Ok(Signer { key, certificate, chain, reason: None, location: None, field: SignatureField::Invisible })
+    }
+
+    /// Reason for signing, shown by pdf viewers
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Where the document was signed, shown by pdf viewers
+    pub fn with_location(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    pub fn with_field(mut self, field: SignatureField) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub(crate) fn set_field(&mut self, field: SignatureField) {
+        self.field = field;
+    }
+
+    /// Common name of the certificate, which is who signs
+    pub fn name(&self) -> String {
+        common_name(&self.certificate)
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+
+    /// Lines of text of the visible signature
+    fn appearance_lines(&self, signed_at: DateTime<Utc>) -> Vec<String> {
+        let mut lines = vec![
+            format!("Digitally signed by {}", self.name()),
+            format!("on {}", signed_at.format("%Y-%m-%d %H:%M UTC")),
+        ];
+        lines.extend(self.reason.clone());
+        lines
+    }
+
+    /// Height in mm the visible signature needs for its text
+    pub(crate) fn appearance_height(&self) -> f64 {
+        let lines = self.appearance_lines(Utc::now()).len() as f64;
+        (lines * APPEARANCE_LEADING + 2.0 * APPEARANCE_PADDING) * 25.4 / 72.0
+    }
+
+    /// Adds a signature field to the pdf and signs all of it.
+    ///
+    /// The pdf is rewritten with space reserved for the signature, then everything but that
+    /// space is signed and the signature is written into it.
+    pub fn sign(&self, pdf: Vec<u8>) -> Result<Vec<u8>, SignError> {
+        let mut doc = lopdf::Document::load_mem(&pdf)?;
+        if !signatures(&doc).is_empty() {
+            return Err(SignError::AlreadySigned);
+        }
+        let pages = doc.get_pages();
+        let (page, rect) = match &self.field {
+            SignatureField::Invisible => (1, [0.0; 4]),
+            SignatureField::Visible { page, rect } => (*page, *rect),
+        };
+        let page_id = *pages.get(&(page as u32)).ok_or(lopdf::Error::PageNumberNotFound(page as u32))?;
+        let signed_at = Utc::now();
+
+        let mut signature = dictionary! {
+            "Type" => "Sig",
+            "Filter" => "Adobe.PPKLite",
+            "SubFilter" => "ETSI.CAdES.detached",
+            "Name" => text_string(&self.name()),
+            "M" => Object::string_literal(signed_at.format("D:%Y%m%d%H%M%S+00'00'").to_string()),
+        };
+        if let Some(reason) = &self.reason {
+            signature.set("Reason", text_string(reason));
+        }
+        if let Some(location) = &self.location {
+            signature.set("Location", text_string(location));
+        }
+        // replaced once the offsets are known
+        signature.set("ByteRange", vec![0.into(), 9999999999i64.into(), 9999999999i64.into(), 9999999999i64.into()]);
+        signature.set("Contents", Object::String(vec![0; SIGNATURE_SIZE], StringFormat::Hexadecimal));
+        let signature = doc.add_object(signature);
+
+        // pdf user space is in points
+        let pt = |mm: f64| Object::Real(mm * 72.0 / 25.4);
+        let width = (rect[2] - rect[0]) * 72.0 / 25.4;
+        let height = (rect[3] - rect[1]) * 72.0 / 25.4;
+        let (resources, content) = match &self.field {
+            SignatureField::Invisible => (Dictionary::new(), Vec::new()),
+            SignatureField::Visible { .. } => self.appearance(&doc, page_id, width, height, signed_at)?,
+        };
+        let appearance = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), Object::Real(width), Object::Real(height)],
+                "Resources" => resources,
+            },
+            content,
+        ));
+        let field = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "FT" => "Sig",
+            "T" => Object::string_literal("Signature1"),
+            "V" => signature,
+            "Rect" => rect.iter().map(|v| pt(*v)).collect::<Vec<Object>>(),
+            // printable and locked, PDF/A doesn't allow annotations without the print flag
+            "F" => 132,
+            "P" => page_id,
+            "AP" => dictionary! { "N" => appearance },
+        });
+
+        let page = doc.get_object_mut(page_id)?.as_dict_mut()?;
+        match page.get_mut(b"Annots") {
+            Ok(Object::Array(annots)) => annots.push(field.into()),
+            _ => page.set("Annots", vec![Object::from(field)]),
+        }
+        let root = doc.trailer.get(b"Root")?.as_reference()?;
+        let form = match doc.get_object(root)?.as_dict()?.get(b"AcroForm") {
+            Ok(Object::Reference(id)) => Some(*id),
+            _ => None,
+        };
+        match form {
+            Some(id) => add_field(doc.get_object_mut(id)?.as_dict_mut()?, field),
+            None => {
+                let catalog = doc.get_object_mut(root)?.as_dict_mut()?;
+                match catalog.get_mut(b"AcroForm") {
+                    Ok(Object::Dictionary(form)) => add_field(form, field),
+                    _ => catalog.set("AcroForm", dictionary! { "Fields" => vec![Object::from(field)], "SigFlags" => 3 }),
+                }
+            },
+        }
+
+        // keeps the binary comment of PDF/A files
+        let out = crate::pdfa::save(&mut doc).map_err(lopdf::Error::IO)?;
+        self.fill_in(out)
+    }
+
+    /// Resources and content of the framed signer, date and reason, written with a font embedded
+    /// for the page so it holds for PDF/A too. Pdfs without one get Helvetica.
+    fn appearance(&self, doc: &lopdf::Document, page_id: lopdf::ObjectId, width: f64, height: f64, signed_at: DateTime<Utc>) -> Result<(Dictionary, Vec<u8>), SignError> {
+        let lines = self.appearance_lines(signed_at);
+        let (font, strings) = match page_font(doc, page_id, &lines) {
+            Some((font, glyphs)) => (font, glyphs.into_iter().map(|ids| Object::String(ids, StringFormat::Hexadecimal)).collect()),
+            None => {
+                let helvetica = dictionary! {
+                    "Type" => "Font",
+                    "Subtype" => "Type1",
+                    "BaseFont" => "Helvetica",
+                    "Encoding" => "WinAnsiEncoding",
+                };
+                let latin1 = |line: &String| line.chars().map(|c| u8::try_from(c).unwrap_or(b'?')).collect();
+                (Object::Dictionary(helvetica), lines.iter().map(|line| Object::String(latin1(line), StringFormat::Literal)).collect::<Vec<Object>>())
+            },
+        };
+
+        // shrinks the text if the field is lower than the lines need
+        let leading = (height - 2.0 * APPEARANCE_PADDING) / lines.len() as f64;
+        let size = APPEARANCE_FONT_SIZE.min(leading * APPEARANCE_FONT_SIZE / APPEARANCE_LEADING).max(1.0);
+        let mut operations = vec![
+            Operation::new("q", vec![]),
+            Operation::new("w", vec![Object::Real(0.5)]),
+            Operation::new("re", vec![Object::Real(0.25), Object::Real(0.25), Object::Real(width - 0.5), Object::Real(height - 0.5)]),
+            Operation::new("S", vec![]),
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F0".into(), Object::Real(size)]),
+            Operation::new("TL", vec![Object::Real(size * APPEARANCE_LEADING / APPEARANCE_FONT_SIZE)]),
+            Operation::new("Td", vec![Object::Real(APPEARANCE_PADDING), Object::Real(height - APPEARANCE_PADDING - size)]),
+        ];
+        for (i, string) in strings.into_iter().enumerate() {
+            if i > 0 {
+                operations.push(Operation::new("T*", vec![]));
+            }
+            operations.push(Operation::new("Tj", vec![string]));
+        }
+        operations.push(Operation::new("ET", vec![]));
+        operations.push(Operation::new("Q", vec![]));
+
+        Ok((dictionary! { "Font" => dictionary! { "F0" => font } }, Content { operations }.encode()?))
+    }
+
+    /// Writes the byte range and the signature of it into the reserved space
+    fn fill_in(&self, mut pdf: Vec<u8>) -> Result<Vec<u8>, SignError> {
+        let placeholder = [b"<".as_slice(), &b"0".repeat(2 * SIGNATURE_SIZE), b">"].concat();
+        let (Some(range_at), Some(contents_at)) = (find(&pdf, BYTE_RANGE_PLACEHOLDER), find(&pdf, &placeholder)) else {
+            return Err(SignError::Pdf(lopdf::Error::Syntax(String::from("signature placeholder not found"))));
+        };
+        let contents_end = contents_at + placeholder.len();
+        let range = [0, contents_at, contents_end, pdf.len() - contents_end];
+
+        // padded with spaces inside the array, so the offsets stay the same
+        let mut written = format!("/ByteRange[{} {} {} {}", range[0], range[1], range[2], range[3]).into_bytes();
+        written.resize(BYTE_RANGE_PLACEHOLDER.len() - 1, b' ');
+        written.push(b']');
+        pdf[range_at..range_at + written.len()].copy_from_slice(&written);
+
+        let signed = [&pdf[..contents_at], &pdf[contents_end..]].concat();
+        let der = crate::cms::sign_detached(&signed, &self.certificate, &self.chain, &self.key)?;
+        if der.len() > SIGNATURE_SIZE {
+            return Err(SignError::TooLarge(der.len()));
+        }
+        let hex: String = der.iter().map(|b| format!("{:02X}", b)).collect();
+        pdf[contents_at + 1..contents_at + 1 + hex.len()].copy_from_slice(hex.as_bytes());
+        Ok(pdf)
+    }
+}
+
+/// Embedded TrueType font of the page that has glyphs for all of the lines, and the lines as
+/// glyph ids of it
+fn page_font(doc: &lopdf::Document, page_id: lopdf::ObjectId, lines: &[String]) -> Option<(Object, Vec<Vec<u8>>)> {
+    let resources = resolve(doc, doc.get_dictionary(page_id).ok()?.get(b"Resources").ok()?)?.as_dict().ok()?;
+    let fonts = resolve(doc, resources.get(b"Font").ok()?)?.as_dict().ok()?;
+    fonts.iter().find_map(|(_, entry)| {
+        let font = resolve(doc, entry)?.as_dict().ok()?;
+        // printpdf embeds whole fonts with Identity-H encoding, so codes are glyph ids
+        if font.get(b"Encoding").and_then(Object::as_name).ok()? != b"Identity-H" {
+            return None;
+        }
+        let descendant = resolve(doc, font.get(b"DescendantFonts").and_then(Object::as_array).ok()?.first()?)?.as_dict().ok()?;
+        let descriptor = resolve(doc, descendant.get(b"FontDescriptor").ok()?)?.as_dict().ok()?;
+        let program = resolve(doc, descriptor.get(b"FontFile2").ok()?)?.as_stream().ok()?;
+        let program = rusttype::Font::from_bytes(program.decompressed_content().unwrap_or_else(|_| program.content.clone())).ok()?;
+        let glyphs = lines.iter().map(|line| {
+            line.chars()
+                .map(|c| u16::try_from(program.glyph(c).id().0).ok().filter(|id| *id != 0))
+                .collect::<Option<Vec<u16>>>()
+                .map(|ids| ids.into_iter().flat_map(u16::to_be_bytes).collect())
+        }).collect::<Option<Vec<Vec<u8>>>>()?;
+        Some((entry.clone(), glyphs))
+    })
+}
+
+fn resolve<'a>(doc: &'a lopdf::Document, object: &'a Object) -> Option<&'a Object> {
+    doc.dereference(object).ok().map(|(_, object)| object)
+}
+
+fn add_field(form: &mut Dictionary, field: lopdf::ObjectId) {
+    match form.get_mut(b"Fields") {
+        Ok(Object::Array(fields)) => fields.push(field.into()),
+        _ => form.set("Fields", vec![Object::from(field)]),
+    }
+    form.set("SigFlags", 3);
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn common_name(certificate: &X509) -> String {
+    certificate.subject_name().entries_by_nid(Nid::COMMONNAME).next()
+        .and_then(|entry| entry.data().to_string().ok())
+        .unwrap_or_default()
+}
+
+/// Signature of a pdf whose signed bytes are unchanged.
+///
+/// Only the integrity is checked, whether the certificate is trusted is up to the caller.
+pub struct VerifiedSignature {
+    /// Common name of the certificate
+    pub signer: String,
+    pub certificate: X509,
+    /// Signing time claimed by the signer, as pdf date
+    pub signed_at: Option<String>,
+    pub reason: Option<String>,
+    pub location: Option<String>,
+    /// Whether nothing was appended to the pdf after signing
+    pub covers_document: bool,
+}
+
+/// Signature dictionaries of the document
+fn signatures(doc: &lopdf::Document) -> Vec<&Dictionary> {
+    doc.objects.values()
+        .filter_map(|object| object.as_dict().ok())
+        .filter(|dict| dict.get(b"Type").and_then(Object::as_name).is_ok_and(|t| t == b"Sig"))
+        .collect()
+}
+
+fn optional_text(dict: &Dictionary, key: &[u8]) -> Option<String> {
+    let bytes = dict.get(key).and_then(Object::as_str).ok()?;
+    Some(match bytes.strip_prefix(&[0xfe, 0xff]) {
+        Some(utf16) => String::from_utf16_lossy(&utf16.chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect::<Vec<u16>>()),
+        None => String::from_utf8_lossy(bytes).into_owned(),
+    })
+}
+
+/// Whether the bytes are exactly the hex string `<...>` holding the value
+fn is_hex_string(bytes: &[u8], value: &[u8]) -> bool {
+    let Some(hex) = bytes.strip_prefix(b"<").and_then(|b| b.strip_suffix(b">")) else { return false };
+    hex.len() == value.len() * 2
+        && hex.chunks(2).zip(value).all(|(pair, byte)| {
+            std::str::from_utf8(pair).ok().and_then(|pair| u8::from_str_radix(pair, 16).ok()) == Some(*byte)
+        })
+}
+
+/// Checks every signature of the pdf against the bytes it covers
+pub fn verify(pdf: &[u8]) -> Result<Vec<VerifiedSignature>, SignError> {
+    let doc = lopdf::Document::load_mem(pdf)?;
+    let dicts = signatures(&doc);
+    if dicts.is_empty() {
+        return Err(SignError::NotSigned);
+    }
+
+    let mut verified = Vec::new();
+    for dict in dicts {
+        let range = dict.get(b"ByteRange").and_then(Object::as_array)?
+            .iter().map(|v| v.as_i64().map(|v| usize::try_from(v).ok()))
+            .collect::<Result<Vec<Option<usize>>, lopdf::Error>>()?;
+        let [Some(start), Some(first), Some(second), Some(length)] = range[..] else { return Err(SignError::Invalid) };
+        let (Some(gap), Some(end)) = (start.checked_add(first), second.checked_add(length)) else {
+            return Err(SignError::Invalid);
+        };
+        if gap > second || end > pdf.len() {
+            return Err(SignError::Invalid);
+        }
+        let signed = [&pdf[start..gap], &pdf[second..end]].concat();
+
+        // all that's left out of the signed bytes is the signature itself
+        let contents = dict.get(b"Contents").and_then(Object::as_str)?;
+        if !is_hex_string(&pdf[gap..second], contents) {
+            return Err(SignError::Invalid);
+        }
+        let mut cms = CmsContentInfo::from_der(contents).map_err(|_| SignError::Invalid)?;
+        // NOVERIFY leaves out the certificate chain, the signed bytes are still checked
+        cms.verify(None, None, Some(&signed), None, CMSOptions::DETACHED | CMSOptions::BINARY | CMSOptions::NOVERIFY)
+            .map_err(|_| SignError::Invalid)?;
+
+        let no_extra_certificates: Stack<X509> = Stack::new()?;
+        let certificate = Pkcs7::from_der(contents)?
+            .signers(&no_extra_certificates, Pkcs7Flags::empty())?
+            .into_iter().next()
+            .ok_or(SignError::Invalid)?;
+        verified.push(VerifiedSignature {
+            signer: common_name(&certificate),
+            certificate,
+            signed_at: optional_text(dict, b"M"),
+            reason: optional_text(dict, b"Reason"),
+            location: optional_text(dict, b"Location"),
+            covers_document: start == 0 && end == pdf.len(),
+        });
+    }
+    Ok(verified)
+}
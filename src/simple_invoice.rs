@@ -10,12 +10,27 @@ use askama::Template;
 #[cfg(feature = "pdf")]
 use crate::invoice::ExportsPDF;
 #[cfg(feature = "pdf")]
+use crate::types::{Contact, PersonalInfo};
+#[cfg(any(feature = "pdf", feature = "html"))]
+use crate::{qr, types::PaymentMethod};
+#[cfg(any(feature = "pdf", feature = "html"))]
+use crate::girocode::epc_payload;
+#[cfg(any(feature = "pdf", feature = "html"))]
+use crate::crypto::payment_uri;
+#[cfg(feature = "pdf")]
+use crate::qr_bill::{swiss_account, PaymentPart, QrBill};
+#[cfg(feature = "pdf")]
+use crate::links::LinkTracker;
+#[cfg(feature = "pdf")]
+use crate::pdfa::{Archive, DocumentInfo};
+#[cfg(feature = "pdf")]
 use genpdf::{ 
     error::Error as genpdfError,
-    Margins, Mm, Alignment, Document, Element, SimplePageDecorator,
+    Margins, Mm, Alignment, Context, Document, Element, Position, RenderResult,
+    render::Area,
     fonts::{FontData, FontFamily},
-    elements::{Break, FrameCellDecorator, LinearLayout, Paragraph, TableLayout, TableLayoutRow},
-    style::{Style, StyledString},
+    elements::{Break, FrameCellDecorator, PageBreak, LinearLayout, Paragraph, TableLayout, TableLayoutRow},
+    style::{Color, Style, StyledString},
 };
 
 /// Simple invoice implementation, the style is nothing special, but it does it's job. 
@@ -25,7 +40,19 @@ pub struct SimpleInvoice {
     invoice: ParsedInvoice,
 
     #[cfg(feature = "pdf")]
-    font_family: FontFamily<FontData>
+    font_family: FontFamily<FontData>,
+
+    /// Profile of the CII xml embedded into the pdf, which makes it a Factur-X PDF/A-3
+    #[cfg(all(feature = "pdf", feature = "cii"))]
+    factur_x: Option<crate::cii::CiiProfile>,
+
+    /// Archival level of the pdf, Factur-X is always PDF/A-3
+    #[cfg(feature = "pdf")]
+    pdf_a: Option<crate::pdfa::PdfA>,
+
+    /// Signs the pdf as the last step, optionally with a visible signature block
+    #[cfg(feature = "sign")]
+    signer: Option<(crate::signature::Signer, bool)>,
 }
 
 impl Invoice for SimpleInvoice {}
@@ -36,9 +63,39 @@ impl SimpleInvoice {
             invoice: Self::parse_raw_invoice(raw), 
             #[cfg(feature = "pdf")]
             font_family: Self::set_pdf_fonts().unwrap(),
+            #[cfg(all(feature = "pdf", feature = "cii"))]
+            factur_x: None,
+            #[cfg(feature = "pdf")]
+            pdf_a: None,
+            #[cfg(feature = "sign")]
+            signer: None,
         }
     }
 
+    /// Makes `to_pdf` render a Factur-X / ZUGFeRD invoice: a PDF/A-3 with the CII xml of the
+    /// profile attached as `factur-x.xml`, readable by people and accounting software alike.
+    #[cfg(all(feature = "pdf", feature = "cii"))]
+    pub fn with_factur_x(mut self, profile: crate::cii::CiiProfile) -> Self {
+        self.factur_x = Some(profile);
+        self
+    }
+
+    /// Makes `to_pdf` render a PDF/A archive of the invoice, with embedded fonts, xmp metadata and
+    /// an sRGB output intent, to keep it for the years the law asks for.
+    #[cfg(feature = "pdf")]
+    pub fn with_pdf_a(mut self, level: crate::pdfa::PdfA) -> Self {
+        self.pdf_a = Some(level);
+        self
+    }
+
+    /// Makes `to_pdf` sign the pdf with a CAdES detached signature. A visible signature gets room below
+    /// the totals, where its field shows the signer, date and reason.
+    #[cfg(feature = "sign")]
+    pub fn with_signature(mut self, signer: crate::signature::Signer, visible: bool) -> Self {
+        self.signer = Some((signer, visible));
+        self
+    }
+
     pub fn get_invoice(self) -> ParsedInvoice {
         self.invoice.clone()
     }
@@ -55,12 +112,59 @@ impl crate::invoice::ExportsHTML for SimpleInvoice {
         let invoice: TemplateableInvoice = TemplateableInvoice::from(self.invoice.clone());
         let rendered = invoice.render()?; 
 
-        let mut file = std::fs::File::create(&format!("{}.html", file_name))?;
+        let mut file = std::fs::File::create(format!("{}.html", file_name))?;
         file.write_all(rendered.as_bytes())?;
         Ok(())
     }
 }
 
+impl crate::invoice::ExportsText for SimpleInvoice {
+    fn to_text(self, file_name: String, format: crate::text::TextFormat) -> Result<(), Box<dyn stdError>> {
+        let text = crate::text::invoice_text(&self.invoice, format);
+        std::fs::write(format!("{}.{}", file_name, format.extension()), text)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ubl")]
+impl crate::invoice::ExportsUBL for SimpleInvoice {
+    fn to_ubl(self, file_name: String) -> Result<(), Box<dyn stdError>> {
+        let xml = crate::ubl::invoice_xml(&self.invoice.to_en16931());
+        std::fs::write(format!("{}.xml", file_name), xml)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cii")]
+impl crate::invoice::ExportsCII for SimpleInvoice {
+    fn to_cii(self, file_name: String, profile: crate::cii::CiiProfile) -> Result<(), Box<dyn stdError>> {
+        let xml = crate::cii::invoice_xml(&self.invoice.to_en16931(), profile);
+        std::fs::write(format!("{}.xml", file_name), xml)?;
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "ubl", feature = "cii"))]
+impl crate::invoice::ExportsXRechnung for SimpleInvoice {
+    fn to_xrechnung(self, file_name: String, syntax: crate::xrechnung::Syntax) -> Result<(), Box<dyn stdError>> {
+        let xml = crate::xrechnung::invoice_xml(&self.invoice, syntax)?;
+        std::fs::write(format!("{}.xml", file_name), xml)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ubl")]
+impl crate::invoice::ExportsPeppol for SimpleInvoice {
+    fn to_peppol(self, file_name: String) -> Result<(), Box<dyn stdError>> {
+        let xml = crate::peppol::envelope_xml(&self.invoice)?;
+        std::fs::write(format!("{}.xml", file_name), xml)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "pdf")]
+const PAGE_MARGIN: u8 = 10;
+
 #[cfg(feature = "pdf")]
 impl ExportsPDF for SimpleInvoice {
 
@@ -71,12 +175,21 @@ impl ExportsPDF for SimpleInvoice {
         let pad_box  = Margins::from(Mm::from(2));
         
         let mut doc = Document::new(self.font_family);
+        if let Some(stamp) = invoice.stamp() {
+            doc.push(Stamp(stamp));
+        }
         doc.push(Break::new(1.5));
-        doc.set_title("invoice");
+        doc.set_title(DocumentInfo::of(&self.invoice).title);
+        // PDF/A brings its own metadata and output intent instead of printpdf's PDF/X ones
+        let archived = self.pdf_a.is_some();
+        #[cfg(feature = "cii")]
+        let archived = archived || self.factur_x.is_some();
+        if archived {
+            doc.set_minimal_conformance();
+        }
 
-        let mut decorator = SimplePageDecorator::new();
-        decorator.set_margins(10);
-        doc.set_page_decorator(decorator);
+        let links = LinkTracker::default();
+        doc.set_page_decorator(links.decorator(PAGE_MARGIN));
 
         let mut layout = LinearLayout::vertical();
 
@@ -128,16 +241,12 @@ impl ExportsPDF for SimpleInvoice {
 
         row = header_table.row();
         row.push_element(Paragraph::new(invoice.from.state.clone()));
-        row.push_element(Paragraph::new(bold_styled_string("Wallet Address"))
-            .aligned(Alignment::Right).padded(pad_text)
-        );
+        row.push_element(Paragraph::new("".to_string()));
         match_row(row.push());
 
         row = header_table.row();
         row.push_element(Paragraph::new(invoice.from.country.clone()));
-        row.push_element(Paragraph::new(invoice.payment.wallet_address.clone())
-            .aligned(Alignment::Right)
-        );
+        row.push_element(Paragraph::new("".to_string()));
         match_row(row.push());
 
         row = header_table.row();
@@ -151,56 +260,141 @@ impl ExportsPDF for SimpleInvoice {
         let mut billed_table = TableLayout::new(vec![1, 1]);
         billed_table.set_cell_decorator(FrameCellDecorator::new(false, false, false));
 
+        let mut method_titles = invoice.payment.methods.iter().map(|m| m.title());
+
         row = billed_table.row();
         row.push_element(Paragraph::new( bold_styled_string("Billed to") ).padded(pad_text));
-        row.push_element(Paragraph::new(bold_styled_string("Expected payment method"))
+        row.push_element(Paragraph::new(bold_styled_string("Accepted payment methods"))
             .aligned(Alignment::Right).padded(pad_text)
         );
         match_row(row.push());
 
         row = billed_table.row();
         row.push_element(Paragraph::new(invoice.to.name.clone()));
-        row.push_element(Paragraph::new(invoice.payment.currency.clone()).aligned(Alignment::Right));
+        row.push_element(Paragraph::new(method_titles.next().unwrap_or_default()).aligned(Alignment::Right));
         match_row(row.push());
 
+        if let Some(contact) = &invoice.contact {
+            row = billed_table.row();
+            row.push_element(Paragraph::new(contact_line(contact)));
+            row.push_element(Paragraph::new(method_titles.next().unwrap_or_default()).aligned(Alignment::Right));
+            match_row(row.push());
+        }
+
         row = billed_table.row();
         row.push_element(Paragraph::new(invoice.to.email.clone()));
-        row.push_element(Paragraph::new("".to_string()));
+        row.push_element(Paragraph::new(method_titles.next().unwrap_or_default()).aligned(Alignment::Right));
         match_row(row.push());
 
         row = billed_table.row();
         row.push_element(Paragraph::new(invoice.to.addr_one.clone()));
-        row.push_element(Paragraph::new("".to_string()));
+        row.push_element(Paragraph::new(method_titles.next().unwrap_or_default()).aligned(Alignment::Right));
         match_row(row.push());
 
         row = billed_table.row();
         row.push_element(Paragraph::new(invoice.to.addr_two.clone()));
-        row.push_element(Paragraph::new("".to_string()));
+        row.push_element(Paragraph::new(method_titles.next().unwrap_or_default()).aligned(Alignment::Right));
         match_row(row.push());
 
         row = billed_table.row();
         row.push_element(Paragraph::new(invoice.to.postal.clone()));
-        row.push_element(Paragraph::new("".to_string()));
+        row.push_element(Paragraph::new(method_titles.next().unwrap_or_default()).aligned(Alignment::Right));
         match_row(row.push());
 
         row = billed_table.row();
         row.push_element(Paragraph::new(invoice.to.state.clone()));
-        row.push_element(Paragraph::new("".to_string()));
+        row.push_element(Paragraph::new(method_titles.next().unwrap_or_default()).aligned(Alignment::Right));
         match_row(row.push());
 
         row = billed_table.row();
         row.push_element(Paragraph::new(invoice.to.country.clone()));
-        row.push_element(Paragraph::new("".to_string()));
+        row.push_element(Paragraph::new(method_titles.next().unwrap_or_default()).aligned(Alignment::Right));
         match_row(row.push());
 
+        for title in method_titles {
+            row = billed_table.row();
+            row.push_element(Paragraph::new("".to_string()));
+            row.push_element(Paragraph::new(title).aligned(Alignment::Right));
+            match_row(row.push());
+        }
+
         layout.push(billed_table);
 
-        layout.push(Break::new(2));
+        // SHIPPING TEXT (table with no walls), only when delivery differs from billing
+        if let Some(ship_to) = &invoice.ship_to {
+            let mut ship_table = TableLayout::new(vec![1, 1]);
+            ship_table.set_cell_decorator(FrameCellDecorator::new(false, false, false));
+
+            row = ship_table.row();
+            row.push_element(Paragraph::new( bold_styled_string("Ship to") ).padded(pad_text));
+            row.push_element(Paragraph::new("".to_string()));
+            match_row(row.push());
+
+            for line in address_lines(ship_to) {
+                row = ship_table.row();
+                row.push_element(Paragraph::new(line));
+                row.push_element(Paragraph::new("".to_string()));
+                match_row(row.push());
+            }
+
+            layout.push(Break::new(1));
+            layout.push(ship_table);
+        }
+
+        // PAYMENT DETAILS, one section per accepted method
+        for method in invoice.payment.methods.iter() {
+            layout.push(Break::new(1));
+            layout.push(Paragraph::new(bold_styled_string(&method.title())).padded(pad_text));
+            // tokens sent on the wrong network are lost, so this goes right below the title
+            if let PaymentMethod::Crypto(wallet) = method {
+                layout.push(Paragraph::new(bold_styled_string(
+                    &format!("Only send {} on {}", wallet.currency, wallet.chain.description())
+                )));
+            }
+            for line in method_details(method) {
+                layout.push(Paragraph::new(line));
+            }
+
+            // scannable SEPA transfer, only possible for EUR invoices. Scanned once the invoice is
+            // paid, it would only pay it twice.
+            if let PaymentMethod::BankTransfer(bank) = method
+                && !invoice.is_paid()
+                && let Ok(payload) = epc_payload(
+                    bank, &invoice.payment.currency, invoice.total.amount_due, invoice.data.invoice_number
+                )
+            {
+                layout.push(qr::pdf_image(&payload, 25.0)?);
+            }
+
+            if let PaymentMethod::Crypto(wallet) = method
+                && !invoice.is_paid()
+            {
+                let uri = payment_uri(
+                    wallet, &invoice.payment.currency, invoice.total.amount_due, invoice.data.invoice_number
+                );
+                layout.push(qr::pdf_image(&uri, 25.0)?);
+            }
+        }
 
-        layout.push(Paragraph::new(bold_styled_string("Transaction")).padded(pad_text));
-        layout.push(Paragraph::new(invoice.payment.tx.clone()));
-        
         layout.push(Break::new(2));
+
+        if invoice.payment.is_paid() {
+            let paid = match &invoice.payment.settled_on {
+                Some(date) => format!("Paid on {}", date),
+                None => String::from("Paid"),
+            };
+            layout.push(Paragraph::new(bold_styled_string(&paid)).padded(pad_text));
+            // hashes are long words that genpdf can't wrap, so they get a smaller font to fit the line
+            if let Some(tx) = &invoice.payment.tx {
+                layout.push(Paragraph::new("Transaction"));
+                let hash = Paragraph::new(StyledString::new(tx.clone(), Style::new().with_font_size(9)));
+                match invoice.payment.tx_url() {
+                    Some(url) => layout.push(links.link(hash, url)),
+                    None => layout.push(hash),
+                }
+            }
+            layout.push(Break::new(2));
+        }
         
         // BOTTOM TABLE
         let mut table = TableLayout::new(vec![4, 1, 2, 1, 2]);
@@ -289,20 +483,98 @@ impl ExportsPDF for SimpleInvoice {
         layout.push(table);
 
         doc.push(layout);
+
+        #[cfg(feature = "sign")]
+        if let Some((signer, true)) = &self.signer {
+            // the signature field draws the signer, date and reason into it
+            doc.push(Break::new(1.5));
+            doc.push(links.track(Reserved(Mm::from(signer.appearance_height()))));
+        }
+
+        // swiss QR-bill payment part, on a page of its own at the end. Only possible for CHF and
+        // EUR invoices between known countries, the invoice goes without it otherwise, and once
+        // it's paid.
+        if swiss_account(&self.invoice.payment).is_some()
+            && !self.invoice.is_paid()
+            && let Ok(bill) = QrBill::from_invoice(&self.invoice)
+        {
+            doc.push(PageBreak::new());
+            doc.push(PaymentPart::new(bill, PAGE_MARGIN));
+        }
         
-        let file_path = format!("{}.pdf", file_name);
-
-        match doc.render_to_file(file_path) {
-            Ok(_)  => {
-                println!("pdf rendered!");
-                Ok(())
-            },
-            Err(e) => { Err(genpdf_error_convert(e)) },
+        let mut rendered = Vec::new();
+        doc.render(&mut rendered).map_err(genpdf_error_convert)?;
+        let mut rendered = links.annotate(rendered)?;
+        #[allow(unused_mut)]
+        let mut pdf_a = self.pdf_a;
+        #[cfg(feature = "cii")]
+        if let Some(profile) = self.factur_x {
+            let info = DocumentInfo::of(&self.invoice);
+            rendered = crate::facturx::embed(rendered, &self.invoice.to_en16931(), profile, info)?;
+            // already a PDF/A-3
+            pdf_a = None;
+        }
+        if let Some(level) = pdf_a {
+            let archive = Archive { level, info: DocumentInfo::of(&self.invoice), attachments: vec![], schemas: vec![] };
+            rendered = archive.convert(rendered)?;
+        }
+        #[cfg(feature = "sign")]
+        if let Some((mut signer, visible)) = self.signer {
+            if visible && let Some((page, rect)) = links.tracked().pop() {
+                signer.set_field(crate::signature::SignatureField::Visible { page, rect });
+            }
+            rendered = signer.sign(rendered)?;
         }
+        std::fs::write(format!("{}.pdf", file_name), rendered)?;
+        println!("pdf rendered!");
+        Ok(())
 
     }
 }
 
+#[cfg(feature = "sign")]
+/// Empty space of the given height across the area, where the visible signature goes
+struct Reserved(Mm);
+
+#[cfg(feature = "sign")]
+impl Element for Reserved {
+    fn render(&mut self, _context: &Context, area: Area<'_>, _style: Style) -> Result<RenderResult, genpdfError> {
+        let mut result = RenderResult::default();
+        if area.size().height < self.0 {
+            result.has_more = true;
+        } else {
+            result.size = genpdf::Size::new(area.size().width, self.0);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "pdf")]
+/// Big framed "PAID"/"VOID" in the top right corner, drawn over the content without taking up space
+struct Stamp(&'static str);
+
+#[cfg(feature = "pdf")]
+impl Element for Stamp {
+    fn render(&mut self, context: &Context, area: Area<'_>, style: Style) -> Result<RenderResult, genpdfError> {
+        let color = Color::Rgb(200, 30, 30);
+        let text = style.bold().with_font_size(28).with_color(color);
+        let width = text.str_width(&context.font_cache, self.0) + Mm::from(8);
+        let height = text.line_height(&context.font_cache) + Mm::from(2);
+        let left = area.size().width - width;
+
+        area.print_str(&context.font_cache, Position::new(left + Mm::from(4), Mm::from(1)), text, self.0)?;
+        let frame = Style::new().with_color(color);
+        area.draw_line(vec![
+            Position::new(left, 0),
+            Position::new(left + width, Mm::from(0)),
+            Position::new(left + width, height),
+            Position::new(left, height),
+            Position::new(left, 0),
+        ], frame);
+        Ok(RenderResult::default())
+    }
+}
+
 #[cfg(feature = "pdf")]
 fn bold_styled_string(text: &str) -> StyledString {
     StyledString {
@@ -311,6 +583,50 @@ fn bold_styled_string(text: &str) -> StyledString {
     }
 }
 
+#[cfg(feature = "pdf")]
+/// Lines describing where to send the payment for a single method
+fn method_details(method: &PaymentMethod) -> Vec<String> {
+    match method {
+        PaymentMethod::BankTransfer(bank) => {
+            let mut lines = vec![
+                format!("Account holder: {}", bank.account_holder),
+                format!("IBAN: {}", bank.iban_display()),
+            ];
+            if let Some(bic) = &bank.bic { lines.push(format!("BIC: {}", bic)); }
+            if let Some(name) = &bank.bank_name { lines.push(format!("Bank: {}", name)); }
+            if let Some(reference) = &bank.reference { lines.push(format!("Reference: {}", reference)); }
+            lines
+        },
+        PaymentMethod::CardLink { url } => vec![format!("Pay online: {}", url)],
+        PaymentMethod::PayPal { account } => vec![account.clone()],
+        PaymentMethod::Crypto(wallet) => {
+            let mut lines = vec![format!("Wallet Address: {}", wallet.address)];
+            if let Some(token) = &wallet.token { lines.push(format!("Token contract: {}", token)); }
+            lines
+        },
+    }
+}
+
+#[cfg(feature = "pdf")]
+/// Single line for the contact person, e.g. "Attn: Jane Doe, Accounting"
+fn contact_line(contact: &Contact) -> String {
+    let mut line = format!("Attn: {}", contact.name);
+    for extra in [&contact.department, &contact.email, &contact.phone].into_iter().flatten() {
+        line.push_str(&format!(", {}", extra));
+    }
+    line
+}
+
+#[cfg(feature = "pdf")]
+/// Name and postal address lines of a party, skipping the empty ones
+fn address_lines(info: &PersonalInfo) -> Vec<String> {
+    [&info.name, &info.addr_one, &info.addr_two, &info.postal, &info.state, &info.country]
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .cloned()
+        .collect()
+}
+
 #[cfg(feature = "pdf")]
 fn match_row(rowsult: Result<(), genpdfError> ) {
     match rowsult {
@@ -326,7 +642,7 @@ fn match_row(rowsult: Result<(), genpdfError> ) {
 /// meant as a helper for the pdf implementation
 pub fn genpdf_error_convert(e: genpdfError ) -> Box<dyn stdError> {
     Box::new(
-        std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+        std::io::Error::other(e.to_string())
     ) as Box<dyn stdError + 'static>
 }
 
@@ -338,18 +654,52 @@ pub fn genpdf_error_convert(e: genpdfError ) -> Box<dyn stdError> {
 struct TemplateableInvoice {
     pub from: crate::types::PersonalInfo,
     pub to: crate::types::PersonalInfo,
+    pub ship_to: Option<crate::types::PersonalInfo>,
+    pub contact: Option<crate::types::Contact>,
     pub items: Vec<crate::types::Item>,
     pub total: crate::types::Total,
     pub payment: crate::types::Payment,
     pub data: crate::types::InvoiceData,
+    pub stamp: Option<&'static str>,
+    pub paid: bool,
+}
+
+#[cfg(feature = "html")]
+impl TemplateableInvoice {
+    /// GiroCode for a bank transfer as inline svg, if the invoice allows for one
+    fn girocode_svg(&self, bank: &crate::types::BankTransfer) -> Option<String> {
+        if self.paid {
+            return None;
+        }
+        let payload = epc_payload(
+            bank, &self.payment.currency, self.total.amount_due, self.data.invoice_number
+        ).ok()?;
+        qr::svg(&payload, 160).ok()
+    }
+
+    /// Payment URI for a wallet, opened by wallet apps when clicked or scanned
+    fn crypto_uri(&self, wallet: &crate::types::CryptoWallet) -> String {
+        payment_uri(wallet, &self.payment.currency, self.total.amount_due, self.data.invoice_number)
+    }
+
+    fn crypto_qr_svg(&self, wallet: &crate::types::CryptoWallet) -> Option<String> {
+        if self.paid {
+            return None;
+        }
+        qr::svg(&self.crypto_uri(wallet), 160).ok()
+    }
 }
 
 #[cfg(feature = "html")]
 impl From<ParsedInvoice> for TemplateableInvoice {
     fn from(parsed: ParsedInvoice) -> Self {
         TemplateableInvoice {
+            stamp: parsed.stamp(),
+            paid: parsed.is_paid(),
             from: parsed.from,
             to: parsed.to,
+            ship_to: parsed.ship_to,
+            contact: parsed.contact,
             items: parsed.items,
             total: parsed.total,
             payment: parsed.payment,
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+
+use crate::types::{InvoiceData, InvoiceStatus, ParsedInvoice, StatusChange};
+
+/// Status change the lifecycle doesn't allow.
+#[derive(Debug)]
+pub enum StatusError {
+    InvalidTransition { from: InvoiceStatus, to: InvoiceStatus },
+    /// Timestamp in the history that isn't RFC 3339
+    InvalidTimestamp(String),
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatusError::InvalidTransition { from, to } => {
+                write!(f, "invoice can't go from {} to {}", from.name(), to.name())
+            },
+            StatusError::InvalidTimestamp(at) => write!(f, "invalid status timestamp: {}", at),
+        }
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+impl InvoiceStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InvoiceStatus::Draft => "draft",
+            InvoiceStatus::Issued => "issued",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Overdue => "overdue",
+            InvoiceStatus::Void => "void",
+        }
+    }
+
+    /// Drafts get issued, issued invoices get paid or become overdue, and anything that isn't
+    /// paid yet can be voided. Paid and void invoices are final.
+    pub fn can_become(&self, next: InvoiceStatus) -> bool {
+        use InvoiceStatus::*;
+        matches!(
+            (self, next),
+            (Draft, Issued) | (Draft, Void) | (Issued, Paid) | (Issued, Overdue) | (Issued, Void) | (Overdue, Paid) | (Overdue, Void)
+        )
+    }
+}
+
+impl InvoiceData {
+    /// Current status, the one of the latest change
+    pub fn status(&self) -> InvoiceStatus {
+        self.history.last().map_or(InvoiceStatus::Draft, |change| change.status)
+    }
+
+    /// Checks that every recorded change is allowed, starting from a draft
+    pub fn validate_history(&self) -> Result<(), StatusError> {
+        let mut status = InvoiceStatus::Draft;
+        for change in self.history.iter() {
+            if !status.can_become(change.status) {
+                return Err(StatusError::InvalidTransition { from: status, to: change.status });
+            }
+            DateTime::parse_from_rfc3339(&change.at).map_err(|_| StatusError::InvalidTimestamp(change.at.clone()))?;
+            status = change.status;
+        }
+        Ok(())
+    }
+}
+
+impl ParsedInvoice {
+    pub fn status(&self) -> InvoiceStatus {
+        self.data.status()
+    }
+
+    /// Moves the invoice to the next status, recording the current time
+    pub fn transition(&mut self, next: InvoiceStatus) -> Result<(), StatusError> {
+        self.transition_at(next, Utc::now())
+    }
+
+    /// Moves the invoice to the next status at the given time.
+    /// Paying an invoice also records the settlement date, unless it already has one.
+    pub fn transition_at(&mut self, next: InvoiceStatus, at: DateTime<Utc>) -> Result<(), StatusError> {
+        let current = self.status();
+        if !current.can_become(next) {
+            return Err(StatusError::InvalidTransition { from: current, to: next });
+        }
+        if next == InvoiceStatus::Paid && self.payment.settled_on.is_none() {
+            self.payment.settled_on = Some(at.date_naive().to_string());
+        }
+        self.data.history.push(StatusChange { status: next, at: at.to_rfc3339() });
+        Ok(())
+    }
+
+    /// Due date, if it's in one of the formats `parse_date` understands
+    pub fn due_date(&self) -> Option<NaiveDate> {
+        parse_date(&self.data.due_date)
+    }
+
+    /// Whether the invoice is still waiting for payment after its due date
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        matches!(self.status(), InvoiceStatus::Issued | InvoiceStatus::Overdue)
+            && self.due_date().is_some_and(|due| due < today)
+    }
+
+    /// Marks an issued invoice as overdue once its due date has passed, returns whether it changed.
+    /// The change is recorded at the start of `today`, so runs for past days keep their date.
+    pub fn mark_overdue(&mut self, today: NaiveDate) -> Result<bool, StatusError> {
+        if self.status() != InvoiceStatus::Issued || !self.is_overdue(today) {
+            return Ok(false);
+        }
+        self.transition_at(InvoiceStatus::Overdue, today.and_time(NaiveTime::MIN).and_utc())?;
+        Ok(true)
+    }
+
+    /// Whether the invoice has been paid, by its status or a recorded payment
+    pub fn is_paid(&self) -> bool {
+        self.status() == InvoiceStatus::Paid || self.payment.is_paid()
+    }
+
+    /// Stamp to put over the rendered invoice, for void and paid invoices
+    pub fn stamp(&self) -> Option<&'static str> {
+        match self.status() {
+            InvoiceStatus::Void => Some("VOID"),
+            _ if self.is_paid() => Some("PAID"),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the dates used in invoice files: "2025-01-16", "16/jan/2025", "16/01/2025", "16.01.2025"
+/// and "16 January 2025"
+pub fn parse_date(date: &str) -> Option<NaiveDate> {
+    const FORMATS: [&str; 6] = ["%Y-%m-%d", "%d/%b/%Y", "%d/%m/%Y", "%d.%m.%Y", "%d %B %Y", "%d %b %Y"];
+    FORMATS.iter().find_map(|format| NaiveDate::parse_from_str(date.trim(), format).ok())
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use crate::invoice::Invoice;
+use crate::reader::reader::FileInvoice;
+use crate::status::parse_date;
+use crate::types::{Catalog, InvoiceStatus, ParsedInvoice};
+use crate::SimpleInvoice;
+
+/// Invoices kept in a JSON-lines file, one invoice per line in the same format `InvoiceReader` reads.
+///
+/// Saving only ever appends, a newer line for the same invoice number replaces the older ones
+/// when loading. `compact` drops the replaced lines.
+pub struct InvoiceStore {
+    path: PathBuf,
+}
+
+impl InvoiceStore {
+    /// Opens the store at `path`, creating an empty one if the file doesn't exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.into();
+        OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(InvoiceStore { path })
+    }
+
+    /// Stores the invoice, replacing an earlier version with the same number
+    pub fn save(&self, invoice: &ParsedInvoice) -> Result<(), Box<dyn std::error::Error>> {
+        let line = serde_json::to_string(&FileInvoice::from(invoice.clone()))?;
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    /// Latest version of every stored invoice, ordered by invoice number
+    pub fn all(&self) -> Result<Vec<ParsedInvoice>, Box<dyn std::error::Error>> {
+        let mut invoices = BTreeMap::new();
+        for line in fs::read_to_string(&self.path)?.lines().filter(|l| !l.trim().is_empty()) {
+            let raw = serde_json::from_str::<FileInvoice>(line)?.resolve(&Catalog::default())?;
+            // the totals aren't stored, every invoice type parses raw invoices the same way
+            let invoice = SimpleInvoice::parse_raw_invoice(raw);
+            invoices.insert(invoice.data.invoice_number, invoice);
+        }
+        Ok(invoices.into_values().collect())
+    }
+
+    pub fn get(&self, invoice_number: u16) -> Result<Option<ParsedInvoice>, Box<dyn std::error::Error>> {
+        Ok(self.all()?.into_iter().find(|i| i.data.invoice_number == invoice_number))
+    }
+
+    /// Stored invoices matching every condition of the query
+    pub fn query(&self, query: &InvoiceQuery) -> Result<Vec<ParsedInvoice>, Box<dyn std::error::Error>> {
+        Ok(self.all()?.into_iter().filter(|i| query.matches(i)).collect())
+    }
+
+    /// Number following the highest one in the store, an error once 65535 is taken
+    pub fn next_invoice_number(&self) -> Result<u16, Box<dyn std::error::Error>> {
+        match self.all()?.iter().map(|i| i.data.invoice_number).max() {
+            None => Ok(1),
+            Some(highest) => Ok(highest.checked_add(1).ok_or("no invoice number left after 65535")?),
+        }
+    }
+
+    /// Rewrites the file with only the latest version of every invoice. The new file is written
+    /// next to the store and renamed over it, so a crash halfway leaves the old one intact.
+    pub fn compact(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut lines = String::new();
+        for invoice in self.all()? {
+            lines.push_str(&serde_json::to_string(&FileInvoice::from(invoice))?);
+            lines.push('\n');
+        }
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        let mut file = File::create(&temp)?;
+        file.write_all(lines.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[derive(Default, Clone)]
+/// Conditions invoices have to meet, all of the set ones have to match.
+pub struct InvoiceQuery {
+    customer: Option<String>,
+    issued_from: Option<NaiveDate>,
+    issued_to: Option<NaiveDate>,
+    status: Option<InvoiceStatus>,
+    min_amount: Option<u32>,
+    max_amount: Option<u32>,
+}
+
+impl InvoiceQuery {
+    pub fn new() -> Self {
+        InvoiceQuery::default()
+    }
+
+    /// Invoices billed to a customer, matched case insensitively on name or email
+    pub fn customer(mut self, customer: impl Into<String>) -> Self {
+        self.customer = Some(customer.into());
+        self
+    }
+
+    /// Invoices issued between both dates, inclusive. Issue dates that can't be parsed never match.
+    pub fn issued_between(mut self, from: NaiveDate, to: NaiveDate) -> Self {
+        self.issued_from = Some(from);
+        self.issued_to = Some(to);
+        self
+    }
+
+    pub fn issued_after(mut self, from: NaiveDate) -> Self {
+        self.issued_from = Some(from);
+        self
+    }
+
+    pub fn issued_before(mut self, to: NaiveDate) -> Self {
+        self.issued_to = Some(to);
+        self
+    }
+
+    /// Invoices whose current recorded status is `status`
+    pub fn status(mut self, status: InvoiceStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Invoices with an amount due between `min` and `max`, inclusive
+    pub fn amount_between(mut self, min: u32, max: u32) -> Self {
+        self.min_amount = Some(min);
+        self.max_amount = Some(max);
+        self
+    }
+
+    pub fn matches(&self, invoice: &ParsedInvoice) -> bool {
+        let customer = self.customer.as_ref().is_none_or(|c| {
+            invoice.to.name.eq_ignore_ascii_case(c) || invoice.to.email.eq_ignore_ascii_case(c)
+        });
+        let issued = parse_date(&invoice.data.issue_date);
+        let dates = (self.issued_from.is_none() && self.issued_to.is_none())
+            || issued.is_some_and(|date| {
+                self.issued_from.is_none_or(|from| date >= from) && self.issued_to.is_none_or(|to| date <= to)
+            });
+        let status = self.status.is_none_or(|s| invoice.status() == s);
+        let amount = invoice.total.amount_due;
+        let amounts = self.min_amount.is_none_or(|min| amount >= min) && self.max_amount.is_none_or(|max| amount <= max);
+
+        customer && dates && status && amounts
+    }
+}
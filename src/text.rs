@@ -0,0 +1,233 @@
+use crate::crypto::payment_uri;
+use crate::types::{ParsedInvoice, PaymentMethod, PersonalInfo};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// Flavour of the text rendering, both align the columns of the line table.
+pub enum TextFormat {
+    /// Plain text, for email bodies
+    Plain,
+    /// Markdown with a pipe table, for chat tools and issue trackers
+    Markdown,
+}
+
+impl TextFormat {
+    /// File extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TextFormat::Plain => "txt",
+            TextFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Text rendering of the invoice, with the same content as the HTML one
+pub fn invoice_text(invoice: &ParsedInvoice, format: TextFormat) -> String {
+    let mut text = TextWriter { format, out: String::new() };
+    let esc = |value: &str| escape(format, value);
+    let currency = &invoice.payment.currency;
+
+    let title = match invoice.stamp() {
+        Some(stamp) => format!("Invoice #{} ({})", invoice.data.invoice_number, stamp),
+        None => format!("Invoice #{}", invoice.data.invoice_number),
+    };
+    text.heading(1, &title);
+    text.lines(&[
+        format!("Issued on: {}", esc(&invoice.data.issue_date)),
+        format!("Payment due by: {}", esc(&invoice.data.due_date)),
+    ]);
+
+    text.heading(2, "From");
+    text.lines(&party_lines(&invoice.from, true).iter().map(|line| esc(line)).collect::<Vec<_>>());
+    text.heading(2, "Billed to");
+    let mut billed_to = party_lines(&invoice.to, true);
+    if let Some(contact) = &invoice.contact {
+        billed_to.push(format!("Attn: {}", contact.name));
+        billed_to.extend([&contact.department, &contact.email, &contact.phone].into_iter().flatten().cloned());
+    }
+    text.lines(&billed_to.iter().map(|line| esc(line)).collect::<Vec<_>>());
+    if let Some(ship_to) = &invoice.ship_to {
+        text.heading(2, "Ship to");
+        text.lines(&party_lines(ship_to, false).iter().map(|line| esc(line)).collect::<Vec<_>>());
+    }
+
+    let rows: Vec<[String; 5]> = invoice.items.iter()
+        .map(|item| [
+            esc(&item.description),
+            item.quantity.to_string(),
+            format!("{} {}", item.unit_price, currency),
+            format!("{}%", item.tax_percent),
+            format!("{} {}", item.total, currency),
+        ])
+        .collect();
+    let totals = [
+        ("Total without tax", invoice.total.no_tax),
+        ("Total Tax Amount", invoice.total.tax_amount),
+        ("Total Amount", invoice.total.amount),
+        ("Amount Due", invoice.total.amount_due),
+    ].map(|(label, amount)| (label.to_string(), format!("{} {}", amount, currency)));
+    text.table(&rows, &totals);
+
+    text.heading(2, "Accepted payment methods");
+    for method in invoice.payment.methods.iter() {
+        text.heading(3, &method.title());
+        let lines = match method {
+            PaymentMethod::BankTransfer(bank) => {
+                let mut lines = vec![format!("Account holder: {}", esc(&bank.account_holder)), format!("IBAN: {}", bank.iban_display())];
+                lines.extend(bank.bic.as_ref().map(|bic| format!("BIC: {}", bic)));
+                lines.extend(bank.bank_name.as_ref().map(|name| format!("Bank: {}", esc(name))));
+                lines.extend(bank.reference.as_ref().map(|reference| format!("Reference: {}", esc(reference))));
+                lines
+            },
+            PaymentMethod::CardLink { url } => vec![text.link("Pay online", url)],
+            PaymentMethod::PayPal { account } => vec![esc(account)],
+            PaymentMethod::Crypto(wallet) => {
+                let mut lines = vec![
+                    format!("Only send {} on {}", wallet.currency, wallet.chain.description()),
+                    format!("Wallet Address: {}", esc(&wallet.address)),
+                ];
+                lines.extend(wallet.token.as_ref().map(|token| format!("Token contract: {}", esc(token))));
+                // a paid invoice doesn't ask for the amount again
+                if !invoice.is_paid() {
+                    let uri = payment_uri(wallet, currency, invoice.total.amount_due, invoice.data.invoice_number);
+                    lines.push(text.link("Pay with wallet app", &uri));
+                }
+                lines
+            },
+        };
+        text.lines(&lines);
+    }
+
+    if invoice.payment.is_paid() {
+        text.heading(2, "Paid");
+        let mut lines = Vec::new();
+        lines.extend(invoice.payment.settled_on.as_ref().map(|date| format!("Paid on {}", esc(date))));
+        if let Some(tx) = &invoice.payment.tx {
+            lines.push(match invoice.payment.tx_url() {
+                Some(url) => format!("Transaction: {}", text.link(tx, &url)),
+                None => format!("Transaction: {}", esc(tx)),
+            });
+        }
+        text.lines(&lines);
+    }
+
+    text.out.trim_end().to_string() + "\n"
+}
+
+/// Name and address lines of a party, as in the HTML rendering
+fn party_lines(party: &PersonalInfo, email: bool) -> Vec<String> {
+    let mut lines = vec![party.name.clone()];
+    if email {
+        lines.push(party.email.clone());
+    }
+    lines.extend([&party.addr_one, &party.addr_two, &party.postal, &party.state, &party.country].into_iter().cloned());
+    lines.retain(|line| !line.is_empty());
+    lines
+}
+
+struct TextWriter {
+    format: TextFormat,
+    out: String,
+}
+
+impl TextWriter {
+    /// Underlined in plain text, the third level ends in a colon instead
+    fn heading(&mut self, level: usize, text: &str) {
+        match self.format {
+            TextFormat::Plain => {
+                let underline = match level {
+                    1 => "=",
+                    2 => "-",
+                    _ => {
+                        self.out.push_str(&format!("{}:\n", text));
+                        return;
+                    },
+                };
+                self.out.push_str(&format!("{}\n{}\n", text, underline.repeat(text.chars().count())));
+            },
+            TextFormat::Markdown => {
+                self.out.push_str(&format!("{} {}\n\n", "#".repeat(level), escape(self.format, text)));
+            },
+        }
+    }
+
+    /// Block of lines, escaped already, kept apart by hard line breaks in markdown
+    fn lines(&mut self, lines: &[String]) {
+        let separator = match self.format {
+            TextFormat::Plain => "\n",
+            TextFormat::Markdown => "  \n",
+        };
+        self.out.push_str(&lines.join(separator));
+        self.out.push_str("\n\n");
+    }
+
+    fn link(&self, text: &str, url: &str) -> String {
+        match self.format {
+            TextFormat::Plain => format!("{} <{}>", text, url),
+            TextFormat::Markdown => format!("[{}]({})", escape(self.format, text), url),
+        }
+    }
+
+    /// Line table with the totals below it, their amounts under the amount column. The cells are
+    /// escaped already.
+    fn table(&mut self, rows: &[[String; 5]], totals: &[(String, String)]) {
+        let header = ["Description", "Qty", "Unit Price", "Tax", "Amount"].map(String::from);
+        let totals: Vec<[String; 5]> = totals.iter()
+            .map(|(label, amount)| match self.format {
+                TextFormat::Plain => [label.clone(), String::new(), String::new(), String::new(), amount.clone()],
+                TextFormat::Markdown => [format!("**{}**", label), String::new(), String::new(), String::new(), format!("**{}**", amount)],
+            })
+            .collect();
+
+        let mut widths = header.each_ref().map(|cell| cell.chars().count());
+        for row in rows.iter().chain(totals.iter()) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        // the description is left aligned, the numbers right aligned
+        let cell = |i: usize, text: &str| match i {
+            0 => format!("{:<width$}", text, width = widths[i]),
+            _ => format!("{:>width$}", text, width = widths[i]),
+        };
+        let line = |row: &[String; 5]| -> String {
+            let cells: Vec<String> = row.iter().enumerate().map(|(i, text)| cell(i, text)).collect();
+            match self.format {
+                TextFormat::Plain => cells.join("  ").trim_end().to_string(),
+                TextFormat::Markdown => format!("| {} |", cells.join(" | ")),
+            }
+        };
+        let rule = match self.format {
+            TextFormat::Plain => widths.map(|w| "-".repeat(w)).join("  "),
+            TextFormat::Markdown => {
+                let rules: Vec<String> = widths.iter().enumerate()
+                    .map(|(i, w)| if i == 0 { format!(":{}", "-".repeat(w - 1)) } else { format!("{}:", "-".repeat(w - 1)) })
+                    .collect();
+                format!("| {} |", rules.join(" | "))
+            },
+        };
+
+        let mut table = vec![line(&header), rule.clone()];
+        table.extend(rows.iter().map(line));
+        if self.format == TextFormat::Plain {
+            table.push(rule);
+        }
+        table.extend(totals.iter().map(line));
+        self.out.push_str(&table.join("\n"));
+        self.out.push_str("\n\n");
+    }
+}
+
+/// Backslash escapes the characters markdown would read as formatting, plain text stays as it is
+fn escape(format: TextFormat, text: &str) -> String {
+    if format == TextFormat::Plain {
+        return text.to_string();
+    }
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<>#|".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 
 
 #[derive(Clone)]
@@ -5,6 +7,8 @@
 pub struct ParsedInvoice {
     pub from: PersonalInfo,
     pub to: PersonalInfo,
+    pub ship_to: Option<PersonalInfo>,
+    pub contact: Option<Contact>,
     pub items: Vec<Item>,
     pub total: Total,
     pub payment: Payment,
@@ -21,6 +25,24 @@ pub struct PersonalInfo {
     pub postal: String,
     pub state: String,
     pub country: String,
+    /// VAT identification number, prefixed with the country code (DE123456789)
+    pub vat_id: Option<String>,
+    pub phone: Option<String>,
+    /// Reference invoices addressed to the party have to quote, like the Leitweg-ID of German
+    /// public authorities
+    pub buyer_reference: Option<String>,
+    /// Electronic address as scheme and identifier, like the Peppol participant `0088:4035811991014`.
+    /// Without one the email is the party's electronic address.
+    pub endpoint_id: Option<String>,
+}
+
+#[derive(Clone)]
+/// Named person or department on the reciever's side, the invoice is addressed to.
+pub struct Contact {
+    pub name: String,
+    pub department: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
 }
 
 #[derive(Clone)]
@@ -29,14 +51,79 @@ pub struct InvoiceData {
     pub invoice_number: u16,
     pub due_date: String,
     pub issue_date: String,
+    /// Status changes, oldest first. An invoice without any is still a draft.
+    pub history: Vec<StatusChange>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// Where the invoice is in its lifecycle.
+pub enum InvoiceStatus {
+    Draft,
+    Issued,
+    Paid,
+    Overdue,
+    Void,
+}
+
+#[derive(Clone, Debug)]
+/// Transition of an invoice to a new status.
+pub struct StatusChange {
+    pub status: InvoiceStatus,
+    /// RFC 3339 timestamp of the change
+    pub at: String,
 }
 
 #[derive(Clone)]
 /// Payment data for the goods/services outlined in the invoice.
 pub struct Payment {
-    pub wallet_address: String,
     pub currency: String,
-    pub tx: String,
+    pub methods: Vec<PaymentMethod>,
+    /// Hash of the transaction that paid the invoice, when paid on-chain
+    pub tx: Option<String>,
+    /// Date the payment was received, marks the invoice as paid
+    pub settled_on: Option<String>,
+}
+
+#[derive(Clone)]
+/// One of the ways the reciever can pay, an invoice can accept several of them.
+pub enum PaymentMethod {
+    BankTransfer(BankTransfer),
+    /// Link to a hosted card payment page
+    CardLink { url: String },
+    /// PayPal account email or paypal.me link
+    PayPal { account: String },
+    Crypto(CryptoWallet),
+}
+
+#[derive(Clone)]
+/// Wallet the reciever should send the tokens to.
+pub struct CryptoWallet {
+    pub address: String,
+    pub currency: String,
+    pub chain: Chain,
+    /// Token contract (or mint) address, none when paying in the chain's native coin
+    pub token: Option<String>,
+    /// Decimals of the token, only needed when the currency isn't a well known one
+    pub decimals: Option<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// Network a crypto wallet lives on, decides the payment URI scheme and address format.
+pub enum Chain {
+    Bitcoin,
+    /// Any EVM network, identified by its chain id (1 is ethereum mainnet, 8453 is base, ...)
+    Evm(u64),
+    Solana,
+}
+
+#[derive(Clone)]
+/// Bank account the reciever should transfer to.
+pub struct BankTransfer {
+    pub iban: String,
+    pub bic: Option<String>,
+    pub account_holder: String,
+    pub bank_name: Option<String>,
+    pub reference: Option<String>,
 }
 
 #[derive(Clone)]
@@ -46,6 +133,7 @@ pub struct Item {
     pub quantity: u8,
     pub unit_price: u32,
     pub tax_percent: u8,
+    pub tax_category: TaxCategory,
     pub total: u32,
 }
 
@@ -58,19 +146,55 @@ pub struct Total {
     pub amount_due: u32,
 }
 
+#[derive(Clone)]
 /// Good or Service, before price calculation.
 pub struct ItemRaw {
     pub description: String,
     pub quantity: u8,
     pub amount: u32,
     pub tax_percent: u8,
+    pub tax_category: TaxCategory,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+/// VAT category of an item, following the UNCL5305 codes used by e-invoicing standards.
+pub enum TaxCategory {
+    Standard,
+    ZeroRated,
+    Exempt,
+    ReverseCharge,
+    IntraCommunity,
+    Export,
+    OutsideScope,
 }
 
+#[derive(Clone)]
 /// Invoice data with no price calculations.
 pub struct RawInvoice {
     pub from: PersonalInfo,
     pub to: PersonalInfo,
+    pub ship_to: Option<PersonalInfo>,
+    pub contact: Option<Contact>,
     pub items: Vec<ItemRaw>,
     pub payment: Payment,
     pub data: InvoiceData, 
 }
+
+#[derive(Clone, Default)]
+/// Parties and products that invoice files can refer to by key, instead of spelling them out.
+pub struct Catalog {
+    /// Our own company, used as sender when an invoice doesn't name one
+    pub company: Option<PersonalInfo>,
+    pub customers: HashMap<String, PersonalInfo>,
+    /// Products and services by their sku
+    pub products: HashMap<String, Product>,
+}
+
+#[derive(Clone)]
+/// Product or service, with the price and tax rate invoices use unless they set their own.
+pub struct Product {
+    pub description: String,
+    pub amount: u32,
+    pub tax_percent: u8,
+    pub tax_category: TaxCategory,
+}
@@ -0,0 +1,322 @@
+use std::fmt;
+
+use roxmltree::{Document, Node};
+
+use crate::en16931::{parse_item, ElectronicAddress, Party, PartyContact, PaymentMeans, PostalAddress, SemanticInvoice};
+use crate::types::{Contact, InvoiceData, Payment, PersonalInfo, RawInvoice};
+use crate::xml::{amount, XmlReader, XmlWriter};
+
+pub const INVOICE_NAMESPACE: &str = "urn:oasis:names:specification:ubl:schema:xsd:Invoice-2";
+pub const CAC_NAMESPACE: &str = "urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2";
+pub const CBC_NAMESPACE: &str = "urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2";
+/// Namespace of the Standard Business Document Header, Peppol sends documents wrapped in
+pub const SBDH_NAMESPACE: &str = "http://www.unece.org/cefact/namespaces/StandardBusinessDocumentHeader";
+
+/// UBL document that can't be read back into an invoice.
+#[derive(Debug)]
+pub enum UblError {
+    Xml(roxmltree::Error),
+    /// Root element isn't a UBL 2.1 invoice
+    NotAnInvoice,
+    /// Element the invoice can't do without, by its path
+    MissingElement(&'static str),
+    InvalidValue { element: &'static str, value: String },
+}
+
+impl fmt::Display for UblError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UblError::Xml(e) => write!(f, "invalid xml: {}", e),
+            UblError::NotAnInvoice => write!(f, "not a UBL invoice"),
+            UblError::MissingElement(path) => write!(f, "missing UBL element {}", path),
+            UblError::InvalidValue { element, value } => write!(f, "invalid value for {}: {}", element, value),
+        }
+    }
+}
+
+impl std::error::Error for UblError {}
+
+/// UBL 2.1 invoice document of the invoice, as mapped onto EN 16931
+pub fn invoice_xml(invoice: &SemanticInvoice) -> String {
+    let mut xml = XmlWriter::new();
+    write_invoice(&mut xml, invoice);
+    xml.finish()
+}
+
+pub(crate) fn write_invoice(xml: &mut XmlWriter, invoice: &SemanticInvoice) {
+    let currency = [("currencyID", invoice.currency.as_str())];
+
+    xml.open("Invoice", &[("xmlns", INVOICE_NAMESPACE), ("xmlns:cac", CAC_NAMESPACE), ("xmlns:cbc", CBC_NAMESPACE)]);
+    xml.leaf("cbc:CustomizationID", &[], &invoice.specification);
+    xml.optional("cbc:ProfileID", invoice.business_process.as_deref());
+    xml.leaf("cbc:ID", &[], &invoice.number);
+    xml.optional("cbc:IssueDate", invoice.issue_date.map(|d| d.to_string()).as_deref());
+    xml.optional("cbc:DueDate", invoice.due_date.map(|d| d.to_string()).as_deref());
+    xml.leaf("cbc:InvoiceTypeCode", &[], &invoice.type_code.to_string());
+    xml.leaf("cbc:DocumentCurrencyCode", &[], &invoice.currency);
+    xml.optional("cbc:BuyerReference", invoice.buyer_reference.as_deref());
+
+    xml.open("cac:AccountingSupplierParty", &[]);
+    write_party(xml, &invoice.seller);
+    xml.close("cac:AccountingSupplierParty");
+    xml.open("cac:AccountingCustomerParty", &[]);
+    write_party(xml, &invoice.buyer);
+    xml.close("cac:AccountingCustomerParty");
+
+    if let Some(delivery) = &invoice.delivery {
+        xml.open("cac:Delivery", &[]);
+        xml.open("cac:DeliveryLocation", &[]);
+        write_address(xml, "cac:Address", &delivery.address);
+        xml.close("cac:DeliveryLocation");
+        xml.open("cac:DeliveryParty", &[]);
+        xml.open("cac:PartyName", &[]);
+        xml.leaf("cbc:Name", &[], &delivery.name);
+        xml.close("cac:PartyName");
+        xml.close("cac:DeliveryParty");
+        xml.close("cac:Delivery");
+    }
+
+    for means in invoice.payment_means.iter() {
+        xml.open("cac:PaymentMeans", &[]);
+        xml.leaf("cbc:PaymentMeansCode", &[], means.type_code);
+        xml.optional("cbc:PaymentID", means.remittance_information.as_deref());
+        if let Some(account) = &means.account {
+            xml.open("cac:PayeeFinancialAccount", &[]);
+            xml.leaf("cbc:ID", &[], account);
+            xml.optional("cbc:Name", means.account_name.as_deref());
+            if let Some(bic) = &means.bic {
+                xml.open("cac:FinancialInstitutionBranch", &[]);
+                xml.leaf("cbc:ID", &[], bic);
+                xml.close("cac:FinancialInstitutionBranch");
+            }
+            xml.close("cac:PayeeFinancialAccount");
+        }
+        xml.close("cac:PaymentMeans");
+    }
+
+    xml.open("cac:TaxTotal", &[]);
+    xml.leaf("cbc:TaxAmount", &currency, &amount(invoice.totals.tax_total));
+    for breakdown in invoice.vat_breakdown.iter() {
+        xml.open("cac:TaxSubtotal", &[]);
+        xml.leaf("cbc:TaxableAmount", &currency, &amount(breakdown.taxable_amount));
+        xml.leaf("cbc:TaxAmount", &currency, &amount(breakdown.tax_amount));
+        xml.open("cac:TaxCategory", &[]);
+        xml.leaf("cbc:ID", &[], breakdown.category.code());
+        xml.leaf("cbc:Percent", &[], &breakdown.rate.to_string());
+        xml.optional("cbc:TaxExemptionReasonCode", breakdown.exemption_reason_code);
+        xml.optional("cbc:TaxExemptionReason", breakdown.exemption_reason.as_deref());
+        write_tax_scheme(xml);
+        xml.close("cac:TaxCategory");
+        xml.close("cac:TaxSubtotal");
+    }
+    xml.close("cac:TaxTotal");
+
+    xml.open("cac:LegalMonetaryTotal", &[]);
+    xml.leaf("cbc:LineExtensionAmount", &currency, &amount(invoice.totals.line_net_total));
+    xml.leaf("cbc:TaxExclusiveAmount", &currency, &amount(invoice.totals.tax_exclusive));
+    xml.leaf("cbc:TaxInclusiveAmount", &currency, &amount(invoice.totals.tax_inclusive));
+    if invoice.totals.paid > 0 {
+        xml.leaf("cbc:PrepaidAmount", &currency, &amount(invoice.totals.paid));
+    }
+    xml.leaf("cbc:PayableAmount", &currency, &amount(invoice.totals.amount_due));
+    xml.close("cac:LegalMonetaryTotal");
+
+    for line in invoice.lines.iter() {
+        xml.open("cac:InvoiceLine", &[]);
+        xml.leaf("cbc:ID", &[], &line.id);
+        xml.leaf("cbc:InvoicedQuantity", &[("unitCode", line.unit_code)], &line.quantity.to_string());
+        xml.leaf("cbc:LineExtensionAmount", &currency, &amount(line.net_amount));
+        xml.open("cac:Item", &[]);
+        xml.leaf("cbc:Name", &[], &line.name);
+        xml.open("cac:ClassifiedTaxCategory", &[]);
+        xml.leaf("cbc:ID", &[], line.category.code());
+        xml.leaf("cbc:Percent", &[], &line.rate.to_string());
+        write_tax_scheme(xml);
+        xml.close("cac:ClassifiedTaxCategory");
+        xml.close("cac:Item");
+        xml.open("cac:Price", &[]);
+        xml.leaf("cbc:PriceAmount", &currency, &amount(line.price));
+        xml.close("cac:Price");
+        xml.close("cac:InvoiceLine");
+    }
+    xml.close("Invoice");
+}
+
+fn write_party(xml: &mut XmlWriter, party: &Party) {
+    xml.open("cac:Party", &[]);
+    if let Some(address) = &party.electronic_address {
+        xml.leaf("cbc:EndpointID", &[("schemeID", &address.scheme)], &address.id);
+    }
+    xml.open("cac:PartyName", &[]);
+    xml.leaf("cbc:Name", &[], &party.name);
+    xml.close("cac:PartyName");
+    write_address(xml, "cac:PostalAddress", &party.address);
+    if let Some(vat_id) = &party.vat_id {
+        xml.open("cac:PartyTaxScheme", &[]);
+        xml.leaf("cbc:CompanyID", &[], vat_id);
+        write_tax_scheme(xml);
+        xml.close("cac:PartyTaxScheme");
+    }
+    xml.open("cac:PartyLegalEntity", &[]);
+    xml.leaf("cbc:RegistrationName", &[], &party.name);
+    xml.close("cac:PartyLegalEntity");
+    if let Some(contact) = &party.contact {
+        xml.open("cac:Contact", &[]);
+        xml.optional("cbc:Name", contact.name.as_deref());
+        xml.optional("cbc:Telephone", contact.phone.as_deref());
+        xml.optional("cbc:ElectronicMail", contact.email.as_deref());
+        xml.close("cac:Contact");
+    }
+    xml.close("cac:Party");
+}
+
+fn write_address(xml: &mut XmlWriter, tag: &str, address: &PostalAddress) {
+    xml.open(tag, &[]);
+    xml.optional("cbc:StreetName", address.line_one.as_deref());
+    xml.optional("cbc:CityName", address.city.as_deref());
+    xml.optional("cbc:PostalZone", address.post_code.as_deref());
+    if let Some(code) = &address.country_code {
+        xml.open("cac:Country", &[]);
+        xml.leaf("cbc:IdentificationCode", &[], code);
+        xml.close("cac:Country");
+    }
+    xml.close(tag);
+}
+
+fn write_tax_scheme(xml: &mut XmlWriter) {
+    xml.open("cac:TaxScheme", &[]);
+    xml.leaf("cbc:ID", &[], "VAT");
+    xml.close("cac:TaxScheme");
+}
+
+const UBL: XmlReader = XmlReader {
+    namespaces: &[("", INVOICE_NAMESPACE), ("cac", CAC_NAMESPACE), ("cbc", CBC_NAMESPACE)],
+};
+
+/// Reads a UBL 2.1 invoice back into a raw invoice, on its own or wrapped in a business document header.
+///
+/// Countries come back as their ISO code, and payment means other than bank transfers,
+/// card links and PayPal can't be told apart, so they're left out.
+pub fn parse_invoice(xml: &str) -> Result<RawInvoice, UblError> {
+    let document = Document::parse(xml).map_err(UblError::Xml)?;
+    let mut root = document.root_element();
+    if root.tag_name().namespace() == Some(SBDH_NAMESPACE)
+        && let Some(invoice) = UBL.children(root, "Invoice").next()
+    {
+        root = invoice;
+    }
+    if !UBL.is(&root, "Invoice") {
+        return Err(UblError::NotAnInvoice);
+    }
+
+    let seller = UBL.find(root, &["cac:AccountingSupplierParty", "cac:Party"])
+        .ok_or(UblError::MissingElement("cac:AccountingSupplierParty/cac:Party"))?;
+    let buyer = UBL.find(root, &["cac:AccountingCustomerParty", "cac:Party"])
+        .ok_or(UblError::MissingElement("cac:AccountingCustomerParty/cac:Party"))?;
+    let from = PersonalInfo::from(parse_party(seller));
+
+    let contact = UBL.find(buyer, &["cac:Contact"])
+        .and_then(|c| UBL.text(c, &["cbc:Name"]).map(|name| (c, name)))
+        .map(|(c, name)| Contact {
+            name,
+            department: None,
+            email: UBL.text(c, &["cbc:ElectronicMail"]),
+            phone: UBL.text(c, &["cbc:Telephone"]),
+        });
+
+    let ship_to = UBL.find(root, &["cac:Delivery"]).map(|delivery| {
+        PersonalInfo::from(Party {
+            name: UBL.text(delivery, &["cac:DeliveryParty", "cac:PartyName", "cbc:Name"]).unwrap_or_default(),
+            vat_id: None,
+            electronic_address: None,
+            address: UBL.find(delivery, &["cac:DeliveryLocation", "cac:Address"]).map(parse_address).unwrap_or_default(),
+            contact: None,
+        })
+    });
+
+    let mut items = vec![];
+    for line in UBL.children(root, "cac:InvoiceLine") {
+        let tax = ["cac:Item", "cac:ClassifiedTaxCategory"];
+        let item = parse_item(
+            UBL.text(line, &["cac:Item", "cbc:Name"]).unwrap_or_default(),
+            &UBL.text(line, &["cbc:InvoicedQuantity"]).ok_or(UblError::MissingElement("cac:InvoiceLine/cbc:InvoicedQuantity"))?,
+            &UBL.text(line, &["cac:Price", "cbc:PriceAmount"])
+                .ok_or(UblError::MissingElement("cac:InvoiceLine/cac:Price/cbc:PriceAmount"))?,
+            UBL.text(line, &[tax[0], tax[1], "cbc:Percent"]).as_deref(),
+            UBL.text(line, &[tax[0], tax[1], "cbc:ID"]).as_deref(),
+        );
+        items.push(item.map_err(|(element, value)| UblError::InvalidValue { element, value })?);
+    }
+
+    let methods = UBL.children(root, "cac:PaymentMeans")
+        .filter_map(|means| PaymentMeans {
+            type_code: PaymentMeans::type_code_of(&UBL.text(means, &["cbc:PaymentMeansCode"]).unwrap_or_default()),
+            remittance_information: UBL.text(means, &["cbc:PaymentID"]),
+            account: UBL.text(means, &["cac:PayeeFinancialAccount", "cbc:ID"]),
+            account_name: UBL.text(means, &["cac:PayeeFinancialAccount", "cbc:Name"]),
+            bic: UBL.text(means, &["cac:PayeeFinancialAccount", "cac:FinancialInstitutionBranch", "cbc:ID"]),
+        }.to_method(&from.name))
+        .collect();
+
+    let number = UBL.text(root, &["cbc:ID"]).ok_or(UblError::MissingElement("cbc:ID"))?;
+    Ok(RawInvoice {
+        from,
+        to: PersonalInfo {
+            buyer_reference: UBL.text(root, &["cbc:BuyerReference"]),
+            ..PersonalInfo::from(parse_party(buyer))
+        },
+        ship_to,
+        contact,
+        items,
+        payment: Payment {
+            currency: UBL.text(root, &["cbc:DocumentCurrencyCode"]).ok_or(UblError::MissingElement("cbc:DocumentCurrencyCode"))?,
+            methods,
+            tx: None,
+            settled_on: None,
+        },
+        data: InvoiceData {
+            invoice_number: number.parse().map_err(|_| UblError::InvalidValue { element: "cbc:ID", value: number.clone() })?,
+            due_date: UBL.text(root, &["cbc:DueDate"]).unwrap_or_default(),
+            issue_date: UBL.text(root, &["cbc:IssueDate"]).ok_or(UblError::MissingElement("cbc:IssueDate"))?,
+            history: vec![],
+        },
+    })
+}
+
+fn parse_party(party: Node) -> Party {
+    Party {
+        name: UBL.text(party, &["cac:PartyLegalEntity", "cbc:RegistrationName"])
+            .or_else(|| UBL.text(party, &["cac:PartyName", "cbc:Name"]))
+            .unwrap_or_default(),
+        vat_id: UBL.text(party, &["cac:PartyTaxScheme", "cbc:CompanyID"]),
+        electronic_address: UBL.find(party, &["cbc:EndpointID"]).and_then(|endpoint| Some(ElectronicAddress {
+            scheme: endpoint.attribute("schemeID")?.to_string(),
+            id: endpoint.text()?.trim().to_string(),
+        })),
+        address: UBL.find(party, &["cac:PostalAddress"]).map(parse_address).unwrap_or_default(),
+        contact: UBL.find(party, &["cac:Contact"]).map(|contact| PartyContact {
+            name: None,
+            phone: UBL.text(contact, &["cbc:Telephone"]),
+            email: UBL.text(contact, &["cbc:ElectronicMail"]),
+        }),
+    }
+}
+
+fn parse_address(address: Node) -> PostalAddress {
+    PostalAddress {
+        line_one: UBL.text(address, &["cbc:StreetName"]),
+        city: UBL.text(address, &["cbc:CityName"]),
+        post_code: UBL.text(address, &["cbc:PostalZone"]),
+        country_code: UBL.text(address, &["cac:Country", "cbc:IdentificationCode"]),
+    }
+}
+
+#[cfg(feature = "from_file")]
+impl crate::InvoiceReader {
+    /// Read a UBL 2.1 invoice and convert it to raw type
+    pub fn from_ubl(file: String) -> Result<Vec<RawInvoice>, Box<dyn std::error::Error>> {
+        let raw = parse_invoice(&std::fs::read_to_string(file)?)?;
+        raw.payment.validate()?;
+        Ok(vec![raw])
+    }
+}
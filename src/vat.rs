@@ -0,0 +1,349 @@
+use chrono::NaiveDate;
+
+use crate::country::iso_code;
+use crate::status::parse_date;
+use crate::types::{InvoiceStatus, ParsedInvoice, TaxCategory};
+
+impl TaxCategory {
+    pub const ALL: [TaxCategory; 7] = [
+        TaxCategory::Standard, TaxCategory::ZeroRated, TaxCategory::Exempt, TaxCategory::ReverseCharge,
+        TaxCategory::IntraCommunity, TaxCategory::Export, TaxCategory::OutsideScope,
+    ];
+
+    /// Category assumed when none is given, standard rated unless the rate is zero
+    pub fn for_rate(tax_percent: u8) -> TaxCategory {
+        if tax_percent > 0 { TaxCategory::Standard } else { TaxCategory::ZeroRated }
+    }
+
+    /// UNCL5305 code
+    pub fn code(&self) -> &'static str {
+        match self {
+            TaxCategory::Standard => "S",
+            TaxCategory::ZeroRated => "Z",
+            TaxCategory::Exempt => "E",
+            TaxCategory::ReverseCharge => "AE",
+            TaxCategory::IntraCommunity => "K",
+            TaxCategory::Export => "G",
+            TaxCategory::OutsideScope => "O",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<TaxCategory> {
+        TaxCategory::ALL.into_iter().find(|c| c.code() == code.trim())
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaxCategory::Standard => "Standard rated",
+            TaxCategory::ZeroRated => "Zero rated",
+            TaxCategory::Exempt => "Exempt",
+            TaxCategory::ReverseCharge => "Reverse charge",
+            TaxCategory::IntraCommunity => "Intra-community supply",
+            TaxCategory::Export => "Export outside the EU",
+            TaxCategory::OutsideScope => "Outside the scope of VAT",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// Dimension the lines of a VAT report can be summed up by.
+pub enum VatGrouping {
+    Rate,
+    Category,
+    Country,
+}
+
+impl VatGrouping {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VatGrouping::Rate => "Tax rate",
+            VatGrouping::Category => "Tax category",
+            VatGrouping::Country => "Customer country",
+        }
+    }
+
+    fn key(&self, line: &VatLine) -> String {
+        match self {
+            VatGrouping::Rate => format!("{}%", line.tax_percent),
+            VatGrouping::Category => line.category.code().to_string(),
+            VatGrouping::Country => line.country.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Net sales and tax of the items sharing a currency, rate, category and customer country.
+pub struct VatLine {
+    pub currency: String,
+    pub tax_percent: u8,
+    pub category: TaxCategory,
+    /// ISO code of the customer's country, or the country as written when it isn't known
+    pub country: String,
+    pub net: u64,
+    pub tax: u64,
+}
+
+impl VatLine {
+    pub fn gross(&self) -> u64 {
+        self.net + self.tax
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Net sales and tax summed up by one dimension, per currency.
+pub struct VatSummary {
+    pub currency: String,
+    /// Rate, category code or country, depending on the grouping
+    pub group: String,
+    pub net: u64,
+    pub tax: u64,
+}
+
+impl VatSummary {
+    pub fn gross(&self) -> u64 {
+        self.net + self.tax
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Sales and VAT collected over a period, as needed for a VAT return.
+pub struct VatReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Invoices issued within the period, in the order given
+    pub invoices: Vec<u16>,
+    /// Sorted by currency, rate, category and country
+    pub lines: Vec<VatLine>,
+    /// Issued invoices whose issue date can't be parsed, left out of the report
+    pub undated: Vec<u16>,
+}
+
+impl VatReport {
+    /// Sums up the items of the invoices issued between `from` and `to`, inclusive.
+    /// Drafts were never sent and voided invoices are cancelled, neither counts as a sale.
+    pub fn new(invoices: &[ParsedInvoice], from: NaiveDate, to: NaiveDate) -> VatReport {
+        let mut report = VatReport { from, to, invoices: vec![], lines: vec![], undated: vec![] };
+
+        for invoice in invoices.iter().filter(|i| !matches!(i.status(), InvoiceStatus::Draft | InvoiceStatus::Void)) {
+            let Some(issued) = parse_date(&invoice.data.issue_date) else {
+                report.undated.push(invoice.data.invoice_number);
+                continue;
+            };
+            if issued < from || issued > to {
+                continue;
+            }
+            report.invoices.push(invoice.data.invoice_number);
+
+            let country = iso_code(&invoice.to.country).unwrap_or_else(|| invoice.to.country.trim().to_string());
+            for item in invoice.items.iter() {
+                let net = item.unit_price as u64 * item.quantity as u64;
+                let position = report.lines.iter().position(|l| {
+                    l.currency == invoice.payment.currency
+                        && l.tax_percent == item.tax_percent
+                        && l.category == item.tax_category
+                        && l.country == country
+                });
+                let line = match position {
+                    Some(i) => &mut report.lines[i],
+                    None => {
+                        report.lines.push(VatLine {
+                            currency: invoice.payment.currency.clone(),
+                            tax_percent: item.tax_percent,
+                            category: item.tax_category,
+                            country: country.clone(),
+                            net: 0,
+                            tax: 0,
+                        });
+                        report.lines.last_mut().unwrap()
+                    },
+                };
+                line.net += net;
+                line.tax += item.total as u64 - net;
+            }
+        }
+        report.lines.sort_by(|a, b| {
+            (&a.currency, a.tax_percent, a.category, &a.country).cmp(&(&b.currency, b.tax_percent, b.category, &b.country))
+        });
+        report
+    }
+
+    /// Lines summed up by rate, category or country, sorted by currency and group
+    pub fn summary(&self, by: VatGrouping) -> Vec<VatSummary> {
+        let mut summaries = sum_up(&self.lines, |l| by.key(l));
+        // the lines are already in rate order, which sorting the "7%" labels as text would undo
+        if by != VatGrouping::Rate {
+            summaries.sort_by(|a, b| (&a.currency, &a.group).cmp(&(&b.currency, &b.group)));
+        }
+        summaries
+    }
+
+    /// Totals per currency, amounts can't be added up across currencies
+    pub fn totals(&self) -> Vec<VatSummary> {
+        sum_up(&self.lines, |_| String::from("Total"))
+    }
+
+    /// Every line as csv, with one total line per currency at the end
+    #[cfg(feature = "from_file")]
+    pub fn to_csv(&self, file_name: String) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = csv::Writer::from_path(format!("{}.csv", file_name))?;
+        writer.write_record(["currency", "tax_percent", "category", "country", "net", "tax", "gross"])?;
+        for line in self.lines.iter() {
+            writer.write_record([
+                line.currency.clone(), line.tax_percent.to_string(), line.category.code().to_string(),
+                line.country.clone(), line.net.to_string(), line.tax.to_string(), line.gross().to_string(),
+            ])?;
+        }
+        for total in self.totals() {
+            writer.write_record([
+                total.currency.clone(), String::new(), String::new(), total.group.clone(),
+                total.net.to_string(), total.tax.to_string(), total.gross().to_string(),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// The period, every line and the summaries by rate, category and country as json
+    #[cfg(feature = "from_file")]
+    pub fn to_json(&self, file_name: String) -> Result<(), Box<dyn std::error::Error>> {
+        use serde_json::{json, Value};
+
+        let summaries = |summaries: Vec<VatSummary>| -> Value {
+            summaries.iter().map(|s| json!({
+                "currency": s.currency, "group": s.group, "net": s.net, "tax": s.tax, "gross": s.gross(),
+            })).collect()
+        };
+        let lines: Value = self.lines.iter().map(|l| json!({
+            "currency": l.currency,
+            "tax_percent": l.tax_percent,
+            "category": l.category.code(),
+            "country": l.country,
+            "net": l.net,
+            "tax": l.tax,
+            "gross": l.gross(),
+        })).collect();
+
+        let report = json!({
+            "from": self.from.to_string(),
+            "to": self.to.to_string(),
+            "invoices": self.invoices,
+            "lines": lines,
+            "by_rate": summaries(self.summary(VatGrouping::Rate)),
+            "by_category": summaries(self.summary(VatGrouping::Category)),
+            "by_country": summaries(self.summary(VatGrouping::Country)),
+            "totals": summaries(self.totals()),
+            "undated": self.undated,
+        });
+        std::fs::write(format!("{}.json", file_name), serde_json::to_string_pretty(&report)?)?;
+        Ok(())
+    }
+}
+
+fn sum_up(lines: &[VatLine], key: impl Fn(&VatLine) -> String) -> Vec<VatSummary> {
+    let mut summaries: Vec<VatSummary> = vec![];
+    for line in lines.iter() {
+        let group = key(line);
+        match summaries.iter_mut().find(|s| s.currency == line.currency && s.group == group) {
+            Some(summary) => {
+                summary.net += line.net;
+                summary.tax += line.tax;
+            },
+            None => summaries.push(VatSummary { currency: line.currency.clone(), group, net: line.net, tax: line.tax }),
+        }
+    }
+    summaries
+}
+
+#[cfg(feature = "pdf")]
+impl crate::invoice::ExportsPDF for VatReport {
+    fn to_pdf(self, file_name: String) -> Result<(), Box<dyn std::error::Error>> {
+        use genpdf::{
+            elements::{Break, FrameCellDecorator, Paragraph, TableLayout},
+            style::{Style, StyledString},
+            Alignment, Document, Element, Margins, Mm, SimplePageDecorator,
+        };
+        use crate::simple_invoice::genpdf_error_convert;
+
+        let pad_box = Margins::from(Mm::from(1));
+        let bold = |s: &str| StyledString::new(s.to_string(), Style::new().bold());
+        let amounts = |net: u64, tax: u64| [net, tax, net + tax];
+
+        let mut doc = Document::new(Self::set_pdf_fonts()?);
+        doc.set_title(format!("VAT report {} - {}", self.from, self.to));
+        doc.set_font_size(9);
+        let mut decorator = SimplePageDecorator::new();
+        decorator.set_margins(10);
+        doc.set_page_decorator(decorator);
+
+        doc.push(Paragraph::new(StyledString::new(
+            format!("Sales and VAT from {} to {}", self.from, self.to), Style::new().bold().with_font_size(14),
+        )));
+        doc.push(Paragraph::new(format!("{} invoices", self.invoices.len())));
+        doc.push(Break::new(1.5));
+
+        // DETAILS, one row per currency, rate, category and country
+        let mut table = TableLayout::new(vec![2, 2, 4, 2, 3, 3, 3]);
+        table.set_cell_decorator(FrameCellDecorator::new(true, true, true));
+        let mut row = table.row();
+        for (title, align) in [
+            ("Currency", Alignment::Left), ("Rate", Alignment::Right), ("Category", Alignment::Left),
+            ("Country", Alignment::Left), ("Net", Alignment::Right), ("Tax", Alignment::Right), ("Gross", Alignment::Right),
+        ] {
+            row.push_element(Paragraph::new(bold(title)).aligned(align).padded(pad_box));
+        }
+        row.push().map_err(genpdf_error_convert)?;
+
+        for line in self.lines.iter() {
+            let mut row = table.row();
+            row.push_element(Paragraph::new(line.currency.clone()).padded(pad_box));
+            row.push_element(Paragraph::new(format!("{}%", line.tax_percent)).aligned(Alignment::Right).padded(pad_box));
+            row.push_element(Paragraph::new(line.category.label()).padded(pad_box));
+            row.push_element(Paragraph::new(line.country.clone()).padded(pad_box));
+            for amount in amounts(line.net, line.tax) {
+                row.push_element(Paragraph::new(amount.to_string()).aligned(Alignment::Right).padded(pad_box));
+            }
+            row.push().map_err(genpdf_error_convert)?;
+        }
+        doc.push(table);
+
+        // SUMMARIES, by every dimension and the totals last
+        let summaries = [VatGrouping::Rate, VatGrouping::Category, VatGrouping::Country]
+            .map(|by| (format!("By {}", by.label().to_lowercase()), by.label(), self.summary(by)));
+        let totals = (String::from("Totals"), "", self.totals());
+        for (heading, title, summaries) in summaries.into_iter().chain(std::iter::once(totals)) {
+            doc.push(Break::new(2));
+            doc.push(Paragraph::new(bold(&heading)));
+            doc.push(Break::new(0.5));
+            let mut table = TableLayout::new(vec![2, 4, 3, 3, 3]);
+            table.set_cell_decorator(FrameCellDecorator::new(true, true, true));
+            let mut row = table.row();
+            for (title, align) in [
+                ("Currency", Alignment::Left), (title, Alignment::Left),
+                ("Net", Alignment::Right), ("Tax", Alignment::Right), ("Gross", Alignment::Right),
+            ] {
+                row.push_element(Paragraph::new(bold(title)).aligned(align).padded(pad_box));
+            }
+            row.push().map_err(genpdf_error_convert)?;
+
+            for summary in summaries.iter() {
+                let mut row = table.row();
+                row.push_element(Paragraph::new(summary.currency.clone()).padded(pad_box));
+                row.push_element(Paragraph::new(summary.group.clone()).padded(pad_box));
+                for amount in amounts(summary.net, summary.tax) {
+                    row.push_element(Paragraph::new(amount.to_string()).aligned(Alignment::Right).padded(pad_box));
+                }
+                row.push().map_err(genpdf_error_convert)?;
+            }
+            doc.push(table);
+        }
+
+        if !self.undated.is_empty() {
+            doc.push(Break::new(1));
+            let numbers: Vec<String> = self.undated.iter().map(|n| format!("#{}", n)).collect();
+            doc.push(Paragraph::new(format!("Left out, issue date can't be read: {}", numbers.join(", "))));
+        }
+
+        doc.render_to_file(format!("{}.pdf", file_name)).map_err(genpdf_error_convert)?;
+        Ok(())
+    }
+}
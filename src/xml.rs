@@ -0,0 +1,100 @@
+#[cfg(any(feature = "ubl", feature = "cii"))]
+use roxmltree::Node;
+
+#[cfg(any(feature = "ubl", feature = "cii"))]
+/// Minimal writer for the xml invoice formats, elements are indented by two spaces per level.
+pub(crate) struct XmlWriter {
+    out: String,
+    depth: usize,
+}
+
+#[cfg(any(feature = "ubl", feature = "cii"))]
+impl XmlWriter {
+    pub fn new() -> Self {
+        XmlWriter { out: String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"), depth: 0 }
+    }
+
+    pub fn open(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        self.indent();
+        self.out.push_str(&format!("<{}{}>\n", tag, Self::attributes(attributes)));
+        self.depth += 1;
+    }
+
+    pub fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.indent();
+        self.out.push_str(&format!("</{}>\n", tag));
+    }
+
+    pub fn leaf(&mut self, tag: &str, attributes: &[(&str, &str)], text: &str) {
+        self.indent();
+        self.out.push_str(&format!("<{}{}>{}</{}>\n", tag, Self::attributes(attributes), escape(text), tag));
+    }
+
+    /// Leaf that's left out entirely when there's no value
+    pub fn optional(&mut self, tag: &str, text: Option<&str>) {
+        if let Some(text) = text {
+            self.leaf(tag, &[], text);
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    fn indent(&mut self) {
+        self.out.push_str(&"  ".repeat(self.depth));
+    }
+
+    fn attributes(attributes: &[(&str, &str)]) -> String {
+        attributes.iter().map(|(name, value)| format!(" {}=\"{}\"", name, escape(value))).collect()
+    }
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(any(feature = "ubl", feature = "cii"))]
+/// Whole currency units as the decimal amount the xml formats expect
+pub(crate) fn amount(value: impl Into<u64>) -> String {
+    format!("{}.00", value.into())
+}
+
+#[cfg(any(feature = "ubl", feature = "cii"))]
+/// Looks up elements by qualified names like `cbc:ID`, with the prefixes a format uses mapped
+/// to their namespaces. Names without a prefix are in the namespace of the empty prefix.
+#[derive(Clone, Copy)]
+pub(crate) struct XmlReader {
+    pub namespaces: &'static [(&'static str, &'static str)],
+}
+
+#[cfg(any(feature = "ubl", feature = "cii"))]
+impl XmlReader {
+    pub fn is(&self, node: &Node, name: &str) -> bool {
+        let (prefix, local) = name.split_once(':').unwrap_or(("", name));
+        let namespace = self.namespaces.iter().find(|(p, _)| *p == prefix).map(|(_, n)| *n);
+        node.is_element() && node.tag_name().name() == local && node.tag_name().namespace() == namespace
+    }
+
+    pub fn children<'a, 'input>(&self, node: Node<'a, 'input>, name: &'static str) -> impl Iterator<Item = Node<'a, 'input>> {
+        let reader = *self;
+        node.children().filter(move |n| reader.is(n, name))
+    }
+
+    pub fn find<'a, 'input>(&self, node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+        path.iter().try_fold(node, |node, name| node.children().find(|n| self.is(n, name)))
+    }
+
+    /// Trimmed text of the element at `path`, none when it's missing or empty
+    pub fn text(&self, node: Node, path: &[&str]) -> Option<String> {
+        self.find(node, path)
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+
+use crate::en16931::{RuleViolation, SemanticInvoice, EMAIL_SCHEME};
+use crate::payment::validate_iban;
+use crate::types::{ParsedInvoice, TaxCategory};
+
+/// Specification identifier (BT-24) of XRechnung 3.0, the German CIUS of EN 16931
+pub const SPECIFICATION: &str = "urn:cen.eu:en16931:2017#compliant#urn:xeinkauf.de:kosit:xrechnung_3.0";
+/// Invoice type codes (BT-3) XRechnung accepts
+pub const TYPE_CODES: [u16; 8] = [326, 380, 384, 389, 381, 875, 876, 877];
+/// Electronic address scheme (EAS) of Leitweg-IDs
+pub const LEITWEG_SCHEME: &str = "0204";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// Xml syntax an XRechnung is written in, both carry the same data.
+pub enum Syntax {
+    #[cfg(feature = "ubl")]
+    Ubl,
+    #[cfg(feature = "cii")]
+    Cii,
+}
+
+/// Invoice that breaks XRechnung rules, so it can't be exported as one.
+#[derive(Debug)]
+pub struct XRechnungError(pub Vec<RuleViolation>);
+
+impl fmt::Display for XRechnungError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules: Vec<String> = self.0.iter().map(|v| v.to_string()).collect();
+        write!(f, "not a valid XRechnung: {}", rules.join(", "))
+    }
+}
+
+impl std::error::Error for XRechnungError {}
+
+impl ParsedInvoice {
+    /// Maps the invoice onto EN 16931 with the XRechnung specification identifier.
+    ///
+    /// XRechnung asks for a seller contact point (BT-41), the seller's name stands in for it when
+    /// there is no contact person.
+    pub fn to_xrechnung(&self) -> SemanticInvoice {
+        let mut invoice = self.to_en16931();
+        invoice.specification = SPECIFICATION.to_string();
+        if let Some(contact) = &mut invoice.seller.contact
+            && contact.name.is_none()
+        {
+            contact.name = Some(invoice.seller.name.clone());
+        }
+        invoice
+    }
+}
+
+impl SemanticInvoice {
+    /// Rules of EN 16931 and the BR-DE rules of XRechnung the invoice doesn't satisfy
+    pub fn validate_xrechnung(&self) -> Vec<RuleViolation> {
+        let mut violations = self.validate();
+        let mut check = |ok: bool, rule: &str, message: &str| {
+            if !ok {
+                violations.push(RuleViolation { rule: rule.to_string(), message: message.to_string() });
+            }
+        };
+
+        check(!self.payment_means.is_empty(), "BR-DE-1", "payment instructions (BG-16) missing");
+        let contact = self.seller.contact.as_ref();
+        check(contact.is_some(), "BR-DE-2", "seller contact (BG-6) missing");
+        check(self.seller.address.city.is_some(), "BR-DE-3", "seller city (BT-37) missing");
+        check(self.seller.address.post_code.is_some(), "BR-DE-4", "seller post code (BT-38) missing");
+        if let Some(contact) = contact {
+            check(contact.name.is_some(), "BR-DE-5", "seller contact point (BT-41) missing");
+            check(contact.phone.is_some(), "BR-DE-6", "seller contact telephone number (BT-42) missing");
+            check(contact.email.is_some(), "BR-DE-7", "seller contact email address (BT-43) missing");
+        }
+        check(self.buyer.address.city.is_some(), "BR-DE-8", "buyer city (BT-52) missing");
+        check(self.buyer.address.post_code.is_some(), "BR-DE-9", "buyer post code (BT-53) missing");
+        if let Some(delivery) = &self.delivery {
+            check(delivery.address.city.is_some(), "BR-DE-10", "deliver to city (BT-77) missing");
+            check(delivery.address.post_code.is_some(), "BR-DE-11", "deliver to post code (BT-78) missing");
+        }
+        check(self.buyer_reference.is_some(), "BR-DE-15", "buyer reference (BT-10), the Leitweg-ID, missing");
+        if let Some(reference) = &self.buyer_reference
+            && self.is_public_authority_buyer()
+        {
+            check(is_leitweg_id(reference), "BT-10", &format!("{} isn't a valid Leitweg-ID", reference));
+        }
+        check(
+            self.seller.vat_id.is_some() || self.lines.iter().all(|l| l.category == TaxCategory::OutsideScope),
+            "BR-DE-16", "seller VAT identifier (BT-31) missing",
+        );
+        check(TYPE_CODES.contains(&self.type_code), "BR-DE-17", "invoice type code (BT-3) isn't one XRechnung accepts");
+        for means in self.payment_means.iter().filter(|m| m.type_code == "58") {
+            let valid = means.account.as_deref().is_some_and(|iban| validate_iban(iban).is_ok());
+            check(valid, "BR-DE-19", "SEPA credit transfer without a valid IBAN (BT-84)");
+        }
+        check(self.specification == SPECIFICATION, "BR-DE-21", "specification identifier (BT-24) isn't the XRechnung one");
+        // optional in EN 16931, mandatory since XRechnung 3.0
+        check(self.seller.electronic_address.is_some(), "BT-34", "seller electronic address missing");
+        check(self.buyer.electronic_address.is_some(), "BT-49", "buyer electronic address missing");
+
+        let contacts = [&self.seller, &self.buyer].into_iter().filter_map(|p| p.contact.as_ref());
+        for contact in contacts {
+            if let Some(phone) = &contact.phone {
+                check(
+                    phone.chars().filter(|c| c.is_ascii_digit()).count() >= 3,
+                    "BR-DE-27", &format!("telephone number {} has less than three digits", phone),
+                );
+            }
+            if let Some(email) = &contact.email {
+                check(is_email(email), "BR-DE-28", &format!("{} isn't an email address", email));
+            }
+        }
+        for party in [&self.seller, &self.buyer] {
+            if let Some(address) = &party.electronic_address
+                && address.scheme == EMAIL_SCHEME
+            {
+                check(is_email(&address.id), "BR-DE-28", &format!("{} isn't an email address", address.id));
+            }
+        }
+        violations
+    }
+
+    /// Whether the buyer is a German public authority, addressed by a Leitweg-ID. That's a buyer
+    /// whose electronic address has the Leitweg-ID scheme, or a German one whose reference starts
+    /// with a digit like the coarse address of every Leitweg-ID does.
+    fn is_public_authority_buyer(&self) -> bool {
+        let by_address = self.buyer.electronic_address.as_ref().is_some_and(|a| a.scheme == LEITWEG_SCHEME);
+        let by_reference = self.buyer.address.country_code.as_deref() == Some("DE")
+            && self.buyer_reference.as_deref().is_some_and(|r| r.starts_with(|c: char| c.is_ascii_digit()));
+        by_address || by_reference
+    }
+}
+
+/// Leitweg-ID of a German public authority: a coarse address of 2 to 12 digits, an optional fine
+/// address of up to 30 letters and digits, and two check digits, apart by dashes. The check digits
+/// are ISO 7064 MOD 97-10 over both addresses, letters counted from 10 for A like in IBANs.
+pub fn is_leitweg_id(id: &str) -> bool {
+    let parts: Vec<&str> = id.trim().split('-').collect();
+    let (coarse, fine, check) = match parts[..] {
+        [coarse, check] => (coarse, "", check),
+        [coarse, fine, check] if !fine.is_empty() => (coarse, fine, check),
+        _ => return false,
+    };
+    if !(2..=12).contains(&coarse.len()) || !coarse.chars().all(|c| c.is_ascii_digit())
+        || fine.len() > 30 || !fine.chars().all(|c| c.is_ascii_alphanumeric())
+        || check.len() != 2 || !check.chars().all(|c| c.is_ascii_digit())
+    {
+        return false;
+    }
+    let remainder = format!("{}{}{}", coarse, fine, check).chars()
+        .filter_map(|c| c.to_digit(36))
+        .fold(0, |rest, value| if value < 10 { (rest * 10 + value) % 97 } else { (rest * 100 + value) % 97 });
+    remainder == 1
+}
+
+/// Exactly one @, with something on both sides that doesn't start or end with a dot
+fn is_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !domain.contains('@')
+                && [local, domain].iter().all(|part| !part.is_empty() && !part.starts_with('.') && !part.ends_with('.'))
+        },
+        None => false,
+    }
+}
+
+/// XRechnung document of the invoice in the given syntax, after checking it against the
+/// EN 16931 and XRechnung rules.
+pub fn invoice_xml(invoice: &ParsedInvoice, syntax: Syntax) -> Result<String, XRechnungError> {
+    let invoice = invoice.to_xrechnung();
+    let violations = invoice.validate_xrechnung();
+    if !violations.is_empty() {
+        return Err(XRechnungError(violations));
+    }
+    Ok(match syntax {
+        #[cfg(feature = "ubl")]
+        Syntax::Ubl => crate::ubl::invoice_xml(&invoice),
+        #[cfg(feature = "cii")]
+        Syntax::Cii => crate::cii::invoice_xml(&invoice, crate::cii::CiiProfile::EN16931),
+    })
+}
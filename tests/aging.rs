@@ -0,0 +1,96 @@
+use chrono::NaiveDate;
+use factura::aging::{AgingBucket, AgingReport};
+use factura::reconcile::{reconcile, Transfer};
+use factura::types::{InvoiceStatus, ParsedInvoice};
+use factura::{ExportsHTML, ExportsPDF};
+
+mod common;
+
+fn invoice(number: u16, customer: &str, due_date: &str, amount: u32) -> ParsedInvoice {
+    let item = format!(r#"{{ "description": "Transport fee", "quantity": 1, "amount": {}, "tax_percent": 0 }}"#, amount);
+    common::invoice(number)
+        .buyer(customer)
+        .items(&[&item])
+        .issued("1/jan/2025")
+        .due(due_date)
+        .history(r#"[ { "status": "issued", "at": "2025-01-01T10:00:00Z" } ]"#)
+        .parsed()
+}
+
+/// Invoices due at different points before the 2025-06-30 report date
+fn invoices() -> Vec<ParsedInvoice> {
+    let mut paid = invoice(6, "Super Corp", "2025-01-01", 999);
+    paid.transition(InvoiceStatus::Paid).unwrap();
+    vec![
+        invoice(1, "Super Corp", "2025-07-15", 100),
+        invoice(2, "Super Corp", "2025-06-30", 200),
+        invoice(3, "Super Corp", "2025-05-01", 300),
+        invoice(4, "Mega Corp", "2025-04-15", 400),
+        invoice(5, "Mega Corp", "2025-02-01", 500),
+        paid,
+        invoice(7, "Mega Corp", "someday", 700),
+    ]
+}
+
+fn as_of() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 6, 30).unwrap()
+}
+
+#[test]
+fn test_buckets() {
+    assert_eq!(AgingBucket::from_days(-1), AgingBucket::NotDue);
+    assert_eq!(AgingBucket::from_days(0), AgingBucket::Days0To30);
+    assert_eq!(AgingBucket::from_days(31), AgingBucket::Days31To60);
+    assert_eq!(AgingBucket::from_days(90), AgingBucket::Days61To90);
+    assert_eq!(AgingBucket::from_days(91), AgingBucket::Over90);
+}
+
+#[test]
+fn test_report_per_customer() {
+    let report = AgingReport::new(&invoices(), as_of());
+
+    let customers: Vec<(&str, [f64; 5])> = report.customers.iter().map(|c| (c.customer.as_str(), c.buckets)).collect();
+    assert_eq!(customers, vec![
+        ("Mega Corp", [0.0, 0.0, 0.0, 400.0, 500.0]),
+        ("Super Corp", [100.0, 200.0, 300.0, 0.0, 0.0]),
+    ]);
+    assert_eq!(report.totals[0].total(), 1500.0);
+    assert_eq!(report.totals[0].amount(AgingBucket::Over90), 500.0);
+    // paid invoices aren't receivables anymore, and undated ones can't be aged
+    assert!(report.invoices.iter().all(|i| i.invoice_number != 6));
+    assert_eq!(report.undated, vec![7]);
+    // most overdue first
+    assert_eq!(report.invoices[0].invoice_number, 5);
+    assert_eq!(report.invoices[0].days_overdue, 149);
+}
+
+#[test]
+fn test_partial_payments_reduce_outstanding() {
+    let invoices = invoices();
+    let transfer = Transfer {
+        date: None,
+        amount: 150.0,
+        currency: String::from("EUR"),
+        reference: Some(String::from("Invoice 5")),
+        account: None,
+        tx: None,
+    };
+    let report = AgingReport::from_reconciliation(&invoices, &reconcile(&invoices, &[transfer]), as_of());
+    assert_eq!(report.customers[0].amount(AgingBucket::Over90), 350.0);
+}
+
+#[test]
+fn test_exports() {
+    let report = AgingReport::new(&invoices(), as_of());
+    report.to_csv(String::from("aging_2025_06_30")).unwrap();
+    let csv = std::fs::read_to_string("aging_2025_06_30.csv").unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "customer,currency,Not due,0-30,31-60,61-90,90+,total");
+    assert_eq!(lines[1], "Mega Corp,EUR,0.00,0.00,0.00,400.00,500.00,900.00");
+    assert_eq!(lines[3], "Total,EUR,100.00,200.00,300.00,400.00,500.00,1500.00");
+
+    report.clone().to_pdf(String::from("aging_2025_06_30")).unwrap();
+    report.to_html(String::from("aging_2025_06_30")).unwrap();
+    let html = std::fs::read_to_string("aging_2025_06_30.html").unwrap();
+    assert!(html.contains("<td class=\"amount\">1500.00</td>"));
+}
@@ -0,0 +1,68 @@
+use factura::audit::{AuditError, AuditLog};
+use factura::types::{InvoiceStatus, ParsedInvoice};
+use factura::SimpleInvoice;
+
+mod common;
+
+fn invoice(number: u16) -> ParsedInvoice {
+    common::invoice(number).parsed()
+}
+
+fn issued(number: u16) -> ParsedInvoice {
+    let mut invoice = invoice(number);
+    invoice.transition(InvoiceStatus::Issued).unwrap();
+    invoice
+}
+
+/// Log with invoices 1 to 3, returns it along with the path to its file
+fn filled_log(name: &str) -> (AuditLog, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_file(&path);
+    let log = AuditLog::open(&path).unwrap();
+    for number in 1..=3 {
+        log.append(&issued(number)).unwrap();
+    }
+    (log, path)
+}
+
+fn audit_error(log: &AuditLog) -> AuditError {
+    *log.verify().unwrap_err().downcast::<AuditError>().unwrap()
+}
+
+#[test]
+fn test_chain_verifies() {
+    let (log, _) = filled_log("factura_audit_chain.jsonl");
+    let entries = log.verify().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[1].previous_hash, entries[0].hash);
+    assert_eq!(log.head().unwrap(), Some(entries[2].hash.clone()));
+    assert_eq!(entries[2].raw_invoice().unwrap().data.invoice_number, 3);
+
+    // drafts aren't final yet
+    assert!(log.append(&invoice(4)).is_err());
+
+    let mut recorded = SimpleInvoice::new(entries[1].raw_invoice().unwrap()).get_invoice();
+    assert!(log.matches_log(&recorded).unwrap());
+    recorded.items[0].unit_price = 300;
+    assert!(!log.matches_log(&recorded).unwrap());
+}
+
+#[test]
+fn test_edited_entry_is_detected() {
+    let (log, path) = filled_log("factura_audit_edited.jsonl");
+    let mut lines: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+    lines[1] = lines[1].replace(r#"\"quantity\":2"#, r#"\"quantity\":1"#);
+    std::fs::write(&path, lines.join("\n")).unwrap();
+
+    assert!(matches!(audit_error(&log), AuditError::Tampered { sequence: 1 }));
+    assert!(log.append(&issued(4)).is_err());
+}
+
+#[test]
+fn test_deleted_entry_is_detected() {
+    let (log, path) = filled_log("factura_audit_deleted.jsonl");
+    let lines: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+    std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+
+    assert!(matches!(audit_error(&log), AuditError::MissingEntry { sequence: 2 }));
+}
@@ -0,0 +1,101 @@
+use factura::types::Catalog;
+use factura::InvoiceReader;
+
+const CATALOG_JSON: &str = r#"{
+    "company": { "email": "joe_schower@supaservices.com", "name": "Joe's Services", "addr_one": "Leuteritzweg",
+                 "addr_two": "13", "postal": "22399", "state": "Hamburg", "country": "Germany" },
+    "customers": {
+        "supercorp": { "email": "billing@corp.com", "name": "Super Corp", "addr_one": "Tegelsbarg",
+                       "addr_two": "73", "postal": "22399", "state": "Hamburg", "country": "Germany" }
+    },
+    "products": {
+        "TRUCK-FEE": { "description": "Big truck transport fee", "amount": 400, "tax_percent": 20 },
+        "LOADING": { "description": "Loading and unloading", "amount": 50, "tax_percent": 20 }
+    }
+}"#;
+
+const CATALOG_TOML: &str = r#"
+[company]
+email = "joe_schower@supaservices.com"
+name = "Joe's Services"
+addr_one = "Leuteritzweg"
+addr_two = "13"
+postal = "22399"
+state = "Hamburg"
+country = "Germany"
+
+[products.TRUCK-FEE]
+description = "Big truck transport fee"
+amount = 400
+tax_percent = 20
+"#;
+
+fn invoices_json(customer: &str, sku: &str) -> String {
+    format!(r#"[{{
+        "customer": "{}",
+        "items": [
+            {{ "sku": "{}", "quantity": 2 }},
+            {{ "sku": "LOADING", "quantity": 1, "amount": 40 }},
+            {{ "description": "Waiting time", "quantity": 1, "amount": 30, "tax_percent": 0 }}
+        ],
+        "payment": {{ "currency": "EUR", "methods": [ {{ "type": "paypal", "account": "billing@supaservices.com" }} ] }},
+        "data": {{ "invoice_number": 376, "due_date": "16/jan/2025", "issue_date": "5/jan/2025" }}
+    }}]"#, customer, sku)
+}
+
+fn write_file(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+fn catalog() -> Catalog {
+    Catalog::from_json(write_file("factura_catalog.json", CATALOG_JSON)).unwrap()
+}
+
+#[test]
+fn test_references_are_filled_in() {
+    let file = write_file("factura_catalog_invoices.json", &invoices_json("supercorp", "TRUCK-FEE"));
+    let invoice = InvoiceReader::from_json_with_catalog(file, &catalog()).unwrap().remove(0);
+
+    assert_eq!(invoice.from.name, "Joe's Services");
+    assert_eq!(invoice.to.name, "Super Corp");
+    assert_eq!(invoice.items[0].description, "Big truck transport fee");
+    assert_eq!((invoice.items[0].amount, invoice.items[0].tax_percent), (400, 20));
+    // the invoice's own values win over the catalog defaults
+    assert_eq!(invoice.items[1].description, "Loading and unloading");
+    assert_eq!(invoice.items[1].amount, 40);
+    assert_eq!(invoice.items[2].description, "Waiting time");
+}
+
+#[test]
+fn test_unknown_references_are_rejected() {
+    let file = write_file("factura_catalog_bad_customer.json", &invoices_json("megacorp", "TRUCK-FEE"));
+    let error = InvoiceReader::from_json_with_catalog(file, &catalog()).err().unwrap();
+    assert_eq!(error.to_string(), "unknown customer: megacorp");
+
+    let file = write_file("factura_catalog_bad_sku.json", &invoices_json("supercorp", "CRANE-FEE"));
+    let error = InvoiceReader::from_json_with_catalog(file.clone(), &catalog()).err().unwrap();
+    assert_eq!(error.to_string(), "unknown product: CRANE-FEE");
+
+    // without a catalog, references can't be resolved at all
+    assert!(InvoiceReader::from_json(file).is_err());
+}
+
+#[test]
+fn test_catalog_from_toml() {
+    let catalog = Catalog::from_toml(write_file("factura_catalog.toml", CATALOG_TOML)).unwrap();
+    assert_eq!(catalog.company().unwrap().name, "Joe's Services");
+    assert_eq!(catalog.product("TRUCK-FEE").unwrap().amount, 400);
+    assert!(catalog.customer("supercorp").is_err());
+}
+
+#[test]
+fn test_malformed_file_is_an_error() {
+    let json = invoices_json("supercorp", "TRUCK-FEE").replace(r#""quantity": 2"#, r#""quantity": "two""#);
+    let file = write_file("factura_catalog_malformed.json", &json);
+    assert!(InvoiceReader::from_json_with_catalog(file, &catalog()).is_err());
+
+    let file = write_file("factura_catalog_malformed.toml", "[[invoices]]\ncustomer = ");
+    assert!(InvoiceReader::from_toml_with_catalog(file, &catalog()).is_err());
+}
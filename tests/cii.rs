@@ -0,0 +1,110 @@
+#![cfg(feature = "cii")]
+
+use factura::cii::{document_profile, invoice_xml, parse_invoice, CiiProfile};
+use factura::types::{ParsedInvoice, PaymentMethod, RawInvoice, TaxCategory};
+use factura::{ExportsCII, InvoiceReader, SimpleInvoice};
+
+mod common;
+
+const LOADING: &str = r#"{ "description": "Loading", "quantity": 3, "amount": 50, "tax_percent": 7 }"#;
+
+fn raw_invoice() -> RawInvoice {
+    common::invoice(376)
+        .seller_fields(common::SELLER_VAT_ID)
+        .buyer_fields(r#""vat_id": "DE987654321""#)
+        .extra(&format!(
+            r#"{}, "contact": {{ "name": "Jane Doe", "email": "jane@corp.com", "phone": "+49 40 123456" }}"#,
+            common::SHIP_TO_WAREHOUSE,
+        ))
+        .items(&[common::TRANSPORT_FEE, LOADING])
+        .methods(&[&common::bank_transfer("Invoice 376"), common::PAYPAL])
+        .raw()
+}
+
+fn invoice() -> ParsedInvoice {
+    SimpleInvoice::new(raw_invoice()).get_invoice()
+}
+
+#[test]
+fn test_round_trip() {
+    for profile in [CiiProfile::Basic, CiiProfile::EN16931, CiiProfile::Extended] {
+        let xml = invoice_xml(&invoice().to_en16931(), profile);
+        assert_eq!(document_profile(&xml).unwrap(), profile);
+
+        let raw = parse_invoice(&xml).unwrap();
+        assert_eq!(raw.data.invoice_number, 376);
+        assert_eq!((raw.data.issue_date.as_str(), raw.data.due_date.as_str()), ("2025-01-05", "2025-01-16"));
+        assert_eq!((raw.from.addr_one.as_str(), raw.from.addr_two.as_str()), ("Leuteritzweg", "13"));
+        assert_eq!(raw.to.vat_id.as_deref(), Some("DE987654321"));
+        assert_eq!(raw.ship_to.as_ref().unwrap().postal, "20457");
+
+        let items: Vec<(&str, u8, u32, u8, TaxCategory)> = raw.items.iter()
+            .map(|i| (i.description.as_str(), i.quantity, i.amount, i.tax_percent, i.tax_category))
+            .collect();
+        assert_eq!(items, vec![
+            ("Big truck transport fee", 2, 400, 19, TaxCategory::Standard),
+            ("Loading", 3, 50, 7, TaxCategory::Standard),
+        ]);
+        assert!(matches!(&raw.payment.methods[0], PaymentMethod::BankTransfer(b)
+            if b.iban == "DE89370400440532013000" && b.reference.as_deref() == Some("Invoice 376")));
+        assert!(matches!(&raw.payment.methods[1], PaymentMethod::PayPal { account } if account == "billing@supaservices.com"));
+
+        let reparsed = SimpleInvoice::new(raw).get_invoice();
+        assert_eq!(reparsed.total.amount_due, invoice().total.amount_due);
+    }
+}
+
+#[test]
+fn test_contacts_need_en16931() {
+    let raw = parse_invoice(&invoice_xml(&invoice().to_en16931(), CiiProfile::EN16931)).unwrap();
+    assert_eq!(raw.contact.unwrap().phone.as_deref(), Some("+49 40 123456"));
+
+    let raw = parse_invoice(&invoice_xml(&invoice().to_en16931(), CiiProfile::Basic)).unwrap();
+    assert!(raw.contact.is_none());
+}
+
+#[test]
+fn test_profiles_without_lines() {
+    let minimum = invoice_xml(&invoice().to_en16931(), CiiProfile::Minimum);
+    assert!(minimum.contains("<ram:ID>urn:factur-x.eu:1p0:minimum</ram:ID>"));
+    assert!(minimum.contains("<ram:ID schemeID=\"VA\">DE123456789</ram:ID>"));
+    assert!(minimum.contains("<ram:DuePayableAmount>1111.00</ram:DuePayableAmount>"));
+    assert!(!minimum.contains("DE987654321"));
+    // the seller's address is only its country
+    assert!(minimum.contains("<ram:CountryID>DE</ram:CountryID>"));
+    assert!(!minimum.contains("ram:PostcodeCode"));
+    assert!(!minimum.contains("ram:LineOne"));
+    assert!(!minimum.contains("ram:CityName"));
+    assert!(!minimum.contains("ram:IncludedSupplyChainTradeLineItem"));
+    assert!(!minimum.contains("ram:SpecifiedTradeSettlementPaymentMeans"));
+    assert_eq!(parse_invoice(&minimum).err().unwrap().to_string(), "the MINIMUM profile has no invoice lines");
+
+    let basic_wl = invoice_xml(&invoice().to_en16931(), CiiProfile::BasicWL);
+    assert!(basic_wl.contains("<ram:IBANID>DE89370400440532013000</ram:IBANID>"));
+    assert!(basic_wl.contains("<ram:BasisAmount>800.00</ram:BasisAmount>"));
+    assert!(!basic_wl.contains("ram:IncludedSupplyChainTradeLineItem"));
+    assert_eq!(document_profile(&basic_wl).unwrap(), CiiProfile::BasicWL);
+}
+
+#[test]
+fn test_export_and_read() {
+    SimpleInvoice::new(raw_invoice()).to_cii(String::from("invoice_376_cii"), CiiProfile::EN16931).unwrap();
+    let raw = InvoiceReader::from_cii(String::from("invoice_376_cii.xml")).unwrap().remove(0);
+    assert_eq!(raw.items.len(), 2);
+    assert_eq!(raw.payment.currency, "EUR");
+}
+
+#[test]
+fn test_fractions_are_rejected() {
+    let xml = invoice_xml(&invoice().to_en16931(), CiiProfile::EN16931);
+    // quantities and rates are whole numbers in the crate, rounding them would change the totals
+    let fractional = xml.replace(r#"<ram:BilledQuantity unitCode="C62">3</ram:BilledQuantity>"#, r#"<ram:BilledQuantity unitCode="C62">2.5</ram:BilledQuantity>"#);
+    assert_eq!(parse_invoice(&fractional).err().unwrap().to_string(), "invalid value for quantity: 2.5");
+    let too_many = xml.replace(r#"<ram:BilledQuantity unitCode="C62">3</ram:BilledQuantity>"#, r#"<ram:BilledQuantity unitCode="C62">300</ram:BilledQuantity>"#);
+    assert_eq!(parse_invoice(&too_many).err().unwrap().to_string(), "invalid value for quantity: 300");
+    let rate = xml.replacen("<ram:RateApplicablePercent>7</ram:RateApplicablePercent>", "<ram:RateApplicablePercent>7.5</ram:RateApplicablePercent>", 1);
+    assert_eq!(parse_invoice(&rate).err().unwrap().to_string(), "invalid value for tax rate: 7.5");
+
+    let decimals = xml.replace(r#"<ram:BilledQuantity unitCode="C62">3</ram:BilledQuantity>"#, r#"<ram:BilledQuantity unitCode="C62">3.000</ram:BilledQuantity>"#);
+    assert_eq!(parse_invoice(&decimals).unwrap().items[1].quantity, 3);
+}
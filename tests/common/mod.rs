@@ -0,0 +1,162 @@
+//! Invoice fixture shared by the tests: Joe's Services billing Super Corp, both in Hamburg,
+//! written as an invoice file and read back through `InvoiceReader` like a user's file would be.
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use factura::types::{ParsedInvoice, RawInvoice};
+use factura::{InvoiceReader, SimpleInvoice};
+
+/// Joe's Services' VAT ID, as seller fields
+pub const SELLER_VAT_ID: &str = r#""vat_id": "DE123456789""#;
+/// Delivery address of Super Corp, as the `"ship_to"` top level field
+pub const SHIP_TO_WAREHOUSE: &str = r#""ship_to": { "email": "", "name": "Super Corp Warehouse", "addr_one": "Hafenstrasse",
+    "addr_two": "2", "postal": "20457", "state": "Hamburg", "country": "Germany" }"#;
+pub const PAYPAL: &str = r#"{ "type": "paypal", "account": "billing@supaservices.com" }"#;
+pub const TRANSPORT_FEE: &str = r#"{ "description": "Big truck transport fee", "quantity": 2, "amount": 400, "tax_percent": 19 }"#;
+
+/// Transfer to Joe's Services' account with the given reference
+pub fn bank_transfer(reference: &str) -> String {
+    format!(r#"{{ "type": "bank_transfer", "iban": "DE89 3704 0044 0532 0130 00", "bic": "COBADEFFXXX",
+                  "account_holder": "Joe's Services", "reference": "{}" }}"#, reference)
+}
+
+/// Invoice with the given number, paid by PayPal for a single transport fee unless told otherwise
+pub fn invoice(number: u16) -> InvoiceJson {
+    InvoiceJson {
+        number,
+        seller_fields: String::new(),
+        buyer_name: String::from("Super Corp"),
+        buyer_email: String::from("billing@corp.com"),
+        buyer_country: String::from("Germany"),
+        buyer_fields: String::new(),
+        extra: String::new(),
+        items: vec![TRANSPORT_FEE.to_string()],
+        currency: String::from("EUR"),
+        methods: vec![PAYPAL.to_string()],
+        issue_date: String::from("5/jan/2025"),
+        due_date: String::from("16/jan/2025"),
+        history: None,
+    }
+}
+
+pub struct InvoiceJson {
+    number: u16,
+    seller_fields: String,
+    buyer_name: String,
+    buyer_email: String,
+    buyer_country: String,
+    buyer_fields: String,
+    extra: String,
+    items: Vec<String>,
+    currency: String,
+    methods: Vec<String>,
+    issue_date: String,
+    due_date: String,
+    history: Option<String>,
+}
+
+impl InvoiceJson {
+    /// Fields added to the seller, like `"vat_id": "DE123456789"`
+    pub fn seller_fields(mut self, fields: &str) -> Self {
+        self.seller_fields = fields.to_string();
+        self
+    }
+
+    /// Buyer's name, billed at `billing@corp.com`
+    pub fn buyer(mut self, name: &str) -> Self {
+        self.buyer_name = name.to_string();
+        self
+    }
+
+    pub fn buyer_email(mut self, email: &str) -> Self {
+        self.buyer_email = email.to_string();
+        self
+    }
+
+    pub fn buyer_country(mut self, country: &str) -> Self {
+        self.buyer_country = country.to_string();
+        self
+    }
+
+    /// Fields added to the buyer, like `"buyer_reference": "PO-2025-17"`
+    pub fn buyer_fields(mut self, fields: &str) -> Self {
+        self.buyer_fields = fields.to_string();
+        self
+    }
+
+    /// Top level fields of the invoice, like `"ship_to"` and `"contact"`
+    pub fn extra(mut self, fields: &str) -> Self {
+        self.extra = fields.to_string();
+        self
+    }
+
+    pub fn items(mut self, items: &[&str]) -> Self {
+        self.items = items.iter().map(|item| item.to_string()).collect();
+        self
+    }
+
+    pub fn currency(mut self, currency: &str) -> Self {
+        self.currency = currency.to_string();
+        self
+    }
+
+    pub fn methods(mut self, methods: &[&str]) -> Self {
+        self.methods = methods.iter().map(|method| method.to_string()).collect();
+        self
+    }
+
+    pub fn issued(mut self, issue_date: &str) -> Self {
+        self.issue_date = issue_date.to_string();
+        self
+    }
+
+    pub fn due(mut self, due_date: &str) -> Self {
+        self.due_date = due_date.to_string();
+        self
+    }
+
+    /// Status history, as the json array of the invoice file
+    pub fn history(mut self, history: &str) -> Self {
+        self.history = Some(history.to_string());
+        self
+    }
+
+    /// The invoice file, a list with only this invoice
+    pub fn json(&self) -> String {
+        let fields = |fields: &str| if fields.is_empty() { String::new() } else { format!(", {}", fields) };
+        let history = self.history.as_ref().map(|h| format!(r#", "history": {}"#, h)).unwrap_or_default();
+        format!(r#"[{{
+            "from": {{ "email": "joe_schower@supaservices.com", "name": "Joe's Services", "addr_one": "Leuteritzweg",
+                      "addr_two": "13", "postal": "22399", "state": "Hamburg", "country": "Germany"{} }},
+            "to": {{ "email": "{}", "name": "{}", "addr_one": "Tegelsbarg",
+                    "addr_two": "73", "postal": "22399", "state": "Hamburg", "country": "{}"{} }}{},
+            "items": [ {} ],
+            "payment": {{ "currency": "{}", "methods": [ {} ] }},
+            "data": {{ "invoice_number": {}, "due_date": "{}", "issue_date": "{}"{} }}
+        }}]"#,
+            fields(&self.seller_fields), self.buyer_email, self.buyer_name, self.buyer_country,
+            fields(&self.buyer_fields), fields(&self.extra), self.items.join(", "), self.currency,
+            self.methods.join(", "), self.number, self.due_date, self.issue_date, history,
+        )
+    }
+
+    /// Writes the invoice file to the temp directory and reads it back
+    pub fn read(&self) -> Result<Vec<RawInvoice>, Box<dyn std::error::Error>> {
+        static FILES: AtomicUsize = AtomicUsize::new(0);
+        let name = format!("factura_fixture_{}_{}.json", std::process::id(), FILES.fetch_add(1, Ordering::Relaxed));
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, self.json())?;
+        let invoices = InvoiceReader::from_json(path.to_string_lossy().into_owned());
+        let _ = std::fs::remove_file(&path);
+        invoices
+    }
+
+    pub fn raw(&self) -> RawInvoice {
+        self.read().unwrap().remove(0)
+    }
+
+    pub fn parsed(&self) -> ParsedInvoice {
+        SimpleInvoice::new(self.raw()).get_invoice()
+    }
+}
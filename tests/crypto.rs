@@ -0,0 +1,174 @@
+use factura::crypto::{explorer_url, payment_uri, validate_address, validate_tx_hash};
+use factura::types::{Chain, CryptoWallet, Payment, PaymentMethod};
+
+fn wallet(chain: Chain, address: &str, currency: &str, token: Option<&str>) -> CryptoWallet {
+    CryptoWallet {
+        address: String::from(address),
+        currency: String::from(currency),
+        chain,
+        token: token.map(String::from),
+        decimals: None,
+    }
+}
+
+#[test]
+fn test_erc20_uri() {
+    let wallet = wallet(
+        Chain::ETHEREUM,
+        "0x8e23Ee67d1332aD560396262C48ffbB01f93d052",
+        "EURC",
+        Some("0x1aBaEA1f7C830bD89Acc67eC4af516284b1bC33c"),
+    );
+    assert_eq!(
+        payment_uri(&wallet, "EURC", 960, 376),
+        "ethereum:0x1aBaEA1f7C830bD89Acc67eC4af516284b1bC33c@1/transfer?address=0x8e23Ee67d1332aD560396262C48ffbB01f93d052&uint256=960e6"
+    );
+}
+
+#[test]
+fn test_native_ether_uri() {
+    let wallet = wallet(Chain::ETHEREUM, "0x8e23Ee67d1332aD560396262C48ffbB01f93d052", "ETH", None);
+    assert_eq!(
+        payment_uri(&wallet, "ETH", 2, 376),
+        "ethereum:0x8e23Ee67d1332aD560396262C48ffbB01f93d052@1?value=2e18"
+    );
+    // priced in another currency, the wallet app has to ask for the amount
+    assert_eq!(
+        payment_uri(&wallet, "EUR", 960, 376),
+        "ethereum:0x8e23Ee67d1332aD560396262C48ffbB01f93d052@1"
+    );
+}
+
+#[test]
+fn test_token_without_contract_uri() {
+    // a token amount can't be asked for without its contract, value= would request ether
+    let usdc = wallet(Chain::ETHEREUM, "0x8e23Ee67d1332aD560396262C48ffbB01f93d052", "USDC", None);
+    assert_eq!(
+        payment_uri(&usdc, "USDC", 960, 376),
+        "ethereum:0x8e23Ee67d1332aD560396262C48ffbB01f93d052@1"
+    );
+    let bnb = wallet(Chain::Evm(56), "0x8e23Ee67d1332aD560396262C48ffbB01f93d052", "BNB", None);
+    assert_eq!(
+        payment_uri(&bnb, "BNB", 3, 376),
+        "ethereum:0x8e23Ee67d1332aD560396262C48ffbB01f93d052@56?value=3e18"
+    );
+}
+
+#[test]
+fn test_bitcoin_uri() {
+    let wallet = wallet(Chain::Bitcoin, "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq", "BTC", None);
+    assert_eq!(
+        payment_uri(&wallet, "BTC", 1, 376),
+        "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?message=Invoice%20376&amount=1"
+    );
+}
+
+#[test]
+fn test_solana_pay_uri() {
+    let wallet = wallet(
+        Chain::Solana,
+        "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU",
+        "USDC",
+        Some("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"),
+    );
+    assert_eq!(
+        payment_uri(&wallet, "USDC", 960, 376),
+        "solana:7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU?amount=960&spl-token=EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v&message=Invoice%20376"
+    );
+}
+
+#[test]
+fn test_chain_id_and_decimals() {
+    let mut wallet = wallet(
+        Chain::Evm(8453),
+        "0x8e23Ee67d1332aD560396262C48ffbB01f93d052",
+        "XYZ",
+        Some("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"),
+    );
+    wallet.decimals = Some(2);
+    assert_eq!(
+        payment_uri(&wallet, "XYZ", 960, 376),
+        "ethereum:0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359@8453/transfer?address=0x8e23Ee67d1332aD560396262C48ffbB01f93d052&uint256=960e2"
+    );
+    assert_eq!(Chain::Evm(8453).description(), "Base (chain ID 8453)");
+}
+
+#[test]
+fn test_evm_addresses() {
+    // EIP-55 test vectors
+    assert!(validate_address(Chain::ETHEREUM, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_ok());
+    assert!(validate_address(Chain::ETHEREUM, "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359").is_ok());
+    assert!(validate_address(Chain::Evm(137), "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB").is_ok());
+    // no checksum at all is accepted, a broken one isn't
+    assert!(validate_address(Chain::ETHEREUM, "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").is_ok());
+    assert!(validate_address(Chain::ETHEREUM, "0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+    assert!(validate_address(Chain::ETHEREUM, "0x123123123123123123123123123").is_err());
+}
+
+#[test]
+fn test_solana_addresses() {
+    assert!(validate_address(Chain::Solana, "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU").is_ok());
+    assert!(validate_address(Chain::Solana, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v").is_ok());
+    // 0 and l aren't part of the base58 alphabet
+    assert!(validate_address(Chain::Solana, "0xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU").is_err());
+    // an evm address is too short for a solana public key
+    assert!(validate_address(Chain::Solana, "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+}
+
+#[test]
+fn test_bitcoin_addresses() {
+    // BIP-173 and BIP-350 test vectors
+    assert!(validate_address(Chain::Bitcoin, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").is_ok());
+    assert!(validate_address(Chain::Bitcoin, "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").is_ok());
+    assert!(validate_address(Chain::Bitcoin, "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0").is_ok());
+    assert!(validate_address(Chain::Bitcoin, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5").is_err());
+    // version 1 program with a bech32 instead of bech32m checksum
+    assert!(validate_address(Chain::Bitcoin, "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vpggk6m").is_err());
+    // legacy base58check addresses
+    assert!(validate_address(Chain::Bitcoin, "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").is_ok());
+    assert!(validate_address(Chain::Bitcoin, "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy").is_ok());
+    assert!(validate_address(Chain::Bitcoin, "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3").is_err());
+}
+
+#[test]
+fn test_transaction_hashes() {
+    let evm = "0x88df016429689c079f3b2f6ad39fa052532c56795b733da78a91ebe6a713944b";
+    assert!(validate_tx_hash(Chain::ETHEREUM, evm).is_ok());
+    assert!(validate_tx_hash(Chain::ETHEREUM, &evm[2..]).is_err());
+    assert!(validate_tx_hash(Chain::ETHEREUM, &evm[..64]).is_err());
+
+    let bitcoin = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+    assert!(validate_tx_hash(Chain::Bitcoin, bitcoin).is_ok());
+    assert!(validate_tx_hash(Chain::Bitcoin, evm).is_err());
+
+    let solana = "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW";
+    assert!(validate_tx_hash(Chain::Solana, solana).is_ok());
+    assert!(validate_tx_hash(Chain::Solana, "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnb").is_err());
+}
+
+#[test]
+fn test_explorer_urls() {
+    let hash = "0x88df016429689c079f3b2f6ad39fa052532c56795b733da78a91ebe6a713944b";
+    assert_eq!(explorer_url(Chain::Evm(8453), hash), Some(format!("https://basescan.org/tx/{}", hash)));
+    assert_eq!(explorer_url(Chain::Evm(999999), hash), None);
+
+    let mut payment = Payment {
+        currency: String::from("USDC"),
+        methods: vec![
+            PaymentMethod::PayPal { account: String::from("billing@supaservices.com") },
+            PaymentMethod::Crypto(wallet(
+                Chain::Evm(137), "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", "USDC", None,
+            )),
+        ],
+        tx: Some(String::from(hash)),
+        settled_on: None,
+    };
+    assert!(payment.is_paid());
+    assert!(payment.validate().is_ok());
+    assert_eq!(payment.tx_url(), Some(format!("https://polygonscan.com/tx/{}", hash)));
+
+    // no accepted network uses bitcoin style hashes
+    payment.tx = Some(String::from(&hash[2..]));
+    assert!(payment.validate().is_err());
+    assert_eq!(payment.tx_url(), None);
+}
@@ -0,0 +1,70 @@
+use factura::en16931::{SemanticInvoice, SPECIFICATION};
+use factura::types::{ParsedInvoice, TaxCategory};
+
+mod common;
+
+fn invoice(seller_vat: &str, buyer_country: &str, currency: &str, items: &[&str]) -> ParsedInvoice {
+    common::invoice(376)
+        .seller_fields(seller_vat)
+        .buyer_country(buyer_country)
+        .items(items)
+        .currency(currency)
+        .methods(&[&common::bank_transfer("Invoice 376")])
+        .parsed()
+}
+
+const BOOKS: &str = r#"{ "description": "Books", "quantity": 1, "amount": 50, "tax_percent": 7 }"#;
+
+#[test]
+fn test_business_terms() {
+    let semantic = invoice(common::SELLER_VAT_ID, "Germany", "EUR", &[common::TRANSPORT_FEE, BOOKS]).to_en16931();
+
+    assert_eq!(semantic.specification, SPECIFICATION);
+    assert_eq!(semantic.number, "376");
+    assert_eq!(semantic.issue_date.unwrap().to_string(), "2025-01-05");
+    assert_eq!(semantic.seller.vat_id.as_deref(), Some("DE123456789"));
+    assert_eq!(semantic.seller.address.line_one.as_deref(), Some("Leuteritzweg 13"));
+    assert_eq!(semantic.buyer.address.country_code.as_deref(), Some("DE"));
+
+    assert_eq!(semantic.lines.len(), 2);
+    assert_eq!((semantic.lines[0].net_amount, semantic.lines[0].unit_code), (800, "C62"));
+    let breakdown: Vec<(TaxCategory, u8, u64, u64)> = semantic.vat_breakdown.iter()
+        .map(|b| (b.category, b.rate, b.taxable_amount, b.tax_amount))
+        .collect();
+    assert_eq!(breakdown, vec![(TaxCategory::Standard, 19, 800, 152), (TaxCategory::Standard, 7, 50, 3)]);
+    assert_eq!((semantic.totals.tax_exclusive, semantic.totals.tax_total, semantic.totals.amount_due), (850, 155, 1005));
+
+    let means = &semantic.payment_means[0];
+    assert_eq!(means.type_code, "58");
+    assert_eq!(means.account.as_deref(), Some("DE89370400440532013000"));
+    assert_eq!(means.remittance_information.as_deref(), Some("Invoice 376"));
+
+    assert_eq!(semantic.validate(), vec![]);
+}
+
+#[test]
+fn test_rule_violations() {
+    let items = [
+        r#"{ "description": "Consulting", "quantity": 1, "amount": 300, "tax_percent": 0, "tax_category": "reverse_charge" }"#,
+        r#"{ "description": "Medical care", "quantity": 1, "amount": 100, "tax_percent": 0, "tax_category": "exempt" }"#,
+        r#"{ "description": "", "quantity": 1, "amount": 100, "tax_percent": 0, "tax_category": "standard" }"#,
+    ];
+    let semantic = invoice("", "Atlantis", "EURC", &items).to_en16931();
+
+    let rules: Vec<String> = semantic.validate().into_iter().map(|v| v.rule).collect();
+    assert_eq!(rules, vec![
+        "BR-CL-04", "BR-11", "BR-25", "BR-S-02", "BR-S-05", "BR-E-02", "BR-E-10", "BR-AE-02",
+    ]);
+}
+
+#[test]
+fn test_totals_are_checked() {
+    let mut semantic = SemanticInvoice::from(&invoice(common::SELLER_VAT_ID, "Germany", "EUR", &[common::TRANSPORT_FEE, BOOKS]));
+    semantic.totals.tax_total += 1;
+    semantic.vat_breakdown[0].tax_amount = 100;
+
+    let violations = semantic.validate();
+    let rules: Vec<&str> = violations.iter().map(|v| v.rule.as_str()).collect();
+    assert_eq!(rules, vec!["BR-CO-14", "BR-CO-15", "BR-CO-17"]);
+    assert_eq!(violations[0].to_string(), "[BR-CO-14] VAT total (BT-110) isn't the sum of the breakdown");
+}
@@ -0,0 +1,74 @@
+#![cfg(all(feature = "pdf", feature = "cii"))]
+
+use factura::cii::{invoice_xml, CiiProfile};
+use factura::facturx::{attached_xml, parse_pdf};
+use factura::types::RawInvoice;
+use factura::{ExportsPDF, InvoiceReader, SimpleInvoice};
+
+mod common;
+
+fn raw_invoice() -> RawInvoice {
+    common::invoice(376)
+        .seller_fields(common::SELLER_VAT_ID)
+        .buyer_fields(r#""vat_id": "DE987654321""#)
+        .items(&[common::TRANSPORT_FEE, r#"{ "description": "Loading", "quantity": 3, "amount": 50, "tax_percent": 7 }"#])
+        .methods(&[&common::bank_transfer("Invoice 376")])
+        .raw()
+}
+
+#[test]
+fn test_hybrid_pdf() {
+    SimpleInvoice::new(raw_invoice()).with_factur_x(CiiProfile::EN16931)
+        .to_pdf(String::from("invoice_376_facturx")).unwrap();
+    let pdf = std::fs::read("invoice_376_facturx.pdf").unwrap();
+
+    let expected = invoice_xml(&SimpleInvoice::new(raw_invoice()).get_invoice().to_en16931(), CiiProfile::EN16931);
+    assert_eq!(attached_xml(&pdf).unwrap(), expected);
+
+    // PDF/A wants a comment of at least four bytes above 127 right after the header
+    let comment = pdf.split(|b| *b == b'\n').nth(1).unwrap();
+    assert!(comment.starts_with(b"%") && comment.len() >= 5 && comment[1..].iter().all(|b| *b > 127));
+
+    let text = String::from_utf8_lossy(&pdf);
+    assert!(text.starts_with("%PDF-1.7\n"));
+    assert!(text.contains("<pdfaid:part>3</pdfaid:part>"));
+    assert!(text.contains("<pdfaid:conformance>B</pdfaid:conformance>"));
+    assert!(text.contains("<fx:DocumentFileName>factur-x.xml</fx:DocumentFileName>"));
+    assert!(text.contains("<fx:ConformanceLevel>EN 16931</fx:ConformanceLevel>"));
+    assert!(text.contains("<pdfaSchema:prefix>fx</pdfaSchema:prefix>"));
+    assert!(text.contains("/AFRelationship/Alternative"));
+    assert!(text.contains("/Subtype/text#2Fxml"));
+    assert!(text.contains("/S/GTS_PDFA1"));
+    assert!(!text.contains("GTS_PDFX"));
+}
+
+#[test]
+fn test_read_hybrid_pdf() {
+    SimpleInvoice::new(raw_invoice()).with_factur_x(CiiProfile::Basic)
+        .to_pdf(String::from("invoice_376_facturx_basic")).unwrap();
+    let raw = InvoiceReader::from_facturx(String::from("invoice_376_facturx_basic.pdf")).unwrap().remove(0);
+    assert_eq!(raw.data.invoice_number, 376);
+    assert_eq!(raw.items.len(), 2);
+
+    let reparsed = SimpleInvoice::new(raw).get_invoice();
+    assert_eq!(reparsed.total.amount_due, SimpleInvoice::new(raw_invoice()).get_invoice().total.amount_due);
+}
+
+#[test]
+fn test_profiles_without_lines() {
+    SimpleInvoice::new(raw_invoice()).with_factur_x(CiiProfile::Minimum)
+        .to_pdf(String::from("invoice_376_facturx_minimum")).unwrap();
+    let pdf = std::fs::read("invoice_376_facturx_minimum.pdf").unwrap();
+
+    let text = String::from_utf8_lossy(&pdf);
+    assert!(text.contains("<fx:ConformanceLevel>MINIMUM</fx:ConformanceLevel>"));
+    assert!(text.contains("/AFRelationship/Data"));
+    assert_eq!(parse_pdf(&pdf).err().unwrap().to_string(), "invalid factur-x.xml: the MINIMUM profile has no invoice lines");
+}
+
+#[test]
+fn test_plain_pdf() {
+    SimpleInvoice::new(raw_invoice()).to_pdf(String::from("invoice_376_plain")).unwrap();
+    let pdf = std::fs::read("invoice_376_plain.pdf").unwrap();
+    assert_eq!(attached_xml(&pdf).err().unwrap().to_string(), "no factur-x.xml attached to the pdf");
+}
@@ -0,0 +1,68 @@
+use factura::girocode::epc_payload;
+use factura::payment::{validate_bic, validate_iban};
+use factura::types::BankTransfer;
+
+#[test]
+fn test_valid_iban() {
+    assert!(validate_iban("DE89370400440532013000").is_ok());
+    assert!(validate_iban("GB82 WEST 1234 5698 7654 32").is_ok());
+    assert!(validate_iban("fr14 2004 1010 0505 0001 3m02 606").is_ok());
+}
+
+#[test]
+fn test_invalid_iban() {
+    // wrong check digits
+    assert!(validate_iban("DE88370400440532013000").is_err());
+    // right checksum rules, wrong length for the country
+    assert!(validate_iban("DE8937040044053201300").is_err());
+    assert!(validate_iban("not an iban").is_err());
+    assert!(validate_iban("").is_err());
+}
+
+#[test]
+fn test_bic() {
+    assert!(validate_bic("COBADEFF").is_ok());
+    assert!(validate_bic("COBADEFFXXX").is_ok());
+    assert!(validate_bic("COBADE").is_err());
+    assert!(validate_bic("C0BADEFF").is_err());
+}
+
+fn bank() -> BankTransfer {
+    BankTransfer {
+        iban: String::from("DE89 3704 0044 0532 0130 00"),
+        bic: Some(String::from("COBADEFFXXX")),
+        account_holder: String::from("Joe's Services"),
+        bank_name: None,
+        reference: None,
+    }
+}
+
+#[test]
+fn test_epc_payload() {
+    let payload = epc_payload(&bank(), "EUR", 960, 376).unwrap();
+    assert_eq!(
+        payload,
+        "BCD\n002\n1\nSCT\nCOBADEFFXXX\nJoe's Services\nDE89370400440532013000\nEUR960.00\n\n\nInvoice 376"
+    );
+}
+
+#[test]
+fn test_epc_payload_structured_reference() {
+    let mut bank = bank();
+    bank.reference = Some(String::from("RF18539007547034"));
+    let payload = epc_payload(&bank, "EUR", 960, 376).unwrap();
+    assert!(payload.ends_with("EUR960.00\n\nRF18539007547034"));
+
+    // printed in groups of four, it's the same reference
+    bank.reference = Some(String::from("RF18 5390 0754 7034"));
+    assert!(epc_payload(&bank, "EUR", 960, 376).unwrap().ends_with("EUR960.00\n\nRF18539007547034"));
+    // wrong check digits, it's only text to the bank
+    bank.reference = Some(String::from("RF19 5390 0754 7034"));
+    assert!(epc_payload(&bank, "EUR", 960, 376).unwrap().ends_with("EUR960.00\n\n\nRF19 5390 0754 7034"));
+}
+
+#[test]
+fn test_epc_payload_requires_eur() {
+    assert!(epc_payload(&bank(), "EURC", 960, 376).is_err());
+    assert!(epc_payload(&bank(), "EUR", 0, 376).is_err());
+}
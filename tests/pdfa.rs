@@ -0,0 +1,70 @@
+use factura::pdfa::PdfA;
+use lopdf::content::Content;
+use factura::types::RawInvoice;
+use factura::{ExportsPDF, SimpleInvoice};
+
+mod common;
+
+fn raw_invoice() -> RawInvoice {
+    common::invoice(377).methods(&[&common::bank_transfer("Invoice 377")]).raw()
+}
+
+fn render(name: &str, level: Option<PdfA>) -> String {
+    let invoice = SimpleInvoice::new(raw_invoice());
+    let invoice = match level {
+        Some(level) => invoice.with_pdf_a(level),
+        None => invoice,
+    };
+    invoice.to_pdf(format!("invoice_377_{}", name)).unwrap();
+    String::from_utf8_lossy(&std::fs::read(format!("invoice_377_{}.pdf", name)).unwrap()).into_owned()
+}
+
+#[test]
+fn test_pdf_a1b() {
+    let text = render("pdfa1", Some(PdfA::A1b));
+    assert!(text.starts_with("%PDF-1.4"));
+    assert!(text.contains("<pdfaid:part>1</pdfaid:part>"));
+    assert!(text.contains("<pdfaid:conformance>B</pdfaid:conformance>"));
+    assert!(text.contains("<dc:creator><rdf:Seq><rdf:li>Joe&apos;s Services</rdf:li></rdf:Seq></dc:creator>"));
+    assert!(text.contains("/Title(Invoice 377)"));
+    assert!(text.contains("/Author(Joe's Services)"));
+    assert!(text.contains("/Subject(Invoice 377 to Super Corp, issued 5/jan/2025)"));
+    assert!(text.contains("/S/GTS_PDFA1"));
+    assert!(text.contains("/FontFile2"));
+    assert!(!text.contains("OCProperties"));
+    assert!(!text.contains("GTS_PDFX"));
+
+    let pdf = std::fs::read("invoice_377_pdfa1.pdf").unwrap();
+    assert!(has_binary_comment(&pdf));
+    // nothing is left of printpdf's layers, neither the marked content nor the groups
+    let doc = lopdf::Document::load_mem(&pdf).unwrap();
+    for page in doc.get_pages().into_values() {
+        let content = Content::decode(&doc.get_page_content(page).unwrap()).unwrap();
+        assert!(!content.operations.iter().any(|operation| operation.operator == "BDC"));
+        assert!(content.operations.iter().any(|operation| operation.operator == "TJ"));
+    }
+    assert!(!text.contains("/OCG"));
+}
+
+#[test]
+fn test_pdf_a3b() {
+    let text = render("pdfa3", Some(PdfA::A3b));
+    assert!(text.starts_with("%PDF-1.7"));
+    assert!(text.contains("<pdfaid:part>3</pdfaid:part>"));
+    assert!(text.contains("<pdf:Keywords>invoice, 377</pdf:Keywords>"));
+    assert!(!text.contains("/EmbeddedFiles"));
+    assert!(has_binary_comment(&std::fs::read("invoice_377_pdfa3.pdf").unwrap()));
+}
+
+/// Whether the header line is followed by a comment of at least four bytes above 127
+fn has_binary_comment(pdf: &[u8]) -> bool {
+    let line = pdf.split(|b| *b == b'\n').nth(1).unwrap();
+    line.starts_with(b"%") && line[1..].len() >= 4 && line[1..].iter().all(|b| *b > 127)
+}
+
+#[test]
+fn test_plain_pdf_title() {
+    let text = render("plain", None);
+    assert!(text.contains("Invoice 377"));
+    assert!(!text.contains("pdfaid"));
+}
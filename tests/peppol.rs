@@ -0,0 +1,104 @@
+#![cfg(feature = "ubl")]
+
+use factura::peppol::{envelope_xml, invoice_xml, PROFILE, SPECIFICATION};
+use factura::types::RawInvoice;
+use factura::ubl::parse_invoice;
+use factura::{ExportsPeppol, InvoiceReader, SimpleInvoice};
+
+fn raw_invoice(name: &str, seller_endpoint: &str, buyer_endpoint: &str) -> RawInvoice {
+    let json = format!(r#"[{{
+        "from": {{ "email": "joe_schower@supaservices.com", "name": "Joe's Services", "addr_one": "Leuteritzweg",
+                  "addr_two": "13", "postal": "22399", "state": "Hamburg", "country": "Germany",
+                  "vat_id": "DE123456789", "endpoint_id": "{}" }},
+        "to": {{ "email": "billing@corp.no", "name": "Super Corp AS", "addr_one": "Karl Johans gate",
+                "addr_two": "22", "postal": "0159", "state": "Oslo", "country": "Norway",
+                "vat_id": "NO974760673MVA", "endpoint_id": "{}", "buyer_reference": "PO-2025-17" }},
+        "items": [
+            {{ "description": "Big truck transport fee", "quantity": 2, "amount": 400, "tax_percent": 0, "tax_category": "export" }}
+        ],
+        "payment": {{ "currency": "EUR", "methods": [
+            {{ "type": "bank_transfer", "iban": "DE89 3704 0044 0532 0130 00", "bic": "COBADEFFXXX",
+              "account_holder": "Joe's Services", "reference": "Invoice 376" }}
+        ] }},
+        "data": {{ "invoice_number": 376, "due_date": "16/jan/2025", "issue_date": "5/jan/2025" }}
+    }}]"#, seller_endpoint, buyer_endpoint);
+    let path = std::env::temp_dir().join(format!("factura_peppol_{}.json", name));
+    std::fs::write(&path, json).unwrap();
+    InvoiceReader::from_json(path.to_string_lossy().into_owned()).unwrap().remove(0)
+}
+
+fn valid_invoice(name: &str) -> RawInvoice {
+    raw_invoice(name, "0088:4035811991014", "0192:974760673")
+}
+
+#[test]
+fn test_invoice() {
+    let xml = invoice_xml(&SimpleInvoice::new(valid_invoice("invoice")).get_invoice()).unwrap();
+
+    assert!(xml.contains(&format!("<cbc:CustomizationID>{}</cbc:CustomizationID>", SPECIFICATION)));
+    assert!(xml.contains(&format!("<cbc:ProfileID>{}</cbc:ProfileID>", PROFILE)));
+    assert!(xml.contains("<cbc:BuyerReference>PO-2025-17</cbc:BuyerReference>"));
+    assert!(xml.contains("<cbc:EndpointID schemeID=\"0088\">4035811991014</cbc:EndpointID>"));
+    assert!(xml.contains("<cbc:EndpointID schemeID=\"0192\">974760673</cbc:EndpointID>"));
+
+    let raw = parse_invoice(&xml).unwrap();
+    assert_eq!(raw.from.endpoint_id.as_deref(), Some("0088:4035811991014"));
+    assert_eq!(raw.to.endpoint_id.as_deref(), Some("0192:974760673"));
+}
+
+#[test]
+fn test_envelope() {
+    let xml = envelope_xml(&SimpleInvoice::new(valid_invoice("envelope")).get_invoice()).unwrap();
+
+    assert!(xml.contains("<StandardBusinessDocument xmlns=\"http://www.unece.org/cefact/namespaces/StandardBusinessDocumentHeader\">"));
+    assert!(xml.contains("<Identifier Authority=\"iso6523-actorid-upis\">0088:4035811991014</Identifier>"));
+    assert!(xml.contains("<Identifier Authority=\"iso6523-actorid-upis\">0192:974760673</Identifier>"));
+    assert!(xml.contains(&format!(
+        "<InstanceIdentifier>urn:oasis:names:specification:ubl:schema:xsd:Invoice-2::Invoice##{}::2.1</InstanceIdentifier>",
+        SPECIFICATION,
+    )));
+    assert!(xml.contains(&format!("<InstanceIdentifier>{}</InstanceIdentifier>", PROFILE)));
+    assert!(xml.contains("<InstanceIdentifier>DE</InstanceIdentifier>"));
+    assert!(xml.trim_end().ends_with("</Invoice>\n</StandardBusinessDocument>"));
+
+    // the wrapped invoice reads like any other
+    let raw = parse_invoice(&xml).unwrap();
+    assert_eq!(raw.data.invoice_number, 376);
+    assert_eq!(raw.to.buyer_reference.as_deref(), Some("PO-2025-17"));
+}
+
+#[test]
+fn test_rules() {
+    let invoice = SimpleInvoice::new(raw_invoice("rules", "0088:4035811991015", "9999:974760673")).get_invoice();
+    let rules: Vec<String> = invoice.to_peppol().validate_peppol().into_iter().map(|v| v.rule).collect();
+    assert_eq!(rules, vec!["PEPPOL-COMMON-R040"]);
+
+    let invoice = SimpleInvoice::new(raw_invoice("scheme", "GLN:4035811991014", "0192:974760672")).get_invoice();
+    let error = invoice_xml(&invoice).err().unwrap();
+    assert_eq!(error.to_string(), concat!(
+        "not a valid Peppol BIS invoice: [PEPPOL-EN16931-CL008] GLN isn't an electronic address scheme (EAS), ",
+        "[PEPPOL-COMMON-R041] 974760672 isn't a valid Norwegian organization number",
+    ));
+
+    let rules: Vec<String> = invoice.to_en16931().validate_peppol().into_iter().map(|v| v.rule).collect();
+    assert!(rules.contains(&String::from("PEPPOL-EN16931-R001")));
+    assert!(rules.contains(&String::from("PEPPOL-EN16931-R004")));
+}
+
+#[test]
+fn test_email_endpoint() {
+    // without an endpoint ID the buyer is addressed by email, which no access point can route to
+    let invoice = SimpleInvoice::new(raw_invoice("email", "0088:4035811991014", "")).get_invoice();
+    let error = envelope_xml(&invoice).err().unwrap();
+    assert_eq!(error.to_string(), concat!(
+        "not a valid Peppol BIS invoice: [PEPPOL-EN16931-CL008] EM:billing@corp.no can't be routed, ",
+        "Peppol participants need an endpoint ID with an ISO 6523 scheme",
+    ));
+}
+
+#[test]
+fn test_export() {
+    SimpleInvoice::new(valid_invoice("export")).to_peppol(String::from("invoice_376_peppol")).unwrap();
+    let raw = InvoiceReader::from_ubl(String::from("invoice_376_peppol.xml")).unwrap().remove(0);
+    assert_eq!(raw.items.len(), 1);
+}
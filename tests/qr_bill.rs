@@ -0,0 +1,153 @@
+use factura::qr_bill::{
+    is_qr_iban, qr_reference, validate_creditor_reference, validate_qr_iban, validate_qr_reference, QrBill, QrBillReference,
+};
+use factura::types::{
+    BankTransfer, InvoiceData, ItemRaw, Payment, PaymentMethod, PersonalInfo, RawInvoice, TaxCategory,
+};
+use factura::{Invoice, SimpleInvoice};
+
+#[test]
+fn test_qr_iban() {
+    assert!(validate_qr_iban("CH44 3199 9123 0008 8901 2").is_ok());
+    assert!(is_qr_iban("CH4431999123000889012"));
+    // regular swiss IBAN, the institution id is outside of the QR-IID range
+    assert!(!is_qr_iban("CH93 0076 2011 6238 5295 7"));
+    assert!(validate_qr_iban("CH93 0076 2011 6238 5295 7").is_err());
+}
+
+#[test]
+fn test_references() {
+    assert!(validate_qr_reference("21 00000 00003 13947 14300 09017").is_ok());
+    assert!(validate_qr_reference("21 00000 00003 13947 14300 09018").is_err());
+    assert_eq!(qr_reference(376), "000000000000000000000003766");
+    assert!(validate_qr_reference(&qr_reference(376)).is_ok());
+
+    assert!(validate_creditor_reference("RF18 5390 0754 7034").is_ok());
+    assert!(validate_creditor_reference("RF19 5390 0754 7034").is_err());
+}
+
+#[test]
+fn test_creditor_reference() {
+    let with_reference = |reference: &str| {
+        let mut raw = swiss_raw_invoice();
+        if let PaymentMethod::BankTransfer(bank) = &mut raw.payment.methods[0] {
+            bank.iban = String::from("CH93 0076 2011 6238 5295 7");
+            bank.reference = Some(reference.to_string());
+        }
+        QrBill::from_invoice(&SimpleInvoice::parse_raw_invoice(raw))
+    };
+    let bill = with_reference(" rf18 5390 0754 7034").unwrap();
+    assert!(matches!(&bill.reference, QrBillReference::Creditor(r) if r == "RF18539007547034"));
+    assert!(with_reference("RF19 5390 0754 7034").is_err());
+    // only text that happens to start with RF, it becomes the message
+    let bill = with_reference("RFQ 2025-17").unwrap();
+    assert!(matches!(bill.reference, QrBillReference::None));
+    assert!(bill.payload().contains("\nNON\n\nRFQ 2025-17\n"));
+}
+
+#[test]
+fn test_spc_payload() {
+    let invoice = SimpleInvoice::parse_raw_invoice(swiss_raw_invoice());
+    let bill = QrBill::from_invoice(&invoice).unwrap();
+
+    assert_eq!(bill.reference_display().unwrap(), "00 00000 00000 00000 00000 03766");
+    assert_eq!(
+        bill.payload(),
+        [
+            "SPC", "0200", "1", "CH4431999123000889012",
+            "S", "Robert Schneider AG", "Rue du Lac", "1268", "2501", "Biel", "CH",
+            "", "", "", "", "", "", "",
+            "960.00", "CHF",
+            "S", "Pia-Maria Rutschmann-Schnyder", "Grosse Marktgasse", "28", "9400", "Rorschach", "CH",
+            "QRR", "000000000000000000000003766", "Invoice 376", "EPD",
+        ].join("\n")
+    );
+}
+
+#[test]
+fn test_payment_part_pdf() {
+    use factura::ExportsPDF;
+    let invoice = SimpleInvoice::new(swiss_raw_invoice());
+    invoice.to_pdf(String::from("services_5_jan_2025_qr_bill")).unwrap();
+}
+
+#[test]
+fn test_pdf_without_payment_part() {
+    use factura::ExportsPDF;
+    // no QR-bill for USD, the pdf is still rendered, only without the payment part
+    let mut raw = swiss_raw_invoice();
+    raw.payment.currency = String::from("USD");
+    assert!(QrBill::from_invoice(&SimpleInvoice::parse_raw_invoice(raw.clone())).is_err());
+    SimpleInvoice::new(raw).to_pdf(String::from("services_5_jan_2025_usd_ch_iban")).unwrap();
+    let pdf = std::fs::read("services_5_jan_2025_usd_ch_iban.pdf").unwrap();
+    assert_eq!(lopdf::Document::load_mem(&pdf).unwrap().get_pages().len(), 1);
+
+    // nor once the invoice is paid
+    let mut raw = swiss_raw_invoice();
+    raw.payment.settled_on = Some(String::from("10/jan/2025"));
+    SimpleInvoice::new(raw).to_pdf(String::from("services_5_jan_2025_qr_bill_paid")).unwrap();
+    let pdf = std::fs::read("services_5_jan_2025_qr_bill_paid.pdf").unwrap();
+    assert_eq!(lopdf::Document::load_mem(&pdf).unwrap().get_pages().len(), 1);
+}
+
+fn swiss_raw_invoice() -> RawInvoice {
+    RawInvoice {
+        from: PersonalInfo {
+            email: String::from("billing@schneider.ch"),
+            name: String::from("Robert Schneider AG"),
+            addr_one: String::from("Rue du Lac"),
+            addr_two: String::from("1268"),
+            postal: String::from("2501"),
+            state: String::from("Biel"),
+            country: String::from("Switzerland"),
+            vat_id: None,
+            phone: None,
+            buyer_reference: None,
+            endpoint_id: None,
+        },
+        to: PersonalInfo {
+            email: String::from("pia@rutschmann.ch"),
+            name: String::from("Pia-Maria Rutschmann-Schnyder"),
+            addr_one: String::from("Grosse Marktgasse"),
+            addr_two: String::from("28"),
+            postal: String::from("9400"),
+            state: String::from("Rorschach"),
+            country: String::from("CH"),
+            vat_id: None,
+            phone: None,
+            buyer_reference: None,
+            endpoint_id: None,
+        },
+        ship_to: None,
+        contact: None,
+        items: vec![
+            ItemRaw {
+                description: String::from("Big truck transport fee"),
+                quantity: 2,
+                amount: 400,
+                tax_percent: 20,
+                tax_category: TaxCategory::Standard,
+            },
+        ],
+        payment: Payment {
+            currency: String::from("CHF"),
+            methods: vec![
+                PaymentMethod::BankTransfer(BankTransfer {
+                    iban: String::from("CH44 3199 9123 0008 8901 2"),
+                    bic: None,
+                    account_holder: String::from("Robert Schneider AG"),
+                    bank_name: None,
+                    reference: None,
+                }),
+            ],
+            tx: None,
+            settled_on: None,
+        },
+        data: InvoiceData {
+            invoice_number: 376,
+            due_date: String::from("16/jan/2025"),
+            issue_date: String::from("5/jan/2025"),
+            history: vec![],
+        },
+    }
+}
@@ -0,0 +1,137 @@
+use factura::{types::PaymentMethod, Invoice, InvoiceReader, InvoiceWriter, SimpleInvoice};
+
+const INVOICE_JSON: &str = r#"[{
+    "from": { "email": "joe_schower@supaservices.com", "name": "Joe's Services", "addr_one": "Leuteritzweg",
+              "addr_two": "13", "postal": "22399", "state": "Hamburg", "country": "Germany" },
+    "to": { "email": "billing@corp.com", "name": "Super Corp", "addr_one": "Tegelsbarg",
+            "addr_two": "73", "postal": "22399", "state": "Hamburg", "country": "Germany" },
+    "items": [ { "description": "Big truck transport fee", "quantity": 2, "amount": 400, "tax_percent": 20 } ],
+    "payment": {
+        "wallet_address": "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "currency": "EURC",
+        "tx": "none",
+        "methods": [
+            { "type": "bank_transfer", "iban": "DE89 3704 0044 0532 0130 00", "account_holder": "Joe's Services" },
+            { "type": "paypal", "account": "billing@supaservices.com" }
+        ]
+    },
+    "data": { "invoice_number": 376, "due_date": "16/jan/2025", "issue_date": "5/jan/2025" }
+}]"#;
+
+fn write_file(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_payment_methods_from_json() {
+    let file = write_file("factura_reader_methods.json", INVOICE_JSON);
+    let invoices = InvoiceReader::from_json(file).unwrap();
+    let methods = &invoices[0].payment.methods;
+
+    // the legacy wallet address comes first, followed by the listed methods
+    assert_eq!(methods.len(), 3);
+    assert!(matches!(&methods[0], PaymentMethod::Crypto(w) if w.currency == "EURC"));
+    assert!(matches!(&methods[1], PaymentMethod::BankTransfer(_)));
+    assert!(matches!(&methods[2], PaymentMethod::PayPal { .. }));
+    // "none" is how older files mark an unpaid invoice
+    assert!(invoices[0].payment.tx.is_none());
+}
+
+#[test]
+fn test_file_with_only_a_wallet() {
+    // the format before payment methods, the wallet address isn't of any chain in particular
+    let json = r#"[{
+        "from": { "email": "joe_schower@supaservices.com", "name": "Joe's Services", "addr_one": "Leuteritzweg",
+                  "addr_two": "13", "postal": "22399", "state": "Hamburg", "country": "Germany" },
+        "to": { "email": "billing@corp.com", "name": "Super Corp", "addr_one": "Tegelsbarg",
+                "addr_two": "73", "postal": "22399", "state": "Hamburg", "country": "Germany" },
+        "items": [ { "description": "Big truck transport fee", "quantity": 2, "amount": 400, "tax_percent": 20 } ],
+        "payment": { "wallet_address": "0x123123123123123123123123123", "currency": "USDC", "tx": "none" },
+        "data": { "invoice_number": 376, "due_date": "16/jan/2025", "issue_date": "5/jan/2025" }
+    }]"#;
+    let invoices = InvoiceReader::from_json(write_file("factura_reader_wallet_only.json", json)).unwrap();
+    let methods = &invoices[0].payment.methods;
+
+    assert_eq!(methods.len(), 1);
+    assert!(matches!(&methods[0], PaymentMethod::Crypto(w) if w.address == "0x123123123123123123123123123"));
+    assert!(invoices[0].payment.tx.is_none());
+    assert_eq!(SimpleInvoice::parse_raw_invoice(invoices[0].clone()).total.amount_due, 960);
+
+    // wallets listed as payment methods are still checked against their chain
+    let json = json.replace(r#""tx": "none""#, r#""tx": "none", "methods": [
+        { "type": "crypto", "address": "0x123123123123123123123123123", "currency": "USDC", "chain": "ethereum" } ]"#);
+    assert!(InvoiceReader::from_json(write_file("factura_reader_bad_wallet.json", &json)).is_err());
+}
+
+#[test]
+fn test_invalid_iban_is_rejected() {
+    let json = INVOICE_JSON.replace("DE89 3704", "DE88 3704");
+    let file = write_file("factura_reader_bad_iban.json", &json);
+    assert!(InvoiceReader::from_json(file).is_err());
+}
+
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir().join(name).to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_parsed_invoice_to_json() {
+    let raw = InvoiceReader::from_json(write_file("factura_reader_export.json", INVOICE_JSON)).unwrap();
+    let parsed: Vec<_> = raw.into_iter().map(SimpleInvoice::parse_raw_invoice).collect();
+    let file = temp_path("factura_writer_parsed.json");
+    InvoiceWriter::to_json(file.clone(), &parsed).unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    assert_eq!(json[0]["items"][0]["total"], 960);
+    assert_eq!(json[0]["total"]["no_tax"], 800);
+    assert_eq!(json[0]["total"]["tax_amount"], 160);
+    assert_eq!(json[0]["total"]["amount_due"], 960);
+
+    // the totals are calculated again when reading
+    let reread = InvoiceReader::from_json(file).unwrap();
+    let reparsed = SimpleInvoice::parse_raw_invoice(reread.into_iter().next().unwrap());
+    assert_eq!(reparsed.total.amount_due, 960);
+    assert_eq!(reparsed.items[0].total, 960);
+    assert_eq!(reparsed.payment.methods.len(), 3);
+    assert_eq!(reparsed.to.name, "Super Corp");
+}
+
+#[test]
+fn test_parsed_invoice_to_toml() {
+    let raw = InvoiceReader::from_json(write_file("factura_reader_export_toml.json", INVOICE_JSON)).unwrap();
+    let parsed = SimpleInvoice::parse_raw_invoice(raw.into_iter().next().unwrap());
+    let file = temp_path("factura_writer_parsed.toml");
+    InvoiceWriter::to_toml(file.clone(), &[parsed]).unwrap();
+
+    let toml = std::fs::read_to_string(&file).unwrap();
+    assert!(toml.contains("[[invoices]]"));
+    assert!(toml.contains("amount_due = 960"));
+
+    let reread = InvoiceReader::from_toml(file).unwrap();
+    assert_eq!(reread.len(), 1);
+    assert_eq!(reread[0].data.invoice_number, 376);
+    assert_eq!(reread[0].items[0].amount, 400);
+    assert_eq!(SimpleInvoice::parse_raw_invoice(reread[0].clone()).total.amount_due, 960);
+}
+
+#[test]
+fn test_raw_invoice_round_trip() {
+    let raw = InvoiceReader::from_json(write_file("factura_reader_raw.json", INVOICE_JSON)).unwrap();
+    let json = temp_path("factura_writer_raw.json");
+    let toml = temp_path("factura_writer_raw.toml");
+    InvoiceWriter::raw_to_json(json.clone(), &raw).unwrap();
+    InvoiceWriter::raw_to_toml(toml.clone(), &raw).unwrap();
+
+    // raw invoices have nothing calculated to write
+    assert!(!std::fs::read_to_string(&json).unwrap().contains("amount_due"));
+    // read and written again, the files come out the same
+    let again = temp_path("factura_writer_raw_again.json");
+    InvoiceWriter::raw_to_json(again.clone(), &InvoiceReader::from_json(json.clone()).unwrap()).unwrap();
+    assert_eq!(std::fs::read_to_string(&again).unwrap(), std::fs::read_to_string(&json).unwrap());
+
+    let again = temp_path("factura_writer_raw_again.toml");
+    InvoiceWriter::raw_to_toml(again.clone(), &InvoiceReader::from_toml(toml.clone()).unwrap()).unwrap();
+    assert_eq!(std::fs::read_to_string(&again).unwrap(), std::fs::read_to_string(&toml).unwrap());
+}
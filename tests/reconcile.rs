@@ -0,0 +1,101 @@
+use factura::reconcile::{reconcile, LedgerReader, Settlement};
+use factura::{types::ParsedInvoice, InvoiceReader, SimpleInvoice};
+
+const INVOICES_JSON: &str = r#"[
+    { "number": 376, "items": [ { "description": "Big truck transport fee", "quantity": 2, "amount": 400, "tax_percent": 20 } ],
+      "reference": "RF18 5390 0754 7034" },
+    { "number": 377, "items": [ { "description": "Loading", "quantity": 1, "amount": 100, "tax_percent": 0 } ] },
+    { "number": 378, "items": [ { "description": "Loading", "quantity": 1, "amount": 100, "tax_percent": 0 } ] },
+    { "number": 379, "items": [ { "description": "Loading", "quantity": 1, "amount": 100, "tax_percent": 0 } ] }
+]"#;
+
+const LEDGER_CSV: &str = "date,amount,currency,reference,account,tx
+2025-01-10,960.00,EUR,RF18 5390 0754 7034,DE89370400440532013000,
+2025-01-11,10.00,EUR,Invoice 376 rest,,
+2025-01-11,50.00,EUR,Payment INV-0377,,
+2025-01-12,20.00,EUR,,DE89 3704 0044 0532 0130 00,
+2025-01-13,100.00,EUR,,DE89 3704 0044 0532 0130 00,
+2025-01-14,30.00,USD,Invoice 379,,
+";
+
+fn write_file(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+/// Invoices to the same customer, all payable to the same IBAN
+fn invoices() -> Vec<ParsedInvoice> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(INVOICES_JSON).unwrap();
+    let invoices: Vec<String> = entries.iter().map(|entry| format!(r#"{{
+        "from": {{ "email": "joe_schower@supaservices.com", "name": "Joe's Services", "addr_one": "Leuteritzweg",
+                  "addr_two": "13", "postal": "22399", "state": "Hamburg", "country": "Germany" }},
+        "to": {{ "email": "billing@corp.com", "name": "Super Corp", "addr_one": "Tegelsbarg",
+                "addr_two": "73", "postal": "22399", "state": "Hamburg", "country": "Germany" }},
+        "items": {},
+        "payment": {{
+            "currency": "EUR",
+            "methods": [ {{ "type": "bank_transfer", "iban": "DE89 3704 0044 0532 0130 00",
+                           "account_holder": "Joe's Services", "reference": {} }} ]
+        }},
+        "data": {{ "invoice_number": {}, "due_date": "16/jan/2025", "issue_date": "5/jan/2025" }}
+    }}"#, entry["items"], entry["reference"], entry["number"])).collect();
+
+    let file = write_file("factura_reconcile_invoices.json", &format!("[{}]", invoices.join(",")));
+    InvoiceReader::from_json(file).unwrap()
+        .into_iter()
+        .map(|raw| SimpleInvoice::new(raw).get_invoice())
+        .collect()
+}
+
+#[test]
+fn test_reconcile_csv_ledger() {
+    let ledger = LedgerReader::from_csv(write_file("factura_reconcile_ledger.csv", LEDGER_CSV)).unwrap();
+    assert_eq!(ledger.len(), 6);
+    assert_eq!(ledger[0].tx, None);
+
+    let report = reconcile(&invoices(), &ledger);
+    let statuses: Vec<Settlement> = report.invoices.iter().map(|i| i.status).collect();
+    assert_eq!(statuses, vec![Settlement::Overpaid, Settlement::Partial, Settlement::Paid, Settlement::Unmatched]);
+
+    assert_eq!(report.invoices[0].received, 970.0);
+    assert_eq!(report.invoices[0].outstanding(), -10.0);
+    assert_eq!(report.invoices[1].outstanding(), 50.0);
+
+    // 20 EUR to the shared IBAN can't be told apart, and USD is never matched to EUR invoices
+    let unmatched: Vec<f64> = report.unmatched.iter().map(|t| t.amount).collect();
+    assert_eq!(unmatched, vec![20.0, 30.0]);
+    assert_eq!(report.with_status(Settlement::Partial).count(), 1);
+}
+
+#[test]
+fn test_reconcile_json_ledger_by_tx() {
+    let tx = "0x88df016429689c079f3b2f6ad39fa052532c56795b733da78a91ebe6a713944b";
+    let mut invoices = invoices();
+    invoices[3].payment.tx = Some(String::from(tx));
+
+    let json = format!(r#"[ {{ "amount": 100, "currency": "eur", "tx": "{}" }} ]"#, tx);
+    let ledger = LedgerReader::from_json(write_file("factura_reconcile_ledger.json", &json)).unwrap();
+
+    let report = reconcile(&invoices, &ledger);
+    assert_eq!(report.invoices[3].status, Settlement::Paid);
+    assert!(report.unmatched.is_empty());
+    assert!(report.to_string().contains("Invoice #379 (Super Corp): Paid, received 100.00 of 100.00 EUR"));
+}
+
+#[test]
+fn test_reference_needs_invoice_number() {
+    // numbers that aren't introduced as the invoice number don't match it
+    let ledger = "date,amount,currency,reference,account,tx
+2025-01-15,100.00,EUR,Rent 2025-03 ref 378,,
+2025-01-15,100.00,EUR,Order 379 from 2025,,
+2025-01-16,100.00,EUR,Payment #0378,,
+2025-01-16,100.00,EUR,Invoice no. 379,,
+";
+    let ledger = LedgerReader::from_csv(write_file("factura_reconcile_references.csv", ledger)).unwrap();
+    let report = reconcile(&invoices(), &ledger);
+    assert_eq!(report.invoices[2].transfers[0].reference.as_deref(), Some("Payment #0378"));
+    assert_eq!(report.invoices[3].transfers[0].reference.as_deref(), Some("Invoice no. 379"));
+    let unmatched: Vec<&str> = report.unmatched.iter().filter_map(|t| t.reference.as_deref()).collect();
+    assert_eq!(unmatched, vec!["Rent 2025-03 ref 378", "Order 379 from 2025"]);
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "sign")]
+
+use factura::pdfa::PdfA;
+use factura::signature::{verify, SignError, SignatureField, Signer};
+use factura::types::RawInvoice;
+use factura::{ExportsPDF, SimpleInvoice};
+use lopdf::content::Content;
+use lopdf::Object;
+use openssl::asn1::Asn1Time;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::x509::{X509NameBuilder, X509};
+
+mod common;
+
+fn raw_invoice() -> RawInvoice {
+    common::invoice(378).methods(&[&common::bank_transfer("Invoice 378")]).raw()
+}
+
+/// Self-signed certificate for "Joe's Services" in a PKCS#12 file
+fn pkcs12(password: &str) -> Vec<u8> {
+    pkcs12_of(PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap(), password)
+}
+
+fn pkcs12_of(key: PKey<Private>, password: &str) -> Vec<u8> {
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "Joe's Services").unwrap();
+    let name = name.build();
+
+    let mut certificate = X509::builder().unwrap();
+    certificate.set_version(2).unwrap();
+    certificate.set_subject_name(&name).unwrap();
+    certificate.set_issuer_name(&name).unwrap();
+    certificate.set_pubkey(&key).unwrap();
+    certificate.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    certificate.set_not_after(&Asn1Time::days_from_now(365).unwrap()).unwrap();
+    certificate.sign(&key, MessageDigest::sha256()).unwrap();
+    let certificate = certificate.build();
+
+    Pkcs12::builder().name("Joe's Services").pkey(&key).cert(&certificate)
+        .build2(password).unwrap().to_der().unwrap()
+}
+
+fn signer() -> Signer {
+    Signer::from_pkcs12(&pkcs12("secret"), "secret").unwrap()
+        .with_reason("Invoice issued")
+        .with_location("Hamburg")
+}
+
+#[test]
+fn test_invisible_signature() {
+    SimpleInvoice::new(raw_invoice()).with_signature(signer(), false)
+        .to_pdf(String::from("invoice_378_signed")).unwrap();
+    let pdf = std::fs::read("invoice_378_signed.pdf").unwrap();
+
+    let text = String::from_utf8_lossy(&pdf);
+    assert!(text.contains("/SubFilter/ETSI.CAdES.detached"));
+    assert!(text.contains("/FT/Sig"));
+    assert!(text.contains("/SigFlags 3"));
+
+    let signatures = verify(&pdf).unwrap();
+    assert_eq!(signatures.len(), 1);
+    assert_eq!(signatures[0].signer, "Joe's Services");
+    assert_eq!(signatures[0].reason.as_deref(), Some("Invoice issued"));
+    assert_eq!(signatures[0].location.as_deref(), Some("Hamburg"));
+    assert!(signatures[0].signed_at.as_ref().is_some_and(|m| m.starts_with("D:")));
+    assert!(signatures[0].covers_document);
+}
+
+/// DER of the CMS signature in the `/Contents` of the signature dictionary
+fn contents(pdf: &[u8]) -> Vec<u8> {
+    let at = pdf.windows(10).position(|w| w == b"/Contents<").unwrap() + 10;
+    let end = at + pdf[at..].iter().position(|b| *b == b'>').unwrap();
+    pdf[at..end].chunks(2).map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap()).collect()
+}
+
+#[test]
+fn test_pades_baseline_attributes() {
+    SimpleInvoice::new(raw_invoice()).with_signature(signer(), false)
+        .to_pdf(String::from("invoice_378_signed_pades")).unwrap();
+    let pdf = std::fs::read("invoice_378_signed_pades.pdf").unwrap();
+    let cms = contents(&pdf);
+
+    let signing_certificate_v2 = [0x06, 0x0b, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x02, 0x2f];
+    let signing_time = [0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x05];
+    assert!(cms.windows(signing_certificate_v2.len()).any(|w| w == signing_certificate_v2));
+    assert!(!cms.windows(signing_time.len()).any(|w| w == signing_time));
+}
+
+#[test]
+fn test_ec_key() {
+    let key = EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap();
+    let signer = Signer::from_pkcs12(&pkcs12_of(PKey::from_ec_key(key).unwrap(), "secret"), "secret").unwrap();
+    SimpleInvoice::new(raw_invoice()).with_signature(signer, false)
+        .to_pdf(String::from("invoice_378_signed_ec")).unwrap();
+    let pdf = std::fs::read("invoice_378_signed_ec.pdf").unwrap();
+    assert_eq!(verify(&pdf).unwrap()[0].signer, "Joe's Services");
+}
+
+#[test]
+fn test_visible_signature_on_pdf_a() {
+    SimpleInvoice::new(raw_invoice()).with_pdf_a(PdfA::A3b).with_signature(signer(), true)
+        .to_pdf(String::from("invoice_378_signed_visible")).unwrap();
+    let pdf = std::fs::read("invoice_378_signed_visible.pdf").unwrap();
+
+    let text = String::from_utf8_lossy(&pdf);
+    assert!(text.contains("<pdfaid:part>3</pdfaid:part>"));
+    // the binary comment after the header survives the rewrite
+    assert!(pdf.starts_with(b"%PDF-1.7\n%") && pdf[10..14].iter().all(|b| *b > 127));
+    assert!(text.contains("/Subtype/Widget"));
+    assert!(!text.contains("/Rect[0 0 0 0]"));
+    assert!(verify(&pdf).unwrap()[0].covers_document);
+
+    // the field draws the signer, date and reason itself, with the invoice's embedded font
+    let doc = lopdf::Document::load_mem(&pdf).unwrap();
+    let widget = doc.objects.values().filter_map(|object| object.as_dict().ok())
+        .find(|dict| dict.get(b"Subtype").and_then(Object::as_name).is_ok_and(|s| s == b"Widget"))
+        .unwrap();
+    let appearance = widget.get(b"AP").and_then(Object::as_dict).and_then(|ap| ap.get(b"N"))
+        .and_then(Object::as_reference).and_then(|id| doc.get_object(id)).and_then(Object::as_stream)
+        .unwrap();
+    let content = Content::decode(&appearance.content).unwrap();
+    assert_eq!(content.operations.iter().filter(|operation| operation.operator == "Tj").count(), 3);
+    let font = appearance.dict.get(b"Resources").and_then(Object::as_dict)
+        .and_then(|resources| resources.get(b"Font")).and_then(Object::as_dict)
+        .and_then(|fonts| fonts.get(b"F0")).unwrap();
+    assert!(font.as_reference().is_ok());
+}
+
+#[test]
+fn test_tampered_pdf() {
+    SimpleInvoice::new(raw_invoice()).with_signature(signer(), false)
+        .to_pdf(String::from("invoice_378_signed_tampered")).unwrap();
+    let mut pdf = std::fs::read("invoice_378_signed_tampered.pdf").unwrap();
+
+    let title = pdf.windows(11).position(|w| w == b"Invoice 378").unwrap();
+    pdf[title + 10] = b'9';
+    assert!(matches!(verify(&pdf), Err(SignError::Invalid)));
+}
+
+#[test]
+fn test_crafted_byte_range() {
+    SimpleInvoice::new(raw_invoice()).with_signature(signer(), false)
+        .to_pdf(String::from("invoice_378_signed_byte_range")).unwrap();
+    let pdf = std::fs::read("invoice_378_signed_byte_range.pdf").unwrap();
+    let at = pdf.windows(11).position(|w| w == b"/ByteRange[").unwrap();
+    let end = at + pdf[at..].iter().position(|b| *b == b']').unwrap();
+    let range: Vec<i64> = String::from_utf8_lossy(&pdf[at + 11..end])
+        .split_whitespace().map(|v| v.parse().unwrap()).collect();
+
+    let with_range = |range: [i64; 4]| {
+        let mut crafted = pdf.clone();
+        let mut written = format!("/ByteRange[{} {} {} {}", range[0], range[1], range[2], range[3]).into_bytes();
+        written.resize(end - at, b' ');
+        crafted[at..end].copy_from_slice(&written);
+        crafted
+    };
+    // negative offsets, ranges that overlap, and a gap wider than the signature
+    assert!(matches!(verify(&with_range([0, -1, range[2], range[3]])), Err(SignError::Invalid)));
+    assert!(matches!(verify(&with_range([0, range[2] + 1, range[2], range[3]])), Err(SignError::Invalid)));
+    assert!(matches!(verify(&with_range([0, range[1] - 1, range[2], range[3]])), Err(SignError::Invalid)));
+    assert!(matches!(verify(&with_range([0, range[1], range[2], i64::MAX])), Err(SignError::Invalid)));
+}
+
+#[test]
+fn test_sign_errors() {
+    SimpleInvoice::new(raw_invoice()).to_pdf(String::from("invoice_378_unsigned")).unwrap();
+    let pdf = std::fs::read("invoice_378_unsigned.pdf").unwrap();
+    assert!(matches!(verify(&pdf), Err(SignError::NotSigned)));
+
+    let signer = signer().with_field(SignatureField::Visible { page: 1, rect: [10.0, 10.0, 80.0, 30.0] });
+    let signed = signer.sign(pdf).unwrap();
+    assert_eq!(verify(&signed).unwrap().len(), 1);
+    assert!(matches!(signer.sign(signed), Err(SignError::AlreadySigned)));
+
+    assert!(matches!(Signer::from_pkcs12(&pkcs12("secret"), "wrong"), Err(SignError::OpenSsl(_))));
+}
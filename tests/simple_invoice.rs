@@ -1,4 +1,6 @@
-use factura::{types::{InvoiceData, ItemRaw, Payment, PersonalInfo, RawInvoice}, ExportsPDF, ExportsHTML, SimpleInvoice};
+#![allow(clippy::let_unit_value)]
+
+use factura::{types::{BankTransfer, Chain, Contact, CryptoWallet, InvoiceData, ItemRaw, Payment, PaymentMethod, PersonalInfo, RawInvoice, TaxCategory}, ExportsPDF, ExportsHTML, SimpleInvoice};
 
 
 #[test]
@@ -17,6 +19,121 @@ fn test_html() {
     assert_eq!(result, ());
 }
 
+#[test]
+fn test_ship_to_and_contact() {
+    let pdf = SimpleInvoice::new(shipped_raw_invoice());
+    let result = pdf.to_pdf(String::from("services_5_jan_2025_shipped")).unwrap();
+    assert_eq!(result, ());
+
+    let html = SimpleInvoice::new(shipped_raw_invoice());
+    let result = html.to_html(String::from("services_5_jan_2025_shipped")).unwrap();
+    assert_eq!(result, ());
+}
+
+#[test]
+fn test_multiple_payment_methods() {
+    let pdf = SimpleInvoice::new(multi_method_raw_invoice());
+    let result = pdf.to_pdf(String::from("services_5_jan_2025_methods")).unwrap();
+    assert_eq!(result, ());
+
+    let html = SimpleInvoice::new(multi_method_raw_invoice());
+    let result = html.to_html(String::from("services_5_jan_2025_methods")).unwrap();
+    assert_eq!(result, ());
+}
+
+#[test]
+fn test_paid_invoice_links_transaction() {
+    let pdf = SimpleInvoice::new(paid_raw_invoice());
+    pdf.to_pdf(String::from("services_5_jan_2025_paid")).unwrap();
+    let rendered = std::fs::read("services_5_jan_2025_paid.pdf").unwrap();
+    let rendered = String::from_utf8_lossy(&rendered);
+    assert!(rendered.contains("/URI"));
+    assert!(rendered.contains(&format!("https://etherscan.io/tx/{}", PAID_TX)));
+
+    let html = SimpleInvoice::new(paid_raw_invoice());
+    html.to_html(String::from("services_5_jan_2025_paid")).unwrap();
+    let rendered = std::fs::read_to_string("services_5_jan_2025_paid.html").unwrap();
+    assert!(rendered.contains(&format!("<a href=\"https://etherscan.io/tx/{}\">", PAID_TX)));
+    assert!(rendered.contains("Paid on 10/jan/2025"));
+}
+
+#[test]
+fn test_paid_invoice_without_payment_codes() {
+    let mut unpaid = multi_method_raw_invoice();
+    unpaid.payment.methods.retain(|method| matches!(method, PaymentMethod::BankTransfer(_) | PaymentMethod::Crypto(_)));
+    let mut paid = unpaid.clone();
+    paid.payment.settled_on = Some(String::from("10/jan/2025"));
+
+    // the GiroCode and the wallet's QR code are the only images of the invoice
+    for (raw, name, codes) in [(unpaid, "services_5_jan_2025_unpaid_codes", true), (paid, "services_5_jan_2025_paid_codes", false)] {
+        SimpleInvoice::new(raw.clone()).to_pdf(name.to_string()).unwrap();
+        let rendered = std::fs::read(format!("{}.pdf", name)).unwrap();
+        assert_eq!(String::from_utf8_lossy(&rendered).contains("/Subtype/Image"), codes);
+
+        SimpleInvoice::new(raw).to_html(name.to_string()).unwrap();
+        let rendered = std::fs::read_to_string(format!("{}.html", name)).unwrap();
+        assert_eq!(rendered.contains("<svg"), codes);
+        assert_eq!(rendered.contains("Pay with wallet app"), codes);
+    }
+}
+
+const PAID_TX: &str = "0x88df016429689c079f3b2f6ad39fa052532c56795b733da78a91ebe6a713944b";
+
+fn paid_raw_invoice() -> RawInvoice {
+    let mut raw = raw_invoice();
+    raw.payment.tx = Some(String::from(PAID_TX));
+    raw.payment.settled_on = Some(String::from("10/jan/2025"));
+    raw
+}
+
+fn multi_method_raw_invoice() -> RawInvoice {
+    let mut raw = raw_invoice();
+    raw.payment.currency = String::from("EUR");
+    raw.payment.methods = vec![
+        PaymentMethod::BankTransfer(BankTransfer {
+            iban: String::from("DE89 3704 0044 0532 0130 00"),
+            bic: Some(String::from("COBADEFFXXX")),
+            account_holder: String::from("Joe's Services"),
+            bank_name: Some(String::from("Commerzbank")),
+            reference: Some(String::from("INV-376")),
+        }),
+        PaymentMethod::CardLink { url: String::from("https://pay.example.com/inv/376") },
+        PaymentMethod::PayPal { account: String::from("billing@supaservices.com") },
+        PaymentMethod::Crypto(CryptoWallet {
+            address: String::from("7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU"), 
+            currency: String::from("USDC"), 
+            chain: Chain::Solana,
+            token: Some(String::from("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")),
+            decimals: None,
+        }),
+    ];
+    raw
+}
+
+fn shipped_raw_invoice() -> RawInvoice {
+    let mut raw = raw_invoice();
+    raw.ship_to = Some(PersonalInfo { 
+        email: String::new(), 
+        name: String::from("Super Corp Warehouse"), 
+        addr_one: String::from("Hafenstrasse"), 
+        addr_two: String::from("2"), 
+        postal: String::from("20457"), 
+        state: String::from("Hamburg"), 
+        country: String::from("Germany"),
+        vat_id: None,
+        phone: None,
+        buyer_reference: None,
+        endpoint_id: None,
+    });
+    raw.contact = Some(Contact {
+        name: String::from("Jane Doe"),
+        department: Some(String::from("Accounts payable")),
+        email: None,
+        phone: None,
+    });
+    raw
+}
+
 fn raw_invoice() -> RawInvoice {
     RawInvoice {
         from: PersonalInfo { 
@@ -26,7 +143,11 @@ fn raw_invoice() -> RawInvoice {
             addr_two: String::from("13"), 
             postal: String::from("22399"),
             state: String::from("Hamburg"), 
-            country: String::from("Germany") 
+            country: String::from("Germany"),
+            vat_id: None,
+            phone: None,
+            buyer_reference: None,
+            endpoint_id: None,
         },
         to: PersonalInfo { 
             email: String::from("billing@corp.com"), 
@@ -35,31 +156,49 @@ fn raw_invoice() -> RawInvoice {
             addr_two: String::from("73"), 
             postal: String::from("22399"), 
             state: String::from("Hamburg"), 
-            country: String::from("Germany") 
+            country: String::from("Germany"),
+            vat_id: None,
+            phone: None,
+            buyer_reference: None,
+            endpoint_id: None,
         },
+        ship_to: None,
+        contact: None,
         items: vec![
             ItemRaw{ 
                 description: String::from("Big truck transport fee"), 
                 quantity: 2,
                 amount: 400, 
                 tax_percent: 20,
+                tax_category: TaxCategory::Standard,
             },
             ItemRaw{ 
                 description: String::from("Big truck transport fee"), 
                 quantity: 2,
                 amount: 400, 
                 tax_percent: 20,
+                tax_category: TaxCategory::Standard,
             }
         ],
         payment: Payment{ 
-            wallet_address: String::from("0x123123123123123123123123123"), 
             currency: String::from("EURC"), 
-            tx: String::from("none"), 
+            methods: vec![
+                PaymentMethod::Crypto(CryptoWallet {
+                    address: String::from("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"), 
+                    currency: String::from("EURC"), 
+                    chain: Chain::ETHEREUM,
+                    token: None,
+                    decimals: None,
+                }),
+            ],
+            tx: None,
+            settled_on: None, 
         },
         data: InvoiceData { 
             invoice_number: 376, 
             due_date: String::from("16/jan/2025"), 
-            issue_date: String::from("5/jan/2025") 
+            issue_date: String::from("5/jan/2025"),
+            history: vec![],
         }
     }
 }
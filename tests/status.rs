@@ -0,0 +1,90 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use factura::status::parse_date;
+use factura::types::{InvoiceStatus, ParsedInvoice};
+use factura::{ExportsHTML, ExportsPDF, SimpleInvoice};
+
+mod common;
+
+fn read_invoices(history: &str) -> Result<Vec<SimpleInvoice>, Box<dyn std::error::Error>> {
+    Ok(common::invoice(376).history(history).read()?.into_iter().map(SimpleInvoice::new).collect())
+}
+
+fn draft() -> ParsedInvoice {
+    read_invoices("[]").unwrap().remove(0).get_invoice()
+}
+
+#[test]
+fn test_transitions() {
+    let mut invoice = draft();
+    assert_eq!(invoice.status(), InvoiceStatus::Draft);
+    assert!(invoice.transition(InvoiceStatus::Paid).is_err());
+
+    invoice.transition(InvoiceStatus::Issued).unwrap();
+    assert!(invoice.transition(InvoiceStatus::Draft).is_err());
+
+    let paid_at = Utc.with_ymd_and_hms(2025, 1, 10, 9, 30, 0).unwrap();
+    invoice.transition_at(InvoiceStatus::Paid, paid_at).unwrap();
+    assert_eq!(invoice.status(), InvoiceStatus::Paid);
+    assert_eq!(invoice.payment.settled_on.as_deref(), Some("2025-01-10"));
+    assert_eq!(invoice.data.history[1].at, "2025-01-10T09:30:00+00:00");
+
+    // paid invoices are final
+    assert!(invoice.transition(InvoiceStatus::Void).is_err());
+    assert_eq!(invoice.stamp(), Some("PAID"));
+}
+
+#[test]
+fn test_overdue_detection() {
+    let before = NaiveDate::from_ymd_opt(2025, 1, 16).unwrap();
+    let after = NaiveDate::from_ymd_opt(2025, 1, 17).unwrap();
+
+    let mut invoice = draft();
+    assert_eq!(invoice.due_date(), Some(before));
+    // drafts haven't been sent, so they can't be late
+    assert!(!invoice.is_overdue(after));
+
+    invoice.transition(InvoiceStatus::Issued).unwrap();
+    assert!(!invoice.mark_overdue(before).unwrap());
+    assert!(invoice.mark_overdue(after).unwrap());
+    assert_eq!(invoice.status(), InvoiceStatus::Overdue);
+    assert_eq!(invoice.data.history.last().unwrap().at, "2025-01-17T00:00:00+00:00");
+    assert!(!invoice.mark_overdue(after).unwrap());
+
+    invoice.transition(InvoiceStatus::Paid).unwrap();
+    assert!(!invoice.is_overdue(after));
+}
+
+#[test]
+fn test_history_from_file() {
+    let history = r#"[ { "status": "issued", "at": "2025-01-05T10:00:00Z" },
+                       { "status": "void", "at": "2025-01-06T10:00:00Z" } ]"#;
+    let invoice = read_invoices(history).unwrap().remove(0);
+    assert_eq!(invoice.get_invoice().status(), InvoiceStatus::Void);
+
+    let skipped_issue = r#"[ { "status": "paid", "at": "2025-01-05T10:00:00Z" } ]"#;
+    assert!(read_invoices(skipped_issue).is_err());
+    let bad_timestamp = r#"[ { "status": "issued", "at": "5/jan/2025" } ]"#;
+    assert!(read_invoices(bad_timestamp).is_err());
+}
+
+#[test]
+fn test_void_stamp() {
+    let history = r#"[ { "status": "issued", "at": "2025-01-05T10:00:00Z" },
+                       { "status": "void", "at": "2025-01-06T10:00:00Z" } ]"#;
+    let read = || read_invoices(history).unwrap().remove(0);
+    assert_eq!(read().get_invoice().stamp(), Some("VOID"));
+
+    read().to_pdf(String::from("services_5_jan_2025_void")).unwrap();
+    read().to_html(String::from("services_5_jan_2025_void")).unwrap();
+    let rendered = std::fs::read_to_string("services_5_jan_2025_void.html").unwrap();
+    assert!(rendered.contains(r#"<div class="stamp">VOID</div>"#));
+}
+
+#[test]
+fn test_date_formats() {
+    let date = NaiveDate::from_ymd_opt(2025, 1, 16);
+    for text in ["2025-01-16", "16/jan/2025", "16/Jan/2025", "16/01/2025", "16.01.2025", "16 January 2025"] {
+        assert_eq!(parse_date(text), date, "{}", text);
+    }
+    assert_eq!(parse_date("next tuesday"), None);
+}
@@ -0,0 +1,85 @@
+use chrono::NaiveDate;
+use factura::store::{InvoiceQuery, InvoiceStore};
+use factura::types::{InvoiceStatus, ParsedInvoice};
+
+mod common;
+
+fn invoice(number: u16, customer: &str, issue_date: &str, amount: u32) -> ParsedInvoice {
+    let item = format!(r#"{{ "description": "Transport fee", "quantity": 1, "amount": {}, "tax_percent": 0 }}"#, amount);
+    common::invoice(number)
+        .buyer(customer)
+        .buyer_email(&format!("billing@{}.com", customer))
+        .items(&[&item])
+        .methods(&[
+            r#"{ "type": "bank_transfer", "iban": "DE89 3704 0044 0532 0130 00", "account_holder": "Joe's Services" }"#,
+            r#"{ "type": "crypto", "address": "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", "currency": "USDC", "chain": "base" }"#,
+        ])
+        .issued(issue_date)
+        .due("30/jan/2025")
+        .parsed()
+}
+
+fn empty_store(name: &str) -> InvoiceStore {
+    let path = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_file(&path);
+    InvoiceStore::open(path).unwrap()
+}
+
+#[test]
+fn test_save_and_update() {
+    let store = empty_store("factura_store_update.jsonl");
+    assert_eq!(store.next_invoice_number().unwrap(), 1);
+
+    let mut first = invoice(376, "Super Corp", "5/jan/2025", 960);
+    store.save(&first).unwrap();
+    store.save(&invoice(377, "Super Corp", "6/jan/2025", 100)).unwrap();
+
+    first.transition(InvoiceStatus::Issued).unwrap();
+    store.save(&first).unwrap();
+
+    let stored = store.get(376).unwrap().unwrap();
+    assert_eq!(stored.status(), InvoiceStatus::Issued);
+    assert_eq!(stored.total.amount_due, 960);
+    assert!(matches!(&stored.payment.methods[1], factura::types::PaymentMethod::Crypto(w) if w.chain.name() == "Base"));
+    assert_eq!(store.all().unwrap().len(), 2);
+    assert_eq!(store.next_invoice_number().unwrap(), 378);
+
+    store.compact().unwrap();
+    let lines = std::fs::read_to_string(std::env::temp_dir().join("factura_store_update.jsonl")).unwrap();
+    assert_eq!(lines.lines().count(), 2);
+    assert_eq!(store.get(376).unwrap().unwrap().status(), InvoiceStatus::Issued);
+    assert!(!std::env::temp_dir().join("factura_store_update.jsonl.tmp").exists());
+}
+
+#[test]
+fn test_last_invoice_number() {
+    let store = empty_store("factura_store_last_number.jsonl");
+    store.save(&invoice(65535, "Super Corp", "5/jan/2025", 960)).unwrap();
+    store.save(&invoice(12, "Super Corp", "6/jan/2025", 100)).unwrap();
+    assert!(store.next_invoice_number().is_err());
+}
+
+#[test]
+fn test_queries() {
+    let store = empty_store("factura_store_query.jsonl");
+    let mut paid = invoice(1, "Super Corp", "2025-01-05", 960);
+    paid.transition(InvoiceStatus::Issued).unwrap();
+    paid.transition(InvoiceStatus::Paid).unwrap();
+    store.save(&paid).unwrap();
+    store.save(&invoice(2, "Super Corp", "20/feb/2025", 100)).unwrap();
+    store.save(&invoice(3, "Mega Corp", "3/mar/2025", 2500)).unwrap();
+
+    let numbers = |query: InvoiceQuery| -> Vec<u16> {
+        store.query(&query).unwrap().iter().map(|i| i.data.invoice_number).collect()
+    };
+    let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+
+    assert_eq!(numbers(InvoiceQuery::new()), vec![1, 2, 3]);
+    assert_eq!(numbers(InvoiceQuery::new().customer("super corp")), vec![1, 2]);
+    assert_eq!(numbers(InvoiceQuery::new().customer("billing@Mega Corp.com")), vec![3]);
+    assert_eq!(numbers(InvoiceQuery::new().issued_between(date(2, 1), date(3, 3))), vec![2, 3]);
+    assert_eq!(numbers(InvoiceQuery::new().issued_before(date(2, 19))), vec![1]);
+    assert_eq!(numbers(InvoiceQuery::new().status(InvoiceStatus::Draft)), vec![2, 3]);
+    assert_eq!(numbers(InvoiceQuery::new().amount_between(500, 1000)), vec![1]);
+    assert_eq!(numbers(InvoiceQuery::new().customer("Super Corp").status(InvoiceStatus::Paid)), vec![1]);
+}
@@ -0,0 +1,70 @@
+use factura::text::{invoice_text, TextFormat};
+use factura::types::RawInvoice;
+use factura::{ExportsText, SimpleInvoice};
+
+mod common;
+
+fn raw_invoice() -> RawInvoice {
+    common::invoice(379)
+        .buyer("Super_Corp")
+        .items(&[common::TRANSPORT_FEE, r#"{ "description": "Loading | unloading", "quantity": 12, "amount": 50, "tax_percent": 7 }"#])
+        .methods(&[
+            r#"{ "type": "bank_transfer", "iban": "DE89370400440532013000", "bic": "COBADEFFXXX",
+                 "account_holder": "Joe's Services", "reference": "Invoice 379" }"#,
+            r#"{ "type": "card_link", "url": "https://pay.example.com/379" }"#,
+        ])
+        .raw()
+}
+
+#[test]
+fn test_plain_text() {
+    let invoice = SimpleInvoice::new(raw_invoice()).get_invoice();
+    let text = invoice_text(&invoice, TextFormat::Plain);
+
+    assert!(text.starts_with("Invoice #379\n============\nIssued on: 5/jan/2025\n"));
+    assert!(text.contains("Super_Corp\nbilling@corp.com\n"));
+    assert!(text.contains(concat!(
+        "Description              Qty  Unit Price  Tax    Amount\n",
+        "-----------------------  ---  ----------  ---  --------\n",
+        "Big truck transport fee    2     400 EUR  19%   952 EUR\n",
+        "Loading | unloading       12      50 EUR   7%   636 EUR\n",
+        "-----------------------  ---  ----------  ---  --------\n",
+        "Total without tax                              1400 EUR\n",
+        "Total Tax Amount                                188 EUR\n",
+        "Total Amount                                   1588 EUR\n",
+        "Amount Due                                     1588 EUR\n",
+    )));
+    assert!(text.contains("Bank transfer:\nAccount holder: Joe's Services\nIBAN: DE89 3704 0044 0532 0130 00\n"));
+    assert!(text.contains("Pay online <https://pay.example.com/379>"));
+    assert!(!text.contains("Paid"));
+}
+
+#[test]
+fn test_markdown() {
+    let invoice = SimpleInvoice::new(raw_invoice()).get_invoice();
+    let text = invoice_text(&invoice, TextFormat::Markdown);
+
+    assert!(text.starts_with("# Invoice \\#379\n\nIssued on: 5/jan/2025  \nPayment due by: 16/jan/2025\n"));
+    assert!(text.contains("## Billed to\n\nSuper\\_Corp  \n"));
+    assert!(text.contains(concat!(
+        "| Description             | Qty | Unit Price | Tax |       Amount |\n",
+        "| :---------------------- | --: | ---------: | --: | -----------: |\n",
+        "| Big truck transport fee |   2 |    400 EUR | 19% |      952 EUR |\n",
+        "| Loading \\| unloading    |  12 |     50 EUR |  7% |      636 EUR |\n",
+        "| **Total without tax**   |     |            |     | **1400 EUR** |\n",
+    )));
+    assert!(text.contains("| **Amount Due**          |     |            |     | **1588 EUR** |\n"));
+    assert!(text.contains("### Card\n\n[Pay online](https://pay.example.com/379)\n"));
+}
+
+#[test]
+fn test_to_text() {
+    SimpleInvoice::new(raw_invoice()).to_text(String::from("invoice_379"), TextFormat::Plain).unwrap();
+    SimpleInvoice::new(raw_invoice()).to_text(String::from("invoice_379"), TextFormat::Markdown).unwrap();
+
+    let plain = std::fs::read_to_string("invoice_379.txt").unwrap();
+    let markdown = std::fs::read_to_string("invoice_379.md").unwrap();
+    assert!(plain.starts_with("Invoice #379\n"));
+    assert!(markdown.starts_with("# Invoice \\#379\n"));
+    assert!(plain.ends_with("Pay online <https://pay.example.com/379>\n"));
+}
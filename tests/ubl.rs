@@ -0,0 +1,85 @@
+#![cfg(feature = "ubl")]
+
+use factura::types::{PaymentMethod, RawInvoice, TaxCategory};
+use factura::ubl::{invoice_xml, parse_invoice};
+use factura::{ExportsUBL, InvoiceReader, SimpleInvoice};
+
+mod common;
+
+fn raw_invoice() -> RawInvoice {
+    common::invoice(376)
+        .seller_fields(common::SELLER_VAT_ID)
+        .buyer("Super Corp & Sons")
+        .extra(&format!(r#"{}, "contact": {{ "name": "Jane Doe", "email": "jane@corp.com" }}"#, common::SHIP_TO_WAREHOUSE))
+        .items(&[
+            common::TRANSPORT_FEE,
+            r#"{ "description": "Customs clearance", "quantity": 1, "amount": 120, "tax_percent": 0, "tax_category": "export" }"#,
+        ])
+        .methods(&[&common::bank_transfer("Invoice 376"), common::PAYPAL])
+        .raw()
+}
+
+#[test]
+fn test_export() {
+    SimpleInvoice::new(raw_invoice()).to_ubl(String::from("invoice_376_ubl")).unwrap();
+    let xml = std::fs::read_to_string("invoice_376_ubl.xml").unwrap();
+
+    assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Invoice xmlns=\"urn:oasis:names:specification:ubl:schema:xsd:Invoice-2\""));
+    assert!(xml.contains("<cbc:CustomizationID>urn:cen.eu:en16931:2017</cbc:CustomizationID>"));
+    assert!(xml.contains("<cbc:IssueDate>2025-01-05</cbc:IssueDate>"));
+    assert!(xml.contains("<cbc:Name>Super Corp &amp; Sons</cbc:Name>"));
+    assert!(xml.contains("<cbc:CompanyID>DE123456789</cbc:CompanyID>"));
+    assert!(xml.contains("<cbc:TaxAmount currencyID=\"EUR\">152.00</cbc:TaxAmount>"));
+    assert!(xml.contains("<cbc:TaxExemptionReasonCode>VATEX-EU-G</cbc:TaxExemptionReasonCode>"));
+    assert!(xml.contains("<cbc:PayableAmount currencyID=\"EUR\">1072.00</cbc:PayableAmount>"));
+    assert!(xml.contains("<cbc:InvoicedQuantity unitCode=\"C62\">2</cbc:InvoicedQuantity>"));
+}
+
+#[test]
+fn test_round_trip() {
+    let invoice = SimpleInvoice::new(raw_invoice()).get_invoice();
+    let raw = parse_invoice(&invoice_xml(&invoice.to_en16931())).unwrap();
+
+    assert_eq!(raw.data.invoice_number, 376);
+    assert_eq!(raw.data.issue_date, "2025-01-05");
+    assert_eq!((raw.from.name.as_str(), raw.from.vat_id.as_deref()), ("Joe's Services", Some("DE123456789")));
+    assert_eq!((raw.from.addr_one.as_str(), raw.from.addr_two.as_str()), ("Leuteritzweg", "13"));
+    assert_eq!((raw.to.name.as_str(), raw.to.country.as_str()), ("Super Corp & Sons", "DE"));
+    assert_eq!(raw.ship_to.as_ref().unwrap().name, "Super Corp Warehouse");
+    assert_eq!(raw.contact.as_ref().unwrap().email.as_deref(), Some("jane@corp.com"));
+
+    let items: Vec<(&str, u8, u32, u8, TaxCategory)> = raw.items.iter()
+        .map(|i| (i.description.as_str(), i.quantity, i.amount, i.tax_percent, i.tax_category))
+        .collect();
+    assert_eq!(items, vec![
+        ("Big truck transport fee", 2, 400, 19, TaxCategory::Standard),
+        ("Customs clearance", 1, 120, 0, TaxCategory::Export),
+    ]);
+
+    assert_eq!(raw.payment.currency, "EUR");
+    assert!(matches!(&raw.payment.methods[0], PaymentMethod::BankTransfer(b)
+        if b.iban == "DE89370400440532013000" && b.bic.as_deref() == Some("COBADEFFXXX") && b.reference.as_deref() == Some("Invoice 376")));
+    assert!(matches!(&raw.payment.methods[1], PaymentMethod::PayPal { account } if account == "billing@supaservices.com"));
+
+    // parsing it again gives the same totals
+    let reparsed = SimpleInvoice::new(raw).get_invoice();
+    assert_eq!(reparsed.total.amount_due, invoice.total.amount_due);
+}
+
+#[test]
+fn test_reader() {
+    let invoice = SimpleInvoice::new(raw_invoice()).get_invoice();
+    let path = std::env::temp_dir().join("factura_ubl_reader.xml");
+    std::fs::write(&path, invoice_xml(&invoice.to_en16931())).unwrap();
+
+    let raw = InvoiceReader::from_ubl(path.to_string_lossy().into_owned()).unwrap().remove(0);
+    assert_eq!(raw.items.len(), 2);
+
+    let error = parse_invoice("<CreditNote/>").err().unwrap();
+    assert_eq!(error.to_string(), "not a UBL invoice");
+    let xml = invoice_xml(&invoice.to_en16931()).replace("<cbc:IssueDate>2025-01-05</cbc:IssueDate>", "");
+    assert_eq!(parse_invoice(&xml).err().unwrap().to_string(), "missing UBL element cbc:IssueDate");
+
+    let xml = invoice_xml(&invoice.to_en16931()).replace(">2</cbc:InvoicedQuantity>", ">2.5</cbc:InvoicedQuantity>");
+    assert_eq!(parse_invoice(&xml).err().unwrap().to_string(), "invalid value for quantity: 2.5");
+}
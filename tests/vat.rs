@@ -0,0 +1,97 @@
+use chrono::NaiveDate;
+use factura::types::{InvoiceStatus, ParsedInvoice, TaxCategory};
+use factura::vat::{VatGrouping, VatReport};
+use factura::ExportsPDF;
+
+mod common;
+
+fn invoice(number: u16, country: &str, issue_date: &str, items: &[&str]) -> ParsedInvoice {
+    common::invoice(number)
+        .buyer_country(country)
+        .items(items)
+        .issued(issue_date)
+        .due("2025-12-31")
+        .history(r#"[ { "status": "issued", "at": "2025-01-01T10:00:00Z" } ]"#)
+        .parsed()
+}
+
+const STANDARD: &str = r#"{ "description": "Transport fee", "quantity": 2, "amount": 100, "tax_percent": 19 }"#;
+const REDUCED: &str = r#"{ "description": "Books", "quantity": 1, "amount": 50, "tax_percent": 7 }"#;
+const REVERSE_CHARGE: &str =
+    r#"{ "description": "Consulting", "quantity": 1, "amount": 300, "tax_percent": 0, "tax_category": "reverse_charge" }"#;
+
+/// Invoices of the first quarter of 2025, and a few that don't belong in its VAT return
+fn invoices() -> Vec<ParsedInvoice> {
+    let mut void = invoice(5, "Germany", "2025-02-01", &[STANDARD]);
+    void.transition(InvoiceStatus::Void).unwrap();
+    let mut draft = invoice(6, "Germany", "2025-02-01", &[STANDARD]);
+    draft.data.history.clear();
+    vec![
+        invoice(1, "Germany", "2025-01-15", &[STANDARD, REDUCED]),
+        invoice(2, "Deutschland", "15/mar/2025", &[STANDARD]),
+        invoice(3, "Austria", "2025-02-10", &[REVERSE_CHARGE]),
+        invoice(4, "Germany", "2025-04-01", &[STANDARD]),
+        void,
+        draft,
+        invoice(7, "Germany", "last week", &[STANDARD]),
+    ]
+}
+
+fn first_quarter() -> VatReport {
+    VatReport::new(&invoices(), NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 3, 31).unwrap())
+}
+
+#[test]
+fn test_categories() {
+    let invoice = invoice(1, "Germany", "2025-01-15", &[STANDARD, REVERSE_CHARGE]);
+    assert_eq!(invoice.items[0].tax_category, TaxCategory::Standard);
+    assert_eq!(invoice.items[1].tax_category, TaxCategory::ReverseCharge);
+    assert_eq!(TaxCategory::for_rate(0), TaxCategory::ZeroRated);
+    assert_eq!(TaxCategory::ReverseCharge.code(), "AE");
+}
+
+#[test]
+fn test_report_lines() {
+    let report = first_quarter();
+    assert_eq!(report.invoices, vec![1, 2, 3]);
+    assert_eq!(report.undated, vec![7]);
+
+    let lines: Vec<(u8, TaxCategory, &str, u64, u64)> = report.lines.iter()
+        .map(|l| (l.tax_percent, l.category, l.country.as_str(), l.net, l.tax))
+        .collect();
+    assert_eq!(lines, vec![
+        (0, TaxCategory::ReverseCharge, "AT", 300, 0),
+        (7, TaxCategory::Standard, "DE", 50, 3),
+        (19, TaxCategory::Standard, "DE", 400, 76),
+    ]);
+}
+
+#[test]
+fn test_summaries() {
+    let report = first_quarter();
+    let groups = |by| report.summary(by).into_iter().map(|s| (s.group, s.net, s.tax)).collect::<Vec<_>>();
+    assert_eq!(groups(VatGrouping::Rate), vec![
+        (String::from("0%"), 300, 0), (String::from("7%"), 50, 3), (String::from("19%"), 400, 76),
+    ]);
+    assert_eq!(groups(VatGrouping::Category), vec![(String::from("AE"), 300, 0), (String::from("S"), 450, 79)]);
+    assert_eq!(groups(VatGrouping::Country), vec![(String::from("AT"), 300, 0), (String::from("DE"), 450, 79)]);
+    assert_eq!(report.totals()[0].gross(), 829);
+}
+
+#[test]
+fn test_exports() {
+    let report = first_quarter();
+    report.to_csv(String::from("vat_2025_q1")).unwrap();
+    let csv = std::fs::read_to_string("vat_2025_q1.csv").unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "currency,tax_percent,category,country,net,tax,gross");
+    assert_eq!(lines[1], "EUR,0,AE,AT,300,0,300");
+    assert_eq!(lines[4], "EUR,,,Total,750,79,829");
+
+    report.to_json(String::from("vat_2025_q1")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string("vat_2025_q1.json").unwrap()).unwrap();
+    assert_eq!(json["from"], "2025-01-01");
+    assert_eq!(json["by_country"][1]["tax"], 79);
+
+    report.to_pdf(String::from("vat_2025_q1")).unwrap();
+}
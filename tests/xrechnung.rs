@@ -0,0 +1,102 @@
+#![cfg(all(feature = "ubl", feature = "cii"))]
+
+use factura::types::RawInvoice;
+use factura::xrechnung::{invoice_xml, is_leitweg_id, Syntax, SPECIFICATION};
+use factura::{ExportsXRechnung, InvoiceReader, SimpleInvoice};
+
+fn raw_invoice(name: &str, seller_extra: &str, buyer_extra: &str) -> RawInvoice {
+    let json = format!(r#"[{{
+        "from": {{ "email": "joe_schower@supaservices.com", "name": "Joe's Services", "addr_one": "Leuteritzweg",
+                  "addr_two": "13", "postal": "22399", "state": "Hamburg", "country": "Germany", "vat_id": "DE123456789"{} }},
+        "to": {{ "email": "rechnung@hamburg.de", "name": "Freie und Hansestadt Hamburg", "addr_one": "Rathausmarkt",
+                "addr_two": "1", "postal": "20095", "state": "Hamburg", "country": "Germany"{} }},
+        "items": [
+            {{ "description": "Big truck transport fee", "quantity": 2, "amount": 400, "tax_percent": 19 }}
+        ],
+        "payment": {{ "currency": "EUR", "methods": [
+            {{ "type": "bank_transfer", "iban": "DE89 3704 0044 0532 0130 00", "bic": "COBADEFFXXX",
+              "account_holder": "Joe's Services", "reference": "Invoice 376" }}
+        ] }},
+        "data": {{ "invoice_number": 376, "due_date": "16/jan/2025", "issue_date": "5/jan/2025" }}
+    }}]"#, seller_extra, buyer_extra);
+    let path = std::env::temp_dir().join(format!("factura_xrechnung_{}.json", name));
+    std::fs::write(&path, json).unwrap();
+    InvoiceReader::from_json(path.to_string_lossy().into_owned()).unwrap().remove(0)
+}
+
+const PHONE: &str = r#", "phone": "+49 40 123456""#;
+const LEITWEG_ID: &str = r#", "buyer_reference": "04011000-12345-03""#;
+
+#[test]
+fn test_ubl() {
+    let invoice = SimpleInvoice::new(raw_invoice("ubl", PHONE, LEITWEG_ID)).get_invoice();
+    let xml = invoice_xml(&invoice, Syntax::Ubl).unwrap();
+
+    assert!(xml.contains(&format!("<cbc:CustomizationID>{}</cbc:CustomizationID>", SPECIFICATION)));
+    assert!(xml.contains("<cbc:BuyerReference>04011000-12345-03</cbc:BuyerReference>"));
+    assert!(xml.contains("<cbc:EndpointID schemeID=\"EM\">rechnung@hamburg.de</cbc:EndpointID>"));
+    assert!(xml.contains("<cbc:Telephone>+49 40 123456</cbc:Telephone>"));
+
+    let raw = factura::ubl::parse_invoice(&xml).unwrap();
+    assert_eq!(raw.to.buyer_reference.as_deref(), Some("04011000-12345-03"));
+    assert_eq!(raw.from.phone.as_deref(), Some("+49 40 123456"));
+    assert_eq!(raw.to.email, "rechnung@hamburg.de");
+}
+
+#[test]
+fn test_cii() {
+    let invoice = SimpleInvoice::new(raw_invoice("cii", PHONE, LEITWEG_ID)).get_invoice();
+    let xml = invoice_xml(&invoice, Syntax::Cii).unwrap();
+
+    assert!(xml.contains(&format!("<ram:ID>{}</ram:ID>", SPECIFICATION)));
+    assert!(xml.contains("<ram:BuyerReference>04011000-12345-03</ram:BuyerReference>"));
+    assert!(xml.contains("<ram:URIID schemeID=\"EM\">joe_schower@supaservices.com</ram:URIID>"));
+
+    let raw = factura::cii::parse_invoice(&xml).unwrap();
+    assert_eq!(raw.to.buyer_reference.as_deref(), Some("04011000-12345-03"));
+    assert_eq!(raw.from.phone.as_deref(), Some("+49 40 123456"));
+}
+
+#[test]
+fn test_rules() {
+    let invoice = SimpleInvoice::new(raw_invoice("missing", "", "")).get_invoice();
+    let rules: Vec<String> = invoice.to_xrechnung().validate_xrechnung().into_iter().map(|v| v.rule).collect();
+    assert_eq!(rules, vec!["BR-DE-6", "BR-DE-15"]);
+
+    // the plain EN 16931 mapping carries the core specification identifier
+    let rules: Vec<String> = invoice.to_en16931().validate_xrechnung().into_iter().map(|v| v.rule).collect();
+    assert!(rules.contains(&String::from("BR-DE-21")));
+
+    let invoice = SimpleInvoice::new(raw_invoice("short_phone", r#", "phone": "12""#, LEITWEG_ID)).get_invoice();
+    let error = invoice_xml(&invoice, Syntax::Ubl).err().unwrap();
+    assert_eq!(error.to_string(), "not a valid XRechnung: [BR-DE-27] telephone number 12 has less than three digits");
+}
+
+#[test]
+fn test_leitweg_id() {
+    assert!(is_leitweg_id("04011000-12345-03"));
+    assert!(is_leitweg_id("991-33333TEST-33"));
+    assert!(is_leitweg_id("04011000-1234512345-06"));
+    assert!(!is_leitweg_id("04011000-12345-67"));
+    assert!(!is_leitweg_id("04011000--03"));
+    assert!(!is_leitweg_id("4-12345-03"));
+
+    let invoice = SimpleInvoice::new(raw_invoice("check_digit", PHONE, r#", "buyer_reference": "04011000-12345-67""#)).get_invoice();
+    let error = invoice_xml(&invoice, Syntax::Ubl).err().unwrap();
+    assert_eq!(error.to_string(), "not a valid XRechnung: [BT-10] 04011000-12345-67 isn't a valid Leitweg-ID");
+
+    // references of companies aren't Leitweg-IDs
+    let invoice = SimpleInvoice::new(raw_invoice("order", PHONE, r#", "buyer_reference": "PO-2025-17""#)).get_invoice();
+    assert!(invoice_xml(&invoice, Syntax::Ubl).is_ok());
+}
+
+#[test]
+fn test_export() {
+    SimpleInvoice::new(raw_invoice("export", PHONE, LEITWEG_ID)).to_xrechnung(String::from("invoice_376_xrechnung"), Syntax::Cii).unwrap();
+    let raw = InvoiceReader::from_cii(String::from("invoice_376_xrechnung.xml")).unwrap().remove(0);
+    assert_eq!(raw.to.buyer_reference.as_deref(), Some("04011000-12345-03"));
+
+    let error = SimpleInvoice::new(raw_invoice("invalid", PHONE, "")).to_xrechnung(String::from("invoice_376_xrechnung_invalid"), Syntax::Ubl);
+    assert!(error.err().unwrap().to_string().contains("[BR-DE-15]"));
+    assert!(!std::path::Path::new("invoice_376_xrechnung_invalid.xml").exists());
+}